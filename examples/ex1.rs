@@ -15,4 +15,6 @@ fn main() {
     t.forward(100.0);
     t.save_svg(&mut File::create("test.svg").unwrap()).unwrap();
     t.save_eps(&mut File::create("test.eps").unwrap()).unwrap();
+    t.save_drawio(&mut File::create("test.drawio").unwrap())
+        .unwrap();
 }
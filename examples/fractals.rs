@@ -0,0 +1,23 @@
+use std::fs::File;
+use turtle_graphics::fractals;
+use turtle_graphics::{Canvas, Position, Turtle};
+
+fn main() {
+    let mut t = Canvas::new();
+    let generators: [fn(&mut Canvas, usize, f32); 5] = [
+        fractals::koch_snowflake,
+        fractals::hilbert,
+        fractals::dragon,
+        fractals::sierpinski_arrowhead,
+        fractals::levy_c,
+    ];
+    for (i, generator) in generators.iter().enumerate() {
+        t.pen_up();
+        t.goto(Position::new(i as f32 * 150.0, 0.0));
+        t.set_heading(0.0);
+        t.pen_down();
+        generator(&mut t, 5, 100.0);
+    }
+    t.save_svg(&mut File::create("fractals.svg").unwrap())
+        .unwrap();
+}
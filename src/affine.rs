@@ -0,0 +1,187 @@
+//! Affine transformations of whole drawings, and composing drawings from
+//! transformed copies of others.
+
+use crate::{Canvas, Degree, Position, Radiant};
+
+/// An affine transformation, mapping `(x, y)` to
+/// `(a * x + c * y + e, b * x + d * y + f)` with the coefficients
+/// `[a, b, c, d, e, f]`, in the order used by SVG and PostScript.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Affine([f32; 6]);
+
+impl Default for Affine {
+    fn default() -> Affine {
+        Affine::identity()
+    }
+}
+
+impl Affine {
+    pub fn new(coefficients: [f32; 6]) -> Affine {
+        Affine(coefficients)
+    }
+
+    pub fn identity() -> Affine {
+        Affine([1.0, 0.0, 0.0, 1.0, 0.0, 0.0])
+    }
+
+    pub fn translate(dx: f32, dy: f32) -> Affine {
+        Affine([1.0, 0.0, 0.0, 1.0, dx, dy])
+    }
+
+    /// Rotates counterclockwise about the origin.
+    pub fn rotate<A: Into<Degree>>(angle: A) -> Affine {
+        let (sin, cos) = Radiant::from(angle.into()).0.sin_cos();
+        Affine([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    /// Scales uniformly about the origin.
+    pub fn scale(factor: f32) -> Affine {
+        Affine::scale_xy(factor, factor)
+    }
+
+    /// Scales about the origin, mirroring for negative factors.
+    pub fn scale_xy(sx: f32, sy: f32) -> Affine {
+        Affine([sx, 0.0, 0.0, sy, 0.0, 0.0])
+    }
+
+    /// Shears, moving `x` by `kx * y` and `y` by `ky * x`.
+    pub fn shear(kx: f32, ky: f32) -> Affine {
+        Affine([1.0, ky, kx, 1.0, 0.0, 0.0])
+    }
+
+    /// Returns the transformation applying `self` first, then `next`.
+    pub fn then(&self, next: &Affine) -> Affine {
+        let [a, b, c, d, e, f] = self.0;
+        let [na, nb, nc, nd, ne, nf] = next.0;
+        Affine([
+            na * a + nc * b,
+            nb * a + nd * b,
+            na * c + nc * d,
+            nb * c + nd * d,
+            na * e + nc * f + ne,
+            nb * e + nd * f + nf,
+        ])
+    }
+
+    /// Returns the inverse transformation, or `None` if the transformation
+    /// collapses the plane.
+    pub fn inverse(&self) -> Option<Affine> {
+        let [a, b, c, d, e, f] = self.0;
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let (ia, ib, ic, id) = (d / det, -b / det, -c / det, a / det);
+        Some(Affine([
+            ia,
+            ib,
+            ic,
+            id,
+            -(ia * e + ic * f),
+            -(ib * e + id * f),
+        ]))
+    }
+
+    pub fn coefficients(&self) -> [f32; 6] {
+        self.0
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.0[0] * self.0[3] - self.0[1] * self.0[2]
+    }
+
+    pub fn apply(&self, pos: Position) -> Position {
+        let [a, b, c, d, e, f] = self.0;
+        Position(a * pos.0 + c * pos.1 + e, b * pos.0 + d * pos.1 + f)
+    }
+
+    /// Returns `true` if the transformation keeps angles, i.e. only
+    /// translates, rotates, mirrors and scales uniformly.
+    pub fn is_similarity(&self) -> bool {
+        let [a, b, c, d, _, _] = self.0;
+        let tolerance = 1e-5 * (a.abs() + b.abs() + c.abs() + d.abs());
+        ((a - d).abs() <= tolerance && (b + c).abs() <= tolerance)
+            || ((a + d).abs() <= tolerance && (b - c).abs() <= tolerance)
+    }
+}
+
+impl Canvas {
+    /// Maps the whole drawing, including the turtle positions and headings,
+    /// by `affine`, e.g. to place a finished sub-drawing within a larger
+    /// one. Fixed pen widths, dash patterns, stamps and text are scaled by
+    /// the square root of the area scale. Under shear or non-uniform
+    /// scaling, where circles don't stay circles, arcs and dots are kept as
+    /// the line segments approximating them, and stamps and text are only
+    /// rotated and scaled.
+    pub fn transform(&mut self, affine: &Affine) {
+        self.checkpoint_full();
+        let [a, b, _, _, _, _] = affine.0;
+        let det = affine.determinant();
+        let mirror = det < 0.0;
+        // Angle of the image of the x axis, after mirroring left to right.
+        let rotation = if mirror { (-b).atan2(-a) } else { b.atan2(a) };
+        let similarity = affine.is_similarity();
+        self.map_geometry(
+            |pos| affine.apply(pos),
+            det.abs().sqrt(),
+            Degree::from(Radiant(rotation)).0,
+            mirror,
+        );
+        if !similarity {
+            for path in self.paths.iter_mut() {
+                path.arcs.clear();
+                path.dot = None;
+            }
+        }
+    }
+
+    /// Adds the paths of `other`, moved by `offset`, on top of this drawing,
+    /// e.g. to tile a motif. See `append_transformed`.
+    pub fn append(&mut self, other: &Canvas, offset: Position) {
+        self.append_transformed(other, &Affine::translate(offset.0, offset.1));
+    }
+
+    /// Adds the paths of `other`, mapped by `affine` like `transform` does,
+    /// on top of this drawing. They keep their pens and go to the layers of
+    /// the same names, which are created as needed. The turtle state, and
+    /// settings like the background, aren't affected.
+    pub fn append_transformed(&mut self, other: &Canvas, affine: &Affine) {
+        self.checkpoint();
+        let mut other = other.clone();
+        other.set_undo_depth(0);
+        other.transform(affine);
+        let layers: Vec<usize> = other
+            .layers
+            .iter()
+            .map(|name| match self.layers.iter().position(|l| l == name) {
+                Some(index) => index,
+                None => {
+                    self.layers.push(name.clone());
+                    self.layer_order.push(self.layers.len() - 1);
+                    self.layers.len() - 1
+                }
+            })
+            .collect();
+        let shapes: Vec<usize> = other
+            .shapes
+            .iter()
+            .map(|shape| match self.shapes.iter().position(|s| s == shape) {
+                Some(index) => index,
+                None => {
+                    self.shapes.push(shape.clone());
+                    self.shapes.len() - 1
+                }
+            })
+            .collect();
+        for mut path in other.paths {
+            if path.points.len() < 2 && path.fill.is_none() && path.label.is_none() {
+                continue;
+            }
+            path.layer = layers[path.layer];
+            if let Some(ref mut stamp) = path.stamp {
+                stamp.shape = shapes[stamp.shape];
+            }
+            self.add_path(path);
+        }
+    }
+}
@@ -0,0 +1,255 @@
+//! Timing the commands of a turtle program, for animated playback.
+//!
+//! An `Animator` passes commands on to another turtle and records when each
+//! starts and how long it takes, either derived from a drawing speed or
+//! from the wall clock. Exporters replay this timeline up to the time of
+//! each frame with `replay_until`.
+
+use crate::recorder::Command;
+use crate::{Degree, Distance, Position, Radiant, Turtle};
+use std::time::Instant;
+
+/// A command with its start time and duration, in seconds.
+#[derive(Copy, Clone, Debug)]
+pub struct TimedCommand {
+    pub start: f32,
+    pub duration: f32,
+    pub command: Command,
+}
+
+impl TimedCommand {
+    pub fn end(&self) -> f32 {
+        self.start + self.duration
+    }
+}
+
+/// A turtle which records a timeline of the commands it passes on to
+/// another turtle.
+pub struct Animator<T: Turtle> {
+    turtle: T,
+    timeline: Vec<TimedCommand>,
+    time: f32,
+    /// Distance per second.
+    speed: f32,
+    /// Degree per second.
+    turn_speed: f32,
+    /// Start of wall clock timing, if used.
+    clock: Option<Instant>,
+}
+
+impl<T: Turtle> Animator<T> {
+    /// Creates an animator moving 100 units and turning 360 degree per
+    /// second.
+    pub fn new(turtle: T) -> Animator<T> {
+        Animator {
+            turtle,
+            timeline: Vec::new(),
+            time: 0.0,
+            speed: 100.0,
+            turn_speed: 360.0,
+            clock: None,
+        }
+    }
+
+    /// Creates an animator which timestamps commands with the wall clock
+    /// time since its creation, e.g. to capture interactive sessions.
+    /// Commands take no time themselves; speeds and pauses are ignored.
+    pub fn with_wall_clock(turtle: T) -> Animator<T> {
+        Animator {
+            clock: Some(Instant::now()),
+            ..Animator::new(turtle)
+        }
+    }
+
+    /// Sets the distance moved per second. Moves are instantaneous for an
+    /// infinite speed, or one of zero or less.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Sets the degree turned per second, see `set_speed`.
+    pub fn set_turn_speed(&mut self, speed: f32) {
+        self.turn_speed = speed;
+    }
+
+    /// Waits `seconds` before the next command.
+    pub fn pause(&mut self, seconds: f32) {
+        if seconds > 0.0 {
+            self.time += seconds;
+        }
+    }
+
+    /// Returns the timed commands, in order.
+    pub fn timeline(&self) -> &[TimedCommand] {
+        &self.timeline
+    }
+
+    /// Returns the time at which the last command ends, including pauses.
+    pub fn duration(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns the turtle the commands are passed to.
+    pub fn turtle(&mut self) -> &mut T {
+        &mut self.turtle
+    }
+
+    /// Returns the turtle and the timeline.
+    pub fn into_inner(self) -> (T, Vec<TimedCommand>) {
+        (self.turtle, self.timeline)
+    }
+
+    /// Performs the commands of the timeline started by `time` with
+    /// `turtle`. A command in progress is performed partially, so that moves
+    /// and turns advance smoothly from frame to frame.
+    pub fn replay_until<U: Turtle>(&self, time: f32, turtle: &mut U) {
+        replay_until(&self.timeline, time, turtle);
+    }
+
+    /// Appends `command`, taking `amount` divided by `speed` seconds.
+    fn record(&mut self, command: Command, amount: f32, speed: f32) {
+        let duration = match self.clock {
+            Some(clock) => {
+                self.time = self.time.max(clock.elapsed().as_secs_f32());
+                0.0
+            }
+            None if speed > 0.0 => amount.abs() / speed,
+            None => 0.0,
+        };
+        self.timeline.push(TimedCommand {
+            start: self.time,
+            duration,
+            command,
+        });
+        self.time += duration;
+    }
+}
+
+/// Performs the commands of `timeline` started by `time` with `turtle`, the
+/// one in progress partially.
+pub(crate) fn replay_until<U: Turtle>(timeline: &[TimedCommand], time: f32, turtle: &mut U) {
+    for timed in timeline.iter() {
+        if timed.start > time {
+            break;
+        }
+        if timed.end() <= time || timed.duration <= 0.0 {
+            timed.command.apply(turtle);
+            continue;
+        }
+        let f = (time - timed.start) / timed.duration;
+        if f <= 0.0 {
+            break;
+        }
+        match timed.command {
+            Command::Forward(distance) => turtle.forward(distance * f),
+            Command::MoveForward(distance) => turtle.move_forward(distance * f),
+            Command::Rotate(angle) => turtle.rotate(angle * f),
+            Command::Arc { radius, extent } => turtle.arc(radius, extent * f),
+            Command::SetHeading(angle) => {
+                let from = turtle.heading().0;
+                let turn = (angle - from + 180.0).rem_euclid(360.0) - 180.0;
+                turtle.set_heading(from + turn * f);
+            }
+            Command::Goto(pos) => turtle.goto(lerp(turtle.position(), pos, f)),
+            Command::SetX(x) => turtle.set_x(lerp(turtle.position(), Position(x, 0.0), f).0),
+            Command::SetY(y) => turtle.set_y(lerp(turtle.position(), Position(0.0, y), f).1),
+            command => command.apply(turtle),
+        }
+    }
+}
+
+fn lerp(p: Position, q: Position, f: f32) -> Position {
+    Position(p.0 + (q.0 - p.0) * f, p.1 + (q.1 - p.1) * f)
+}
+
+impl<T: Turtle> Turtle for Animator<T> {
+    fn forward<D: Into<Distance>>(&mut self, distance: D) {
+        let distance = distance.into();
+        self.record(Command::Forward(distance.0), distance.0, self.speed);
+        self.turtle.forward(distance);
+    }
+
+    fn move_forward<D: Into<Distance>>(&mut self, distance: D) {
+        let distance = distance.into();
+        self.record(Command::MoveForward(distance.0), distance.0, self.speed);
+        self.turtle.move_forward(distance);
+    }
+
+    fn rotate<A: Into<Degree>>(&mut self, angle: A) {
+        let angle = angle.into();
+        self.record(Command::Rotate(angle.0), angle.0, self.turn_speed);
+        self.turtle.rotate(angle);
+    }
+
+    fn position(&self) -> Position {
+        self.turtle.position()
+    }
+
+    fn set_x(&mut self, x: f32) {
+        let dx = x - self.turtle.position().0;
+        self.record(Command::SetX(x), dx, self.speed);
+        self.turtle.set_x(x);
+    }
+
+    fn set_y(&mut self, y: f32) {
+        let dy = y - self.turtle.position().1;
+        self.record(Command::SetY(y), dy, self.speed);
+        self.turtle.set_y(y);
+    }
+
+    fn heading(&self) -> Degree {
+        self.turtle.heading()
+    }
+
+    fn set_heading<A: Into<Degree>>(&mut self, angle: A) {
+        let angle = angle.into();
+        let turn = (angle.0 - self.turtle.heading().0 + 180.0).rem_euclid(360.0) - 180.0;
+        self.record(Command::SetHeading(angle.0), turn, self.turn_speed);
+        self.turtle.set_heading(angle);
+    }
+
+    fn towards(&self, pos: Position) -> Degree {
+        self.turtle.towards(pos)
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.turtle.is_pen_down()
+    }
+
+    fn pen_down(&mut self) {
+        self.record(Command::PenDown, 0.0, 0.0);
+        self.turtle.pen_down();
+    }
+
+    fn pen_up(&mut self) {
+        self.record(Command::PenUp, 0.0, 0.0);
+        self.turtle.pen_up();
+    }
+
+    fn goto(&mut self, pos: Position) {
+        let distance = self.turtle.distance_to(pos).0;
+        self.record(Command::Goto(pos), distance, self.speed);
+        self.turtle.goto(pos);
+    }
+
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        let (radius, extent) = (radius.into(), extent.into());
+        let length = radius.0 * Radiant::from(extent).0;
+        let command = Command::Arc {
+            radius: radius.0,
+            extent: extent.0,
+        };
+        self.record(command, length, self.speed);
+        self.turtle.arc(radius, extent);
+    }
+
+    fn push(&mut self) {
+        self.record(Command::Push, 0.0, 0.0);
+        self.turtle.push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.record(Command::Pop, 0.0, 0.0);
+        self.turtle.pop()
+    }
+}
@@ -0,0 +1,131 @@
+//! Recognizing circular arcs in drawn lines.
+//!
+//! Circles traced with many short `forward` moves, or paths transformed
+//! after drawing, end up as long runs of tiny segments. Finding the runs
+//! which lie on a circle lets exporters write them as single arcs.
+
+use crate::{ArcSegment, Canvas, Path, Position};
+use std::f32::consts::PI;
+
+impl Canvas {
+    /// Finds runs of at least three segments whose points, and the middles
+    /// of the segments, lie within `tolerance` of a circle, turning the
+    /// same way throughout, and records them as arcs of up to 180 degree.
+    /// Like those drawn by `arc`, they are exported as exact arcs once
+    /// `set_exact_arcs` is enabled. Runs already recorded as arcs, paths
+    /// with varying pen pressure, dots, stamps and text are left alone.
+    ///
+    /// Returns the number of arcs found.
+    pub fn recognize_arcs(&mut self, tolerance: f32) -> usize {
+        self.checkpoint_full();
+        let mut found = 0;
+        for path in self.paths.iter_mut() {
+            if path.pressures.is_empty()
+                && path.dot.is_none()
+                && path.stamp.is_none()
+                && path.label.is_none()
+            {
+                found += recognize_path(path, tolerance.max(0.0));
+            }
+        }
+        found
+    }
+}
+
+fn recognize_path(path: &mut Path, tolerance: f32) -> usize {
+    let points = &path.points;
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i + 3 < points.len() {
+        // Points up to `limit` aren't part of a recorded arc yet.
+        let mut limit = points.len() - 1;
+        if let Some(arc) = path
+            .arcs
+            .iter()
+            .find(|arc| arc.end > i && arc.start <= limit)
+        {
+            if arc.start <= i {
+                i = arc.end;
+                continue;
+            }
+            limit = arc.start;
+        }
+        let mut best = None;
+        let mut j = i + 3;
+        while j <= limit {
+            match fit(&points[i..=j], tolerance) {
+                Some(arc) => best = Some((j, arc)),
+                None => break,
+            }
+            j += 1;
+        }
+        match best {
+            Some((end, (radius, ccw))) => {
+                found.push(ArcSegment {
+                    start: i,
+                    end,
+                    radius,
+                    ccw,
+                });
+                i = end;
+            }
+            None => i += 1,
+        }
+    }
+    let count = found.len();
+    path.arcs.extend(found);
+    path.arcs.sort_by_key(|arc| arc.start);
+    count
+}
+
+/// Returns the radius and direction of the arc through `points`, if they
+/// lie on one of at most 180 degree.
+fn fit(points: &[Position], tolerance: f32) -> Option<(f32, bool)> {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let center = circumcenter(first, points[points.len() / 2], last)?;
+    let radius = distance(center, first);
+    let on_circle = |pos: Position| (distance(center, pos) - radius).abs() <= tolerance;
+
+    let mut ccw = None;
+    for w in points.windows(3) {
+        let cross = (w[1].0 - w[0].0) * (w[2].1 - w[1].1) - (w[1].1 - w[0].1) * (w[2].0 - w[1].0);
+        if cross == 0.0 || *ccw.get_or_insert(cross > 0.0) != (cross > 0.0) {
+            return None;
+        }
+    }
+    let ccw = ccw?;
+    let all_on_circle = points.iter().all(|&pos| on_circle(pos))
+        && points
+            .windows(2)
+            .all(|w| on_circle(Position((w[0].0 + w[1].0) / 2.0, (w[0].1 + w[1].1) / 2.0)));
+    if !all_on_circle {
+        return None;
+    }
+
+    let angle = |pos: Position| (pos.1 - center.1).atan2(pos.0 - center.0);
+    let sweep = (angle(last) - angle(first)) * if ccw { 1.0 } else { -1.0 };
+    if sweep.rem_euclid(2.0 * PI) > PI + 1e-4 {
+        return None;
+    }
+    Some((radius, ccw))
+}
+
+/// Returns the center of the circle through `p`, `q` and `r`, unless they
+/// are collinear.
+fn circumcenter(p: Position, q: Position, r: Position) -> Option<Position> {
+    let (bx, by) = (q.0 - p.0, q.1 - p.1);
+    let (cx, cy) = (r.0 - p.0, r.1 - p.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    Some(Position(
+        p.0 + (cy * b2 - by * c2) / d,
+        p.1 + (bx * c2 - cx * b2) / d,
+    ))
+}
+
+fn distance(p: Position, q: Position) -> f32 {
+    (q.0 - p.0).hypot(q.1 - p.1)
+}
@@ -0,0 +1,274 @@
+//! Driving an AxiDraw live over the EiBotBoard protocol.
+//!
+//! An `AxiDrawTurtle` translates turtle moves into stepper motor commands
+//! for the EiBotBoard (EBB) of AxiDraw machines and sends them as they
+//! happen. Like the `plotter` module, it writes to any `Write`, e.g. the
+//! serial device of the board opened as a file:
+//!
+//! ```text
+//! let port = OpenOptions::new().write(true).open("/dev/ttyACM0")?;
+//! let mut axidraw = AxiDrawTurtle::new(port, AxiDrawConfig::default())?;
+//! ```
+//!
+//! The board acknowledges commands with `OK`, which isn't read here; the
+//! serial driver blocks writes while the command queue of the board is
+//! full. Drawings with many lines benefit from `Canvas::optimize_travel`
+//! and `Canvas::join_paths` before being replayed on the machine.
+
+use crate::tracker::Tracker;
+use crate::{Degree, Distance, Position, Turtle};
+use std::io::{self, Write};
+
+/// Longest duration of a single move command, in seconds. Longer moves are
+/// split, so that speed can change along them.
+const SLICE: f32 = 0.025;
+
+/// Setup of an `AxiDrawTurtle`. Lengths are in turtle units, which are
+/// millimeters by default.
+#[derive(Copy, Clone, Debug)]
+pub struct AxiDrawConfig {
+    /// Motor steps per turtle unit. The default of 80 is one millimeter
+    /// with 16x microstepping.
+    pub steps_per_unit: f32,
+    /// Position of the turtle origin to the right of and below the home
+    /// corner, where the carriage starts.
+    pub origin: (f32, f32),
+    /// Width and height of the area the carriage may move in, from the
+    /// home corner. Moves beyond it are cut short at its border.
+    pub limits: (f32, f32),
+    /// Speed while drawing, in units per second.
+    pub pen_down_speed: f32,
+    /// Speed while moving with the pen raised, in units per second.
+    pub pen_up_speed: f32,
+    /// Acceleration, in units per second squared. Every line starts and
+    /// ends at rest.
+    pub acceleration: f32,
+    /// Time given to the servo for raising or lowering the pen, in
+    /// milliseconds.
+    pub pen_delay: u32,
+}
+
+impl Default for AxiDrawConfig {
+    fn default() -> AxiDrawConfig {
+        AxiDrawConfig {
+            steps_per_unit: 80.0,
+            origin: (100.0, 100.0),
+            // AxiDraw V3, letter and A4 size.
+            limits: (300.0, 218.0),
+            pen_down_speed: 25.0,
+            pen_up_speed: 75.0,
+            acceleration: 200.0,
+            pen_delay: 150,
+        }
+    }
+}
+
+/// A turtle drawing on an AxiDraw as it moves.
+///
+/// The physical pen is only lowered for lines; moves without drawing are
+/// made once the next line starts. Write errors are kept until `finish`,
+/// which raises the pen, returns home and turns off the motors; nothing is
+/// sent after the first one.
+pub struct AxiDrawTurtle<W: Write> {
+    writer: W,
+    config: AxiDrawConfig,
+    tracker: Tracker<()>,
+    /// Carriage position in steps right of and below the home corner.
+    steps: (i64, i64),
+    /// Whether the pen is on the paper.
+    lowered: bool,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> AxiDrawTurtle<W> {
+    /// Enables the motors and raises the pen. The carriage has to be in the
+    /// home corner.
+    pub fn new(writer: W, config: AxiDrawConfig) -> io::Result<AxiDrawTurtle<W>> {
+        let mut axidraw = AxiDrawTurtle {
+            writer,
+            config,
+            tracker: Tracker::new(()),
+            steps: (0, 0),
+            lowered: true,
+            error: None,
+        };
+        axidraw.send(format_args!("EM,1,1\r"));
+        axidraw.raise_pen();
+        match axidraw.error.take() {
+            Some(error) => Err(error),
+            None => Ok(axidraw),
+        }
+    }
+
+    /// Sets the speed while drawing, in units per second.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.config.pen_down_speed = speed;
+    }
+
+    /// Raises the pen, returns to the home corner, turns off the motors and
+    /// returns the writer, or the first write error.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.raise_pen();
+        self.move_steps((0, 0), self.config.pen_up_speed);
+        self.send(format_args!("EM,0,0\r"));
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(self.writer),
+        }
+    }
+
+    /// Sends a command and flushes it to the board.
+    fn send(&mut self, args: std::fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(error) = self
+                .writer
+                .write_fmt(args)
+                .and_then(|_| self.writer.flush())
+            {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    fn raise_pen(&mut self) {
+        if self.lowered {
+            let delay = self.config.pen_delay;
+            self.send(format_args!("SP,1,{}\r", delay));
+            self.lowered = false;
+        }
+    }
+
+    fn lower_pen(&mut self) {
+        if !self.lowered {
+            let delay = self.config.pen_delay;
+            self.send(format_args!("SP,0,{}\r", delay));
+            self.lowered = true;
+        }
+    }
+
+    /// Returns the steps from the home corner to `pos`, within the limits.
+    fn to_steps(&self, pos: Position) -> (i64, i64) {
+        let config = &self.config;
+        let x = (config.origin.0 + pos.0).clamp(0.0, config.limits.0);
+        let y = (config.origin.1 - pos.1).clamp(0.0, config.limits.1);
+        (
+            (x * config.steps_per_unit).round() as i64,
+            (y * config.steps_per_unit).round() as i64,
+        )
+    }
+
+    /// Moves the carriage in a straight line to `target`, accelerating from
+    /// rest up to `speed` and back.
+    fn move_steps(&mut self, target: (i64, i64), speed: f32) {
+        let (dx, dy) = (target.0 - self.steps.0, target.1 - self.steps.1);
+        if dx == 0 && dy == 0 {
+            return;
+        }
+        let length = (dx as f32).hypot(dy as f32) / self.config.steps_per_unit;
+        let acceleration = self.config.acceleration.max(f32::EPSILON);
+        // Trapezoidal speed profile, or a triangle if the line is too short
+        // to reach `speed`.
+        let speed = speed.max(f32::EPSILON).min((acceleration * length).sqrt());
+        let ramp = speed / acceleration;
+        let duration = 2.0 * ramp + (length - speed * ramp) / speed;
+        let travelled = |t: f32| {
+            if t < ramp {
+                acceleration * t * t / 2.0
+            } else if t < duration - ramp {
+                speed * ramp / 2.0 + speed * (t - ramp)
+            } else {
+                let left = duration - t;
+                length - acceleration * left * left / 2.0
+            }
+        };
+
+        let slices = (duration / SLICE).ceil().max(1.0) as usize;
+        let start = self.steps;
+        let mut elapsed_ms = 0;
+        for i in 1..=slices {
+            let t = duration * i as f32 / slices as f32;
+            let fraction = if i == slices {
+                1.0
+            } else {
+                travelled(t) / length
+            };
+            let x = start.0 + (dx as f32 * fraction).round() as i64;
+            let y = start.1 + (dy as f32 * fraction).round() as i64;
+            let (sx, sy) = (x - self.steps.0, y - self.steps.1);
+            if sx == 0 && sy == 0 {
+                // The time of the slice goes to the next one moving.
+                continue;
+            }
+            // Round durations so that their sum stays close to the profile.
+            let ms = ((t * 1000.0).round() as i64 - elapsed_ms).max(1);
+            elapsed_ms += ms;
+            // The motors of the AxiDraw move diagonally.
+            self.send(format_args!("SM,{},{},{}\r", ms, sx + sy, sx - sy));
+            self.steps = (x, y);
+        }
+    }
+
+    /// Draws a line from `from` to `to`.
+    fn line(&mut self, from: Position, to: Position) {
+        let (from, to) = (self.to_steps(from), self.to_steps(to));
+        if self.steps != from {
+            self.raise_pen();
+            self.move_steps(from, self.config.pen_up_speed);
+        }
+        self.lower_pen();
+        self.move_steps(to, self.config.pen_down_speed);
+    }
+}
+
+impl<W: Write> Turtle for AxiDrawTurtle<W> {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        if let Some(from) = self.tracker.forward(distance.into().0) {
+            let to = self.tracker.current().pos;
+            self.line(from.pos, to);
+        }
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        self.tracker.move_forward(distance.into().0);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.rotate(angle.into());
+    }
+
+    fn position(&self) -> Position {
+        self.tracker.current().pos
+    }
+
+    fn heading(&self) -> Degree {
+        self.tracker.current().heading
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.set_heading(angle.into());
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.tracker.current().pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.tracker.state().pendown = true;
+    }
+
+    fn pen_up(&mut self) {
+        self.tracker.state().pendown = false;
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.tracker.goto(pos);
+    }
+
+    fn push(&mut self) {
+        self.tracker.push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.tracker.pop()
+    }
+}
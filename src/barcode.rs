@@ -0,0 +1,657 @@
+//! QR codes and Code 128 barcodes drawn as turtle paths.
+//!
+//! Barcodes are drawn with their top-left corner at the current turtle
+//! position, independent of the heading. Dark modules (or bars) are either
+//! filled regions, or hatched with a continuous zig-zag stroke so that pen
+//! plotters can reproduce them. No quiet zone is drawn.
+
+use crate::{Canvas, Color, Position};
+
+/// How dark modules are rendered.
+#[derive(Copy, Clone, Debug)]
+pub enum ModuleStyle {
+    /// Fill with a solid color.
+    Fill(Color),
+    /// Hatch with parallel strokes `spacing` apart. Use a spacing at most the
+    /// width of the pen to get fully covered modules.
+    Hatch(f32),
+}
+
+/// Draws the axis-aligned rectangle spanned by `top_left` and `bottom_right`.
+fn draw_rect(canvas: &mut Canvas, top_left: Position, bottom_right: Position, style: ModuleStyle) {
+    let (x0, y0, x1, y1) = (top_left.0, top_left.1, bottom_right.0, bottom_right.1);
+    match style {
+        ModuleStyle::Fill(color) => {
+            canvas.add_polygon(
+                vec![
+                    Position(x0, y0),
+                    Position(x1, y0),
+                    Position(x1, y1),
+                    Position(x0, y1),
+                ],
+                color,
+            );
+        }
+        ModuleStyle::Hatch(spacing) => {
+            let (width, height) = ((x1 - x0).abs(), (y1 - y0).abs());
+            // Strokes run parallel to the longer side, so that narrow bars
+            // need only a few strokes.
+            let horizontal = width >= height;
+            let across = if horizontal { height } else { width };
+            let n = (across / spacing.max(f32::EPSILON)).ceil().max(1.0) as usize + 1;
+            let mut points = Vec::with_capacity(2 * n);
+            for i in 0..n {
+                let t = i as f32 / (n - 1) as f32;
+                let (a, b) = if horizontal {
+                    let y = y0 + t * (y1 - y0);
+                    (Position(x0, y), Position(x1, y))
+                } else {
+                    let x = x0 + t * (x1 - x0);
+                    (Position(x, y0), Position(x, y1))
+                };
+                if i % 2 == 0 {
+                    points.push(a);
+                    points.push(b);
+                } else {
+                    points.push(b);
+                    points.push(a);
+                }
+            }
+            canvas.add_polyline(points);
+        }
+    }
+}
+
+/// Calls `f(start, end)` for every run of `true` values in `row`.
+fn foreach_run<F: FnMut(usize, usize)>(row: &[bool], mut f: F) {
+    let mut i = 0;
+    while i < row.len() {
+        if row[i] {
+            let start = i;
+            while i < row.len() && row[i] {
+                i += 1;
+            }
+            f(start, i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Error correction level of a QR code.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EcLevel {
+    /// Recovers ~7% of the codewords.
+    L,
+    /// Recovers ~15% of the codewords.
+    M,
+    /// Recovers ~25% of the codewords.
+    Q,
+    /// Recovers ~30% of the codewords.
+    H,
+}
+
+impl EcLevel {
+    fn ordinal(self) -> usize {
+        match self {
+            EcLevel::L => 0,
+            EcLevel::M => 1,
+            EcLevel::Q => 2,
+            EcLevel::H => 3,
+        }
+    }
+
+    fn format_bits(self) -> u32 {
+        match self {
+            EcLevel::L => 1,
+            EcLevel::M => 0,
+            EcLevel::Q => 3,
+            EcLevel::H => 2,
+        }
+    }
+}
+
+const ECC_CODEWORDS_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+const NUM_ERROR_CORRECTION_BLOCKS: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+/// Number of modules available for data and error correction codewords.
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize, ecl: EcLevel) -> usize {
+    let e = ecl.ordinal();
+    num_raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[e][version] as usize
+            * NUM_ERROR_CORRECTION_BLOCKS[e][version] as usize
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_mul(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11d);
+        z ^= ((y as u32 >> i) & 1) * x as u32;
+    }
+    z as u8
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_mul(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_mul(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &b in data {
+        let factor = b ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor.iter()) {
+            *x ^= gf_mul(y, factor);
+        }
+    }
+    result
+}
+
+/// A QR code symbol, encoding binary data in byte mode.
+pub struct QrCode {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes `data` using the smallest version (1 to 40) which fits at the
+    /// given error correction level. Returns `None` if the data is too long.
+    pub fn encode(data: &[u8], ecl: EcLevel) -> Option<QrCode> {
+        let version = (1..=40).find(|&v| {
+            let count_bits = if v <= 9 { 8 } else { 16 };
+            data.len() < (1 << count_bits)
+                && 4 + count_bits + 8 * data.len() <= num_data_codewords(v, ecl) * 8
+        })?;
+        let count_bits = if version <= 9 { 8 } else { 16 };
+        let capacity = num_data_codewords(version, ecl) * 8;
+
+        let mut bits: Vec<bool> = Vec::with_capacity(capacity);
+        let append = |value: u32, len: usize, bits: &mut Vec<bool>| {
+            for i in (0..len).rev() {
+                bits.push((value >> i) & 1 != 0);
+            }
+        };
+        append(0b0100, 4, &mut bits);
+        append(data.len() as u32, count_bits, &mut bits);
+        for &b in data {
+            append(b as u32, 8, &mut bits);
+        }
+        let terminator = (capacity - bits.len()).min(4);
+        append(0, terminator, &mut bits);
+        let pad_bits = (8 - bits.len() % 8) % 8;
+        append(0, pad_bits, &mut bits);
+        for &pad in [0xec, 0x11].iter().cycle() {
+            if bits.len() >= capacity {
+                break;
+            }
+            append(pad, 8, &mut bits);
+        }
+
+        let codewords: Vec<u8> = bits
+            .chunks(8)
+            .map(|byte| byte.iter().fold(0u8, |acc, &bit| (acc << 1) | bit as u8))
+            .collect();
+
+        let mut qr = QrCode::new(version);
+        let all_codewords = qr.add_ecc_and_interleave(&codewords, ecl);
+        qr.draw_function_patterns(ecl);
+        qr.draw_codewords(&all_codewords);
+
+        // Choose the mask with the lowest penalty.
+        let mut best = (0, i32::MAX);
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(ecl, mask);
+            let penalty = qr.penalty_score();
+            if penalty < best.1 {
+                best = (mask, penalty);
+            }
+            qr.apply_mask(mask);
+        }
+        qr.apply_mask(best.0);
+        qr.draw_format_bits(ecl, best.0);
+        Some(qr)
+    }
+
+    fn new(version: usize) -> QrCode {
+        let size = version * 4 + 17;
+        QrCode {
+            version,
+            size,
+            modules: vec![false; size * size],
+            is_function: vec![false; size * size],
+        }
+    }
+
+    /// The version (1 to 40) of the symbol.
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    /// The width and height of the symbol in modules.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if the module at column `x` and row `y` is dark.
+    pub fn module(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// Draws the symbol with modules of `module_size` points. Horizontally
+    /// adjacent dark modules are merged into a single rectangle.
+    pub fn draw(&self, canvas: &mut Canvas, module_size: f32, style: ModuleStyle) {
+        canvas.checkpoint();
+        let origin = canvas.current_state().pos;
+        for y in 0..self.size {
+            let row = &self.modules[y * self.size..(y + 1) * self.size];
+            let top = origin.1 - y as f32 * module_size;
+            foreach_run(row, |start, end| {
+                draw_rect(
+                    canvas,
+                    Position(origin.0 + start as f32 * module_size, top),
+                    Position(origin.0 + end as f32 * module_size, top - module_size),
+                    style,
+                );
+            });
+        }
+    }
+
+    fn set_function_module(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, ecl: EcLevel) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function_module(6, i, i % 2 == 0);
+            self.set_function_module(i, 6, i % 2 == 0);
+        }
+
+        self.draw_finder_pattern(3, 3);
+        self.draw_finder_pattern(size - 4, 3);
+        self.draw_finder_pattern(3, size - 4);
+
+        let positions = self.alignment_pattern_positions();
+        let n = positions.len();
+        for i in 0..n {
+            for j in 0..n {
+                let overlaps_finder = (i == 0 && (j == 0 || j == n - 1)) || (i == n - 1 && j == 0);
+                if !overlaps_finder {
+                    self.draw_alignment_pattern(positions[i], positions[j]);
+                }
+            }
+        }
+
+        // Reserve the format area, the final bits are drawn after masking.
+        self.draw_format_bits(ecl, 0);
+        self.draw_version();
+    }
+
+    fn draw_finder_pattern(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if xx >= 0 && yy >= 0 && (xx as usize) < self.size && (yy as usize) < self.size {
+                    let dist = dx.abs().max(dy.abs());
+                    self.set_function_module(xx as usize, yy as usize, dist != 2 && dist != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment_pattern(&mut self, x: usize, y: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let dark = dx.abs().max(dy.abs()) != 1;
+                self.set_function_module((x as i32 + dx) as usize, (y as i32 + dy) as usize, dark);
+            }
+        }
+    }
+
+    fn alignment_pattern_positions(&self) -> Vec<usize> {
+        if self.version == 1 {
+            return Vec::new();
+        }
+        let num_align = self.version / 7 + 2;
+        let step = (self.version * 8 + num_align * 3 + 5) / (num_align * 4 - 4) * 2;
+        let mut result: Vec<usize> = (0..num_align - 1)
+            .map(|i| self.size - 7 - i * step)
+            .collect();
+        result.push(6);
+        result.reverse();
+        result
+    }
+
+    fn draw_format_bits(&mut self, ecl: EcLevel, mask: usize) {
+        let data = (ecl.format_bits() << 3) | mask as u32;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        for i in 0..6 {
+            self.set_function_module(8, i, bit(i));
+        }
+        self.set_function_module(8, 7, bit(6));
+        self.set_function_module(8, 8, bit(7));
+        self.set_function_module(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function_module(14 - i, 8, bit(i));
+        }
+
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function_module(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function_module(8, size - 15 + i, bit(i));
+        }
+        self.set_function_module(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let mut rem = self.version as u32;
+        for _ in 0..12 {
+            rem = (rem << 1) ^ ((rem >> 11) * 0x1f25);
+        }
+        let bits = ((self.version as u32) << 12) | rem;
+        for i in 0..18 {
+            let bit = (bits >> i) & 1 != 0;
+            let a = self.size - 11 + i % 3;
+            let b = i / 3;
+            self.set_function_module(a, b, bit);
+            self.set_function_module(b, a, bit);
+        }
+    }
+
+    fn add_ecc_and_interleave(&self, data: &[u8], ecl: EcLevel) -> Vec<u8> {
+        let e = ecl.ordinal();
+        let num_blocks = NUM_ERROR_CORRECTION_BLOCKS[e][self.version] as usize;
+        let block_ecc_len = ECC_CODEWORDS_PER_BLOCK[e][self.version] as usize;
+        let raw_codewords = num_raw_data_modules(self.version) / 8;
+        let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+        let short_block_len = raw_codewords / num_blocks;
+
+        let divisor = reed_solomon_divisor(block_ecc_len);
+        let mut blocks: Vec<Vec<u8>> = Vec::with_capacity(num_blocks);
+        let mut k = 0;
+        for i in 0..num_blocks {
+            let len = short_block_len - block_ecc_len + if i < num_short_blocks { 0 } else { 1 };
+            let mut block = data[k..k + len].to_vec();
+            k += len;
+            let ecc = reed_solomon_remainder(&block, &divisor);
+            if i < num_short_blocks {
+                // Padding, skipped while interleaving.
+                block.push(0);
+            }
+            block.extend(ecc);
+            blocks.push(block);
+        }
+
+        let mut result = Vec::with_capacity(raw_codewords);
+        for i in 0..=short_block_len {
+            for (j, block) in blocks.iter().enumerate() {
+                if i != short_block_len - block_ecc_len || j >= num_short_blocks {
+                    result.push(block[i]);
+                }
+            }
+        }
+        result
+    }
+
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.is_function[y * size + x] && i < data.len() * 8 {
+                        self.modules[y * size + x] = (data[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// XORs the data modules with the given mask pattern. Applying the same
+    /// mask twice undoes it.
+    fn apply_mask(&mut self, mask: usize) {
+        let size = self.size;
+        for y in 0..size {
+            for x in 0..size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                if invert && !self.is_function[y * size + x] {
+                    self.modules[y * size + x] ^= true;
+                }
+            }
+        }
+    }
+
+    fn penalty_score(&self) -> i32 {
+        let size = self.size;
+        let get = |x: usize, y: usize| self.modules[y * size + x];
+        let mut penalty = 0;
+
+        // Runs of five or more modules of the same color, and finder-like
+        // patterns, in rows and columns.
+        for transpose in [false, true].iter() {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| if *transpose { get(a, b) } else { get(b, a) })
+                    .collect();
+                let mut run = 1;
+                for b in 1..=size {
+                    if b < size && line[b] == line[b - 1] {
+                        run += 1;
+                    } else {
+                        if run >= 5 {
+                            penalty += run - 2;
+                        }
+                        run = 1;
+                    }
+                }
+                let light = |i: i32| i < 0 || i >= size as i32 || !line[i as usize];
+                for b in 0..size.saturating_sub(6) {
+                    let pattern = [true, false, true, true, true, false, true];
+                    if pattern.iter().enumerate().all(|(k, &p)| line[b + k] == p) {
+                        let b = b as i32;
+                        let before = (b - 4..b).all(&light);
+                        let after = (b + 7..b + 11).all(&light);
+                        if before || after {
+                            penalty += 40;
+                        }
+                    }
+                }
+            }
+        }
+
+        // 2x2 blocks of the same color.
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let c = get(x, y);
+                if c == get(x + 1, y) && c == get(x, y + 1) && c == get(x + 1, y + 1) {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light modules.
+        let dark = self.modules.iter().filter(|&&m| m).count() as i32;
+        let total = (size * size) as i32;
+        let k = ((dark * 20 - total * 10).abs() + total - 1) / total - 1;
+        penalty += k.max(0) * 10;
+
+        penalty
+    }
+}
+
+/// Bar/space widths of the Code 128 symbols 0 to 106 (stop).
+const CODE128_PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212",
+    "221213", "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221",
+    "223211", "221132", "221231", "213212", "223112", "312131", "311222", "321122", "321221",
+    "312212", "322112", "322211", "212123", "212321", "232121", "111323", "131123", "131321",
+    "112313", "132113", "132311", "211313", "231113", "231311", "112133", "112331", "132131",
+    "113123", "113321", "133121", "313121", "211331", "231131", "213113", "213311", "213131",
+    "311123", "311321", "331121", "312113", "312311", "332111", "314111", "221411", "431111",
+    "111224", "111422", "121124", "121421", "141122", "141221", "112214", "112412", "122114",
+    "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111", "111242",
+    "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311",
+    "113141", "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const CODE128_START_B: usize = 104;
+const CODE128_STOP: usize = 106;
+
+/// A Code 128 barcode using code set B (printable ASCII).
+pub struct Code128 {
+    modules: Vec<bool>,
+}
+
+impl Code128 {
+    /// Encodes `text`. Returns `None` if `text` contains characters outside
+    /// of printable ASCII.
+    pub fn encode(text: &str) -> Option<Code128> {
+        let mut symbols = vec![CODE128_START_B];
+        for c in text.chars() {
+            if !(' '..='\u{7f}').contains(&c) {
+                return None;
+            }
+            symbols.push(c as usize - 32);
+        }
+        let checksum = symbols
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| i.max(1) * s)
+            .sum::<usize>()
+            % 103;
+        symbols.push(checksum);
+        symbols.push(CODE128_STOP);
+
+        let mut modules = Vec::new();
+        for &symbol in symbols.iter() {
+            for (i, width) in CODE128_PATTERNS[symbol].bytes().enumerate() {
+                let bar = i % 2 == 0;
+                for _ in 0..(width - b'0') {
+                    modules.push(bar);
+                }
+            }
+        }
+        Some(Code128 { modules })
+    }
+
+    /// The width of the barcode in modules.
+    pub fn width(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Returns `true` if the module at `x` is part of a bar.
+    pub fn module(&self, x: usize) -> bool {
+        self.modules[x]
+    }
+
+    /// Draws the barcode with a narrow bar width of `module_width` points.
+    pub fn draw(&self, canvas: &mut Canvas, module_width: f32, height: f32, style: ModuleStyle) {
+        canvas.checkpoint();
+        let origin = canvas.current_state().pos;
+        foreach_run(&self.modules, |start, end| {
+            draw_rect(
+                canvas,
+                Position(origin.0 + start as f32 * module_width, origin.1),
+                Position(origin.0 + end as f32 * module_width, origin.1 - height),
+                style,
+            );
+        });
+    }
+}
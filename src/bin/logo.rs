@@ -0,0 +1,98 @@
+//! Interactive Logo interpreter. Reads commands from stdin line by line and
+//! rewrites the SVG file given as argument (default `logo.svg`) after each
+//! command, so that a viewer which reloads it shows the drawing as it grows.
+//!
+//! With `--optimize`, duplicate lines are removed and the remaining ones
+//! joined and reordered for plotting before writing the file. With
+//! `--timings`, the time spent parsing, drawing, optimizing and writing is
+//! printed to stderr after each command.
+
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::time::{Duration, Instant};
+use turtle_graphics::logo::Workspace;
+use turtle_graphics::{Canvas, HeadingMode};
+
+fn main() {
+    let mut filename = None;
+    let (mut optimize, mut timings) = (false, false);
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--optimize" => optimize = true,
+            "--timings" => timings = true,
+            _ if arg.starts_with("--") || filename.is_some() => {
+                eprintln!("usage: logo [--optimize] [--timings] [FILE.svg]");
+                process::exit(2);
+            }
+            _ => filename = Some(arg),
+        }
+    }
+    let filename = filename.unwrap_or_else(|| "logo.svg".to_string());
+    let mut canvas = Canvas::with_heading_mode(HeadingMode::Compass);
+    let mut workspace = Workspace::new();
+    let mut input = String::new();
+
+    let stdin = io::stdin();
+    prompt("? ");
+    for line in stdin.lock().lines() {
+        let line = line.expect("failed to read stdin");
+        input.push_str(&line);
+        input.push('\n');
+        let mut phases = Vec::new();
+        let start = Instant::now();
+        let parsed = workspace.parse(&input);
+        phases.push(("parse", start.elapsed()));
+        let result = parsed.and_then(|program| {
+            let start = Instant::now();
+            let result = workspace.execute(program, &mut canvas);
+            phases.push(("generate", start.elapsed()));
+            result
+        });
+        match result {
+            Err(ref err) if err.is_incomplete() => {
+                prompt("> ");
+                continue;
+            }
+            Err(err) => eprintln!("{}", err),
+            Ok(()) => {}
+        }
+        input.clear();
+
+        let mut output = None;
+        if optimize {
+            let start = Instant::now();
+            let mut optimized = canvas.clone();
+            optimized.set_undo_depth(0);
+            optimized.remove_duplicate_segments(0.0);
+            optimized.join_paths(0.0);
+            optimized.optimize_travel();
+            output = Some(optimized);
+            phases.push(("optimize", start.elapsed()));
+        }
+        let start = Instant::now();
+        let output = output.as_ref().unwrap_or(&canvas);
+        if let Err(err) = File::create(&filename).and_then(|mut file| output.save_svg(&mut file)) {
+            eprintln!("failed to write {}: {}", filename, err);
+        }
+        phases.push(("export", start.elapsed()));
+        if timings {
+            print_timings(&phases);
+        }
+        prompt("? ");
+    }
+}
+
+fn print_timings(phases: &[(&str, Duration)]) {
+    let phases: Vec<String> = phases
+        .iter()
+        .map(|(name, time)| format!("{} {:.3} ms", name, time.as_secs_f64() * 1000.0))
+        .collect();
+    eprintln!("{}", phases.join(", "));
+}
+
+fn prompt(text: &str) {
+    print!("{}", text);
+    io::stdout().flush().unwrap();
+}
@@ -0,0 +1,213 @@
+//! Clipping exports to a rectangle.
+//!
+//! Lines are cut with the Cohen–Sutherland algorithm and filled regions
+//! with Sutherland–Hodgman, so that every export format, not only those
+//! with a view box, leaves out what lies outside.
+
+use crate::{Canvas, Path, Position, Rect};
+
+const INSIDE: u8 = 0;
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const BOTTOM: u8 = 4;
+const TOP: u8 = 8;
+
+impl Canvas {
+    /// Clips exported drawings to `rect`, or turns clipping off for `None`.
+    /// Lines are cut where they cross its border; dots, stamps and text are
+    /// kept if their center lies within it. The drawing itself is not
+    /// changed.
+    pub fn set_export_clip(&mut self, rect: Option<Rect>) {
+        self.export_clip = rect;
+    }
+
+    /// Returns the rectangle exports are clipped to, if any.
+    pub fn export_clip(&self) -> Option<Rect> {
+        self.export_clip
+    }
+
+    /// Removes everything outside of `rect`.
+    pub(crate) fn clip_paths(&mut self, rect: Rect) {
+        let paths = std::mem::take(&mut self.paths);
+        for path in paths {
+            let anchor = match (&path.dot, &path.stamp, &path.label) {
+                (Some((center, _)), _, _) => Some(*center),
+                (_, Some(stamp), _) => Some(stamp.pos),
+                (_, _, Some(label)) => Some(label.pos),
+                _ => None,
+            };
+            if let Some(anchor) = anchor {
+                if rect.contains(anchor) {
+                    self.paths.push(path);
+                }
+            } else if path.fill.is_some() {
+                let points = clip_polygon(&path.points, rect);
+                if points.len() > 2 {
+                    self.paths.push(Path {
+                        points,
+                        arcs: Vec::new(),
+                        ..path
+                    });
+                }
+            } else {
+                self.paths.extend(clip_polyline(path, rect));
+            }
+        }
+        if self.paths.is_empty() {
+            let pos = self.current_state().pos;
+            self.paths.push(self.new_path(vec![pos], None));
+        }
+    }
+}
+
+fn outcode(pos: Position, rect: Rect) -> u8 {
+    let mut code = INSIDE;
+    if pos.0 < rect.x {
+        code |= LEFT;
+    } else if pos.0 > rect.x + rect.width {
+        code |= RIGHT;
+    }
+    if pos.1 < rect.y {
+        code |= BOTTOM;
+    } else if pos.1 > rect.y + rect.height {
+        code |= TOP;
+    }
+    code
+}
+
+/// Returns the part of the segment `p`-`q` within `rect`, if any.
+fn clip_segment(mut p: Position, mut q: Position, rect: Rect) -> Option<(Position, Position)> {
+    let (mut code_p, mut code_q) = (outcode(p, rect), outcode(q, rect));
+    loop {
+        if code_p | code_q == INSIDE {
+            return Some((p, q));
+        }
+        if code_p & code_q != INSIDE {
+            return None;
+        }
+        // Move an end point outside onto the border it lies beyond.
+        let code = if code_p != INSIDE { code_p } else { code_q };
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let pos = if code & TOP != 0 {
+            let y = rect.y + rect.height;
+            Position(p.0 + dx * (y - p.1) / dy, y)
+        } else if code & BOTTOM != 0 {
+            Position(p.0 + dx * (rect.y - p.1) / dy, rect.y)
+        } else if code & RIGHT != 0 {
+            let x = rect.x + rect.width;
+            Position(x, p.1 + dy * (x - p.0) / dx)
+        } else {
+            Position(rect.x, p.1 + dy * (rect.x - p.0) / dx)
+        };
+        if code == code_p {
+            p = pos;
+            code_p = outcode(p, rect);
+        } else {
+            q = pos;
+            code_q = outcode(q, rect);
+        }
+    }
+}
+
+/// Cuts a stroked path into the pieces within `rect`.
+fn clip_polyline(path: Path, rect: Rect) -> Vec<Path> {
+    let mut points = path.points.clone();
+    let mut pressures = path.pressures.clone();
+    if path.closed && points.len() > 2 {
+        points.push(points[0]);
+        if !pressures.is_empty() {
+            pressures.push(pressures[0]);
+        }
+    }
+    if points.iter().all(|&pos| rect.contains(pos)) {
+        return vec![path];
+    }
+    let pressure_at = |i: usize, t: f32| match pressures.get(i + 1) {
+        Some(&next) => pressures[i] + (next - pressures[i]) * t,
+        None => 1.0,
+    };
+
+    let mut pieces: Vec<Path> = Vec::new();
+    let mut piece: Option<Path> = None;
+    for i in 0..points.len().saturating_sub(1) {
+        let (p, q) = (points[i], points[i + 1]);
+        let (a, b) = match clip_segment(p, q, rect) {
+            Some(segment) => segment,
+            None => {
+                pieces.extend(piece.take());
+                continue;
+            }
+        };
+        let length2 = (q.0 - p.0).powi(2) + (q.1 - p.1).powi(2);
+        let param = |pos: Position| {
+            if length2 > 0.0 {
+                ((pos.0 - p.0) * (q.0 - p.0) + (pos.1 - p.1) * (q.1 - p.1)) / length2
+            } else {
+                0.0
+            }
+        };
+        let continues = piece
+            .as_ref()
+            .and_then(|piece| piece.points.last())
+            .is_some_and(|last| last.0 == a.0 && last.1 == a.1);
+        if !continues {
+            pieces.extend(piece.take());
+            let mut start = Path {
+                points: vec![a],
+                pressures: Vec::new(),
+                closed: false,
+                arcs: Vec::new(),
+                ..path.clone()
+            };
+            if !pressures.is_empty() {
+                start.pressures.push(pressure_at(i, param(a)));
+            }
+            piece = Some(start);
+        }
+        let current = piece.as_mut().unwrap();
+        current.points.push(b);
+        if !pressures.is_empty() {
+            current.pressures.push(pressure_at(i, param(b)));
+        }
+    }
+    pieces.extend(piece);
+    pieces
+}
+
+/// Clips a polygon to `rect`, one border after the other.
+fn clip_polygon(points: &[Position], rect: Rect) -> Vec<Position> {
+    let (right, top) = (rect.x + rect.width, rect.y + rect.height);
+    let at_x = |x: f32| {
+        move |p: Position, q: Position| Position(x, p.1 + (q.1 - p.1) * (x - p.0) / (q.0 - p.0))
+    };
+    let at_y = |y: f32| {
+        move |p: Position, q: Position| Position(p.0 + (q.0 - p.0) * (y - p.1) / (q.1 - p.1), y)
+    };
+    let polygon = clip_border(points, |p| p.0 >= rect.x, at_x(rect.x));
+    let polygon = clip_border(&polygon, |p| p.0 <= right, at_x(right));
+    let polygon = clip_border(&polygon, |p| p.1 >= rect.y, at_y(rect.y));
+    clip_border(&polygon, |p| p.1 <= top, at_y(top))
+}
+
+/// Clips a polygon to the side of a border where `inside` holds; `cross`
+/// returns where a segment crosses the border.
+fn clip_border<I, C>(points: &[Position], inside: I, cross: C) -> Vec<Position>
+where
+    I: Fn(Position) -> bool,
+    C: Fn(Position, Position) -> Position,
+{
+    let mut polygon = Vec::new();
+    for (i, &q) in points.iter().enumerate() {
+        let p = points[(i + points.len() - 1) % points.len()];
+        match (inside(p), inside(q)) {
+            (true, true) => polygon.push(q),
+            (true, false) => polygon.push(cross(p, q)),
+            (false, true) => {
+                polygon.push(cross(p, q));
+                polygon.push(q);
+            }
+            (false, false) => {}
+        }
+    }
+    polygon
+}
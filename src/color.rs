@@ -0,0 +1,102 @@
+use std::fmt;
+
+/// An opaque RGB color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    /// Creates a color from hue (in degree), saturation and value (both in
+    /// `0.0..=1.0`).
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let chroma = value * saturation;
+        Color::from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Creates a color from hue (in degree), saturation and lightness (both
+    /// in `0.0..=1.0`).
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        Color::from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    fn from_hue_chroma(hue: f32, chroma: f32, m: f32) -> Color {
+        let h = hue.rem_euclid(360.0) / 60.0;
+        let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (chroma, x, 0.0),
+            1 => (x, chroma, 0.0),
+            2 => (0.0, chroma, x),
+            3 => (0.0, x, chroma),
+            4 => (x, 0.0, chroma),
+            _ => (chroma, 0.0, x),
+        };
+        let to_u8 = |c: f32| ((c + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::rgb(to_u8(r), to_u8(g), to_u8(b))
+    }
+
+    /// Returns hue (in degree, `0.0..360.0`), chroma, minimum and maximum
+    /// component.
+    fn hue_chroma(&self) -> (f32, f32, f32, f32) {
+        let (r, g, b) = self.to_unit_rgb();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let hue = if chroma == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / chroma).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / chroma + 2.0)
+        } else {
+            60.0 * ((r - g) / chroma + 4.0)
+        };
+        (hue, chroma, min, max)
+    }
+
+    /// Returns hue (in degree, `0.0..360.0`), saturation and value.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, chroma, _, max) = self.hue_chroma();
+        let saturation = if max == 0.0 { 0.0 } else { chroma / max };
+        (hue, saturation, max)
+    }
+
+    /// Returns hue (in degree, `0.0..360.0`), saturation and lightness.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, chroma, min, max) = self.hue_chroma();
+        let lightness = (max + min) / 2.0;
+        let saturation = if lightness == 0.0 || lightness == 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Returns the color components scaled to `0.0..=1.0`, as used by
+    /// PostScript's `setrgbcolor`.
+    pub fn to_unit_rgb(&self) -> (f32, f32, f32) {
+        (
+            self.r as f32 / 255.0,
+            self.g as f32 / 255.0,
+            self.b as f32 / 255.0,
+        )
+    }
+}
+
+/// Formats the color as hex triplet (`#rrggbb`), as used by SVG.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
@@ -0,0 +1,157 @@
+//! Several turtles drawing on the same canvas.
+//!
+//! Every turtle has its own position, heading, pen and state stack. The
+//! canvas draws with the selected turtle; a `TurtleCursor` selects its
+//! turtle before every command, so that cursors can be used side by side.
+//! The fill region of `begin_fill`/`end_fill` is shared by all turtles.
+
+use crate::{Canvas, Degree, Distance, Position, Turtle, TurtleState};
+use std::mem;
+
+impl Canvas {
+    /// Adds a turtle in the initial state (at the origin, heading upwards,
+    /// pen down) and returns its id. The turtle created with the canvas has
+    /// id 0.
+    pub fn add_turtle(&mut self) -> usize {
+        self.turtles.push(vec![TurtleState::initial()]);
+        self.turtles.len() - 1
+    }
+
+    /// Returns the number of turtles.
+    pub fn turtle_count(&self) -> usize {
+        self.turtles.len()
+    }
+
+    /// Returns the id of the turtle the canvas currently draws with.
+    pub fn selected_turtle(&self) -> usize {
+        self.active_turtle
+    }
+
+    /// Makes the canvas draw with turtle `id`, continuing at its position
+    /// with its pen.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no turtle `id`.
+    pub fn select_turtle(&mut self, id: usize) {
+        assert!(id < self.turtles.len(), "no turtle {}", id);
+        if id != self.active_turtle {
+            self.swap_turtle(id);
+            self.restyle_current_path();
+        }
+    }
+
+    /// Stores the state of the selected turtle and loads that of turtle `id`.
+    pub(crate) fn swap_turtle(&mut self, id: usize) {
+        mem::swap(&mut self.states, &mut self.turtles[self.active_turtle]);
+        mem::swap(&mut self.states, &mut self.turtles[id]);
+        self.active_turtle = id;
+    }
+
+    /// Returns a cursor drawing with turtle `id` on this canvas.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no turtle `id`.
+    pub fn turtle(&mut self, id: usize) -> TurtleCursor<'_> {
+        assert!(id < self.turtles.len(), "no turtle {}", id);
+        TurtleCursor { canvas: self, id }
+    }
+
+    /// Returns the current state of turtle `id`, selected or not.
+    fn turtle_state(&self, id: usize) -> &TurtleState {
+        if id == self.active_turtle {
+            self.current_state()
+        } else {
+            self.turtles[id].last().unwrap()
+        }
+    }
+}
+
+/// One of several turtles drawing on a canvas, see `Canvas::turtle`.
+pub struct TurtleCursor<'a> {
+    canvas: &'a mut Canvas,
+    id: usize,
+}
+
+impl<'a> TurtleCursor<'a> {
+    /// Returns the id of the turtle.
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Returns the canvas with the turtle selected, e.g. to change its pen.
+    pub fn canvas(&mut self) -> &mut Canvas {
+        self.canvas.select_turtle(self.id);
+        self.canvas
+    }
+}
+
+impl<'a> Turtle for TurtleCursor<'a> {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        self.canvas().forward(distance);
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        self.canvas().move_forward(distance);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        self.canvas().rotate(angle);
+    }
+
+    fn position(&self) -> Position {
+        self.canvas.turtle_state(self.id).pos
+    }
+
+    fn set_x(&mut self, x: f32) {
+        self.canvas().set_x(x);
+    }
+
+    fn set_y(&mut self, y: f32) {
+        self.canvas().set_y(y);
+    }
+
+    fn heading(&self) -> Degree {
+        let angle = self.canvas.turtle_state(self.id).angle;
+        self.canvas.heading_from_angle(angle)
+    }
+
+    fn towards(&self, pos: Position) -> Degree {
+        self.canvas
+            .heading_towards(self.position(), pos)
+            .unwrap_or_else(|| self.heading())
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        self.canvas().set_heading(angle);
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.canvas.turtle_state(self.id).pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.canvas().pen_down();
+    }
+
+    fn pen_up(&mut self) {
+        self.canvas().pen_up();
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.canvas().goto(pos);
+    }
+
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        self.canvas().arc(radius, extent);
+    }
+
+    fn push(&mut self) {
+        self.canvas().push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.canvas().pop()
+    }
+}
@@ -0,0 +1,278 @@
+//! Wrapping drawings around a cylinder, e.g. for printing on mugs or lamp
+//! shades.
+//!
+//! A cylinder maps x coordinates to angles: the strip from the seam to one
+//! circumference right of it is one full turn. When exporting a canvas with
+//! a cylinder set, everything outside of the strip is wrapped into it, and
+//! strokes crossing the seam are split there.
+
+use crate::{Canvas, Degree, Path, Position};
+
+/// A cylinder the drawing is wrapped around.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cylinder {
+    /// x coordinate of the seam.
+    pub seam: f32,
+    /// Width of one full turn.
+    pub circumference: f32,
+}
+
+impl Cylinder {
+    pub fn new(seam: f32, circumference: f32) -> Cylinder {
+        Cylinder {
+            seam,
+            circumference,
+        }
+    }
+
+    /// Returns the angle around the cylinder at `x`, from the seam, in the
+    /// range `0.0..360.0`.
+    pub fn angle(&self, x: f32) -> Degree {
+        Degree(((x - self.seam) / self.circumference * 360.0).rem_euclid(360.0))
+    }
+
+    /// Returns the number of turns between the seam and `x`.
+    fn turn(&self, x: f32) -> f32 {
+        ((x - self.seam) / self.circumference).floor()
+    }
+
+    /// Moves `pos` by `turns` circumferences to the left.
+    fn shift(&self, pos: Position, turns: f32) -> Position {
+        Position(pos.0 - turns * self.circumference, pos.1)
+    }
+}
+
+/// The edges of the strip which meet at the seam.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SeamEdge {
+    /// The seam itself.
+    Left,
+    /// One circumference right of the seam.
+    Right,
+}
+
+/// A stroke which ends at an edge of the strip, without a stroke continuing
+/// it at the other edge.
+#[derive(Copy, Clone, Debug)]
+pub struct SeamMismatch {
+    /// End of the stroke, after wrapping.
+    pub position: Position,
+    pub edge: SeamEdge,
+}
+
+impl Canvas {
+    /// Wraps exported drawings around `cylinder`, or stops wrapping for
+    /// `None`. Use `seam_mismatches` to check that the drawing continues
+    /// seamlessly.
+    pub fn set_cylinder(&mut self, cylinder: Option<Cylinder>) {
+        self.cylinder = cylinder;
+    }
+
+    /// Returns the ends of strokes at the left or right edge of the wrapped
+    /// drawing which aren't continued within `tolerance` at the other edge.
+    /// These show up as visible breaks at the seam when printed. Returns
+    /// nothing if no cylinder is set.
+    pub fn seam_mismatches(&self, tolerance: f32) -> Vec<SeamMismatch> {
+        let cylinder = match self.cylinder {
+            Some(cylinder) => cylinder,
+            None => return Vec::new(),
+        };
+        let mut canvas = self.clone();
+        canvas.wrap_cylinder(cylinder);
+
+        let left = cylinder.seam;
+        let right = cylinder.seam + cylinder.circumference;
+        let mut ends = Vec::new();
+        for path in canvas.paths.iter() {
+            let stroke = path.fill.is_none() && !path.closed && path.points.len() > 1;
+            if stroke {
+                ends.push(path.points[0]);
+                ends.push(*path.points.last().unwrap());
+            }
+        }
+        let at = |pos: &Position, x: f32| (pos.0 - x).abs() <= tolerance;
+        let continued = |pos: &Position, x: f32| {
+            ends.iter()
+                .any(|other| at(other, x) && (other.1 - pos.1).abs() <= tolerance)
+        };
+
+        let mut mismatches = Vec::new();
+        for pos in ends.iter() {
+            if at(pos, left) && !continued(pos, right) {
+                mismatches.push(SeamMismatch {
+                    position: *pos,
+                    edge: SeamEdge::Left,
+                });
+            } else if at(pos, right) && !continued(pos, left) {
+                mismatches.push(SeamMismatch {
+                    position: *pos,
+                    edge: SeamEdge::Right,
+                });
+            }
+        }
+        mismatches
+    }
+
+    /// Moves all geometry into the strip of the first turn of `cylinder`,
+    /// splitting strokes and fills at the seam.
+    pub(crate) fn wrap_cylinder(&mut self, cylinder: Cylinder) {
+        if !(cylinder.circumference > 0.0 && cylinder.circumference.is_finite()) {
+            return;
+        }
+        let paths = std::mem::take(&mut self.paths);
+        for path in paths {
+            wrap_path(path, &cylinder, &mut self.paths);
+        }
+        for state in self.states.iter_mut() {
+            state.pos = cylinder.shift(state.pos, cylinder.turn(state.pos.0));
+        }
+        if let Some(ref mut filling) = self.filling {
+            for pos in filling.vertices.iter_mut() {
+                *pos = cylinder.shift(*pos, cylinder.turn(pos.0));
+            }
+        }
+    }
+}
+
+/// Moves a path, which isn't split, by `turns` circumferences to the left.
+fn shift_path(mut path: Path, cylinder: &Cylinder, turns: f32) -> Path {
+    for pos in path.points.iter_mut() {
+        *pos = cylinder.shift(*pos, turns);
+    }
+    if let Some((center, radius)) = path.dot {
+        path.dot = Some((cylinder.shift(center, turns), radius));
+    }
+    if let Some(ref mut stamp) = path.stamp {
+        stamp.pos = cylinder.shift(stamp.pos, turns);
+    }
+    if let Some(ref mut label) = path.label {
+        label.pos = cylinder.shift(label.pos, turns);
+    }
+    path
+}
+
+fn wrap_path(path: Path, cylinder: &Cylinder, out: &mut Vec<Path>) {
+    // Dots, stamps and labels are moved as a whole.
+    let anchor = match (path.dot, path.stamp, &path.label) {
+        (Some((center, _)), _, _) => Some(center),
+        (_, Some(stamp), _) => Some(stamp.pos),
+        (_, _, Some(label)) => Some(label.pos),
+        _ => None,
+    };
+    if let Some(anchor) = anchor {
+        let turns = cylinder.turn(anchor.0);
+        out.push(shift_path(path, cylinder, turns));
+        return;
+    }
+    let turns: Vec<f32> = path.points.iter().map(|pos| cylinder.turn(pos.0)).collect();
+    match turns.first() {
+        None => {}
+        Some(&first) if turns.iter().all(|&t| t == first) => {
+            out.push(shift_path(path, cylinder, first))
+        }
+        _ if path.fill.is_some() => wrap_fill(path, cylinder, out),
+        _ => wrap_stroke(path, cylinder, out),
+    }
+}
+
+/// Splits a stroke where it crosses the seam, and moves the pieces into the
+/// strip. Arcs of split strokes are exported as line segments.
+fn wrap_stroke(path: Path, cylinder: &Cylinder, out: &mut Vec<Path>) {
+    let (mut points, mut pressures) = (path.points.clone(), path.pressures.clone());
+    if path.closed {
+        points.push(points[0]);
+        if !pressures.is_empty() {
+            pressures.push(pressures[0]);
+        }
+    }
+    let empty = Path {
+        points: Vec::new(),
+        pressures: Vec::new(),
+        arcs: Vec::new(),
+        closed: false,
+        ..path
+    };
+
+    let mut piece = empty.clone();
+    let mut piece_turns = None;
+    for i in 1..points.len() {
+        let (p, q) = (points[i - 1], points[i]);
+        let pressure = |t: f32| pressures.get(i - 1).map(|&a| a + t * (pressures[i] - a));
+
+        // Points where the segment crosses the seam, and its end points.
+        let mut splits = vec![(0.0, p), (1.0, q)];
+        let (min, max) = (p.0.min(q.0), p.0.max(q.0));
+        let mut x = cylinder.seam + (cylinder.turn(min) + 1.0) * cylinder.circumference;
+        while x < max {
+            let t = (x - p.0) / (q.0 - p.0);
+            splits.push((t, Position(x, p.1 + t * (q.1 - p.1))));
+            x += cylinder.circumference;
+        }
+        splits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        for w in splits.windows(2) {
+            let ((ta, a), (tb, b)) = (w[0], w[1]);
+            let turns = cylinder.turn((a.0 + b.0) / 2.0);
+            if piece_turns != Some(turns) {
+                if piece.points.len() > 1 {
+                    out.push(piece);
+                }
+                piece = empty.clone();
+                piece.points.push(cylinder.shift(a, turns));
+                piece.pressures.extend(pressure(ta));
+                piece_turns = Some(turns);
+            }
+            piece.points.push(cylinder.shift(b, turns));
+            piece.pressures.extend(pressure(tb));
+        }
+    }
+    if piece.points.len() > 1 {
+        out.push(piece);
+    }
+}
+
+/// Cuts a filled region into the parts in each turn, and moves them into the
+/// strip.
+fn wrap_fill(path: Path, cylinder: &Cylinder, out: &mut Vec<Path>) {
+    let (min, max) = path
+        .points
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), pos| {
+            (min.min(pos.0), max.max(pos.0))
+        });
+    let mut turns = cylinder.turn(min);
+    while turns <= cylinder.turn(max) {
+        let left = cylinder.seam + turns * cylinder.circumference;
+        let right = left + cylinder.circumference;
+        let points = clip(&path.points, |x| x >= left, left);
+        let points = clip(&points, |x| x <= right, right);
+        if points.len() > 2 {
+            out.push(Path {
+                points: points
+                    .into_iter()
+                    .map(|pos| cylinder.shift(pos, turns))
+                    .collect(),
+                arcs: Vec::new(),
+                ..path.clone()
+            });
+        }
+        turns += 1.0;
+    }
+}
+
+/// Clips a polygon to the side of the vertical line at `x` where `inside`
+/// holds.
+fn clip<F: Fn(f32) -> bool>(points: &[Position], inside: F, x: f32) -> Vec<Position> {
+    let mut clipped = Vec::new();
+    for (i, &p) in points.iter().enumerate() {
+        let q = points[(i + 1) % points.len()];
+        if inside(p.0) {
+            clipped.push(p);
+        }
+        if inside(p.0) != inside(q.0) {
+            let t = (x - p.0) / (q.0 - p.0);
+            clipped.push(Position(x, p.1 + t * (q.1 - p.1)));
+        }
+    }
+    clipped
+}
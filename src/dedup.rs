@@ -0,0 +1,238 @@
+//! Removing segments drawn more than once.
+//!
+//! Naive fractal code and traversals which walk every edge twice draw many
+//! lines repeatedly. A plotter traces each of them again, wearing the pen
+//! and soaking the paper, and every copy adds to the file size.
+
+use crate::{Canvas, Path, Position};
+use std::collections::HashMap;
+
+/// Most cells a segment is walked through, as a multiple of the cell size.
+/// Longer segments are kept in a list checked against every segment.
+const MAX_STEPS: f32 = 4096.0;
+
+/// A kept segment.
+struct Segment {
+    from: Position,
+    to: Position,
+    /// Index of its path, to compare pens and layers.
+    path: usize,
+}
+
+/// Segments bucketed into square cells by the area they cover.
+struct Grid {
+    cell: f32,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    /// Segments too long to bucket.
+    long: Vec<usize>,
+    segments: Vec<Segment>,
+}
+
+impl Grid {
+    fn key(&self, pos: Position) -> (i64, i64) {
+        (
+            (pos.0 / self.cell).floor() as i64,
+            (pos.1 / self.cell).floor() as i64,
+        )
+    }
+
+    /// Adds `segment` to the cells around those it passes through. Cells
+    /// are more than twice as large as the tolerance, so these contain all
+    /// points within tolerance of it.
+    fn insert(&mut self, segment: Segment) {
+        let index = self.segments.len();
+        let (from, to) = (segment.from, segment.to);
+        let steps = (distance(from, to) / self.cell).ceil();
+        if steps.is_nan() || steps > MAX_STEPS {
+            self.long.push(index);
+        } else {
+            let steps = steps as usize;
+            let mut last = None;
+            for k in 0..=steps {
+                let t = if steps == 0 {
+                    0.0
+                } else {
+                    k as f32 / steps as f32
+                };
+                let key = self.key(Position(
+                    from.0 + (to.0 - from.0) * t,
+                    from.1 + (to.1 - from.1) * t,
+                ));
+                if last == Some(key) {
+                    continue;
+                }
+                last = Some(key);
+                for x in key.0.saturating_sub(1)..=key.0.saturating_add(1) {
+                    for y in key.1.saturating_sub(1)..=key.1.saturating_add(1) {
+                        let indices = self.cells.entry((x, y)).or_default();
+                        if indices.last() != Some(&index) {
+                            indices.push(index);
+                        }
+                    }
+                }
+            }
+        }
+        self.segments.push(segment);
+    }
+
+    /// Returns whether both ends of `from`-`to` lie within `tolerance` of a
+    /// kept segment for which `same_style` holds.
+    fn covers<F: Fn(usize) -> bool>(
+        &self,
+        from: Position,
+        to: Position,
+        tolerance: f32,
+        same_style: F,
+    ) -> bool {
+        let nearby = self
+            .cells
+            .get(&self.key(from))
+            .map_or(&[][..], Vec::as_slice);
+        nearby.iter().chain(self.long.iter()).any(|&i| {
+            let segment = &self.segments[i];
+            same_style(segment.path)
+                && distance_to_segment(from, segment) <= tolerance
+                && distance_to_segment(to, segment) <= tolerance
+        })
+    }
+}
+
+impl Canvas {
+    /// Removes line segments which retrace a segment drawn before with the
+    /// same pen on the same layer, in either direction. A segment counts as
+    /// retraced if both of its ends lie within `tolerance` of the earlier
+    /// one, so shorter pieces of a longer line are removed as well, while
+    /// segments overlapping only partially are kept. Paths are split where
+    /// segments are removed. Filled regions, dots, stamps and text are not
+    /// changed, nor is a region currently being filled.
+    ///
+    /// Segments are visited in drawing order, and of segments retracing
+    /// each other the first drawn is kept, so the result is the same every
+    /// time. No randomness is involved.
+    ///
+    /// Returns the number of segments removed.
+    pub fn remove_duplicate_segments(&mut self, tolerance: f32) -> usize {
+        self.checkpoint_full();
+        let tolerance = tolerance.max(0.0);
+        let limit = match self.filling {
+            Some(ref filling) => filling.path_index.min(self.paths.len()),
+            None => self.paths.len(),
+        };
+        let tail = self.paths.split_off(limit);
+        let paths = std::mem::take(&mut self.paths);
+
+        // Cells about as large as a typical segment keep both the number of
+        // segments per cell and of cells per segment small.
+        let mut lengths: Vec<f32> = paths
+            .iter()
+            .filter(|path| path.is_line())
+            .flat_map(|path| path.points.windows(2).map(|w| distance(w[0], w[1])))
+            .filter(|&length| length > 0.0)
+            .collect();
+        let cell = if lengths.is_empty() {
+            1.0
+        } else {
+            let middle = lengths.len() / 2;
+            let (_, median, _) = lengths.select_nth_unstable_by(middle, f32::total_cmp);
+            *median
+        };
+        let mut grid = Grid {
+            cell: cell.max(tolerance * 4.0).max(f32::MIN_POSITIVE),
+            cells: HashMap::new(),
+            long: Vec::new(),
+            segments: Vec::new(),
+        };
+
+        let mut removed = 0;
+        let mut kept: Vec<Path> = Vec::with_capacity(paths.len());
+        for (index, path) in paths.iter().enumerate() {
+            if !path.is_line() || path.points.len() < 2 {
+                kept.push(path.clone());
+                continue;
+            }
+            let mut points = path.points.clone();
+            let mut pressures = path.pressures.clone();
+            if path.closed && points.len() > 2 {
+                points.push(points[0]);
+                if !pressures.is_empty() {
+                    pressures.push(pressures[0]);
+                }
+            }
+            let segments = points.len() - 1;
+            let mut retained = vec![false; segments];
+            for (i, done) in retained.iter_mut().enumerate() {
+                let (from, to) = (points[i], points[i + 1]);
+                let same_style =
+                    |other: usize| paths[other].layer == path.layer && paths[other].pen == path.pen;
+                if grid.covers(from, to, tolerance, same_style) {
+                    removed += 1;
+                } else {
+                    *done = true;
+                    grid.insert(Segment {
+                        from,
+                        to,
+                        path: index,
+                    });
+                }
+            }
+            if retained.iter().all(|&r| r) {
+                kept.push(path.clone());
+                continue;
+            }
+            // Split into runs of retained segments.
+            let mut start = None;
+            for i in 0..=segments {
+                match (start, retained.get(i).copied().unwrap_or(false)) {
+                    (None, true) => start = Some(i),
+                    (Some(s), false) => {
+                        kept.push(Path {
+                            points: points[s..=i].to_vec(),
+                            pressures: if pressures.is_empty() {
+                                Vec::new()
+                            } else {
+                                pressures[s..=i].to_vec()
+                            },
+                            closed: false,
+                            arcs: Vec::new(),
+                            ..path.clone()
+                        });
+                        start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.paths = kept;
+        let shift = self.paths.len();
+        self.paths.extend(tail);
+        if let Some(ref mut filling) = self.filling {
+            filling.path_index = shift;
+        }
+        // Drawing continues at the turtle, which the last path must end at.
+        let pos = self.current_state().pos;
+        let continues = self
+            .paths
+            .last()
+            .and_then(|path| path.points.last())
+            .is_some_and(|last| last.0 == pos.0 && last.1 == pos.1);
+        if !continues {
+            self.paths.push(self.new_path(vec![pos], None));
+        }
+        removed
+    }
+}
+
+fn distance(p: Position, q: Position) -> f32 {
+    ((q.0 - p.0).powi(2) + (q.1 - p.1).powi(2)).sqrt()
+}
+
+fn distance_to_segment(pos: Position, segment: &Segment) -> f32 {
+    let (p, q) = (segment.from, segment.to);
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let length2 = dx * dx + dy * dy;
+    if length2 == 0.0 {
+        return distance(pos, p);
+    }
+    let t = (((pos.0 - p.0) * dx + (pos.1 - p.1) * dy) / length2).clamp(0.0, 1.0);
+    distance(pos, Position(p.0 + dx * t, p.1 + dy * t))
+}
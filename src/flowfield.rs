@@ -0,0 +1,356 @@
+//! Flow portraits: streamlines traced along the edges of a grayscale image.
+//!
+//! The direction field is derived from the image gradients (Sobel), rotated
+//! by 90 degree so that the streamlines follow contours instead of crossing
+//! them. Directions are stored in doubled-angle form, so that opposite
+//! gradients reinforce instead of cancelling each other out when smoothed or
+//! interpolated.
+
+use crate::{Canvas, Position};
+use std::io::{self, Read};
+
+/// A grayscale image with brightness values in `0.0..=1.0`, stored row by
+/// row from the top.
+#[derive(Clone, Debug)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<f32>,
+}
+
+impl GrayImage {
+    /// Creates an image from `pixels`. Returns `None` if the number of
+    /// pixels doesn't match the dimensions.
+    pub fn new(width: usize, height: usize, pixels: Vec<f32>) -> Option<GrayImage> {
+        if width == 0 || height == 0 || pixels.len() != width * height {
+            return None;
+        }
+        Some(GrayImage {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Creates an image by evaluating `f(x, y)` for every pixel.
+    pub fn from_fn<F: FnMut(usize, usize) -> f32>(
+        width: usize,
+        height: usize,
+        mut f: F,
+    ) -> GrayImage {
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(f(x, y));
+            }
+        }
+        GrayImage {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Reads a binary (`P5`) or plain (`P2`) portable graymap.
+    pub fn read_pgm<R: Read>(rd: &mut R) -> io::Result<GrayImage> {
+        let mut data = Vec::new();
+        rd.read_to_end(&mut data)?;
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        // Parses the header fields, skipping whitespace and comments.
+        let mut pos = 0;
+        let mut fields = Vec::new();
+        while fields.len() < 4 {
+            while pos < data.len() && (data[pos].is_ascii_whitespace() || data[pos] == b'#') {
+                if data[pos] == b'#' {
+                    while pos < data.len() && data[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(invalid("truncated PGM header"));
+            }
+            fields.push(String::from_utf8_lossy(&data[start..pos]).into_owned());
+        }
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| invalid("invalid PGM header"))
+        };
+        let (width, height, maxval) = (
+            number(&fields[1])?,
+            number(&fields[2])?,
+            number(&fields[3])?,
+        );
+        if maxval == 0 || maxval > 65535 {
+            return Err(invalid("invalid PGM maximum value"));
+        }
+
+        let pixels: Vec<f32> = match fields[0].as_str() {
+            "P5" => {
+                // Exactly one whitespace character separates header and data.
+                let body = &data[(pos + 1).min(data.len())..];
+                let bytes_per_pixel = if maxval < 256 { 1 } else { 2 };
+                body.chunks(bytes_per_pixel)
+                    .take(width * height)
+                    .map(|c| {
+                        let v = if bytes_per_pixel == 1 {
+                            c[0] as usize
+                        } else {
+                            (c[0] as usize) << 8 | *c.get(1).unwrap_or(&0) as usize
+                        };
+                        v as f32 / maxval as f32
+                    })
+                    .collect()
+            }
+            "P2" => String::from_utf8_lossy(&data[pos..])
+                .split_whitespace()
+                .take(width * height)
+                .map(|v| number(v).map(|v| v as f32 / maxval as f32))
+                .collect::<io::Result<_>>()?,
+            _ => return Err(invalid("not a PGM file")),
+        };
+        GrayImage::new(width, height, pixels).ok_or_else(|| invalid("truncated PGM data"))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the brightness at pixel (`x`, `y`), clamped to the border.
+    pub fn get(&self, x: isize, y: isize) -> f32 {
+        let x = x.max(0).min(self.width as isize - 1) as usize;
+        let y = y.max(0).min(self.height as isize - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Direction field of an image, in pixel coordinates (y downwards).
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    /// Doubled-angle vectors `(m cos 2a, m sin 2a)`.
+    tensors: Vec<(f32, f32)>,
+}
+
+impl FlowField {
+    /// Builds the field from the gradients of `image`, smoothed by a box
+    /// filter of the given `radius` (in pixels).
+    pub fn from_gradients(image: &GrayImage, radius: usize) -> FlowField {
+        let (width, height) = (image.width, image.height);
+        let mut tensors = Vec::with_capacity(width * height);
+        for y in 0..height as isize {
+            for x in 0..width as isize {
+                let p = |dx: isize, dy: isize| image.get(x + dx, y + dy);
+                let gx =
+                    (p(1, -1) + 2.0 * p(1, 0) + p(1, 1)) - (p(-1, -1) + 2.0 * p(-1, 0) + p(-1, 1));
+                let gy =
+                    (p(-1, 1) + 2.0 * p(0, 1) + p(1, 1)) - (p(-1, -1) + 2.0 * p(0, -1) + p(1, -1));
+                // Contour direction is perpendicular to the gradient.
+                let (tx, ty) = (-gy, gx);
+                // Squaring the complex number doubles the angle.
+                tensors.push((tx * tx - ty * ty, 2.0 * tx * ty));
+            }
+        }
+
+        let mut field = FlowField {
+            width,
+            height,
+            tensors,
+        };
+        if radius > 0 {
+            field.blur(radius as isize);
+        }
+        field
+    }
+
+    fn blur(&mut self, radius: isize) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        for &(sx, sy) in [(1, 0), (0, 1)].iter() {
+            let source = self.tensors.clone();
+            for y in 0..height {
+                for x in 0..width {
+                    let mut sum = (0.0, 0.0);
+                    for k in -radius..=radius {
+                        let xx = (x + k * sx).max(0).min(width - 1);
+                        let yy = (y + k * sy).max(0).min(height - 1);
+                        let t = source[(yy * width + xx) as usize];
+                        sum = (sum.0 + t.0, sum.1 + t.1);
+                    }
+                    self.tensors[(y * width + x) as usize] = sum;
+                }
+            }
+        }
+    }
+
+    /// Returns the unit direction and strength of the field at (`x`, `y`),
+    /// bilinearly interpolated. The sign of the direction is arbitrary.
+    pub fn direction(&self, x: f32, y: f32) -> ((f32, f32), f32) {
+        let x = x.max(0.0).min((self.width - 1) as f32);
+        let y = y.max(0.0).min((self.height - 1) as f32);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+        let t = |x: usize, y: usize| self.tensors[y * self.width + x];
+        let lerp =
+            |a: (f32, f32), b: (f32, f32), f: f32| (a.0 + (b.0 - a.0) * f, a.1 + (b.1 - a.1) * f);
+        let (c, s) = lerp(
+            lerp(t(x0, y0), t(x1, y0), fx),
+            lerp(t(x0, y1), t(x1, y1), fx),
+            fy,
+        );
+        let strength = (c * c + s * s).sqrt();
+        let angle = s.atan2(c) / 2.0;
+        ((angle.cos(), angle.sin()), strength)
+    }
+}
+
+/// Parameters for tracing streamlines.
+#[derive(Copy, Clone, Debug)]
+pub struct StreamlineOptions {
+    /// Distance between consecutive points of a streamline, in pixels.
+    pub step: f32,
+    /// Minimum distance between different streamlines, in pixels.
+    pub separation: f32,
+    /// Maximum number of steps in each direction from the seed.
+    pub max_steps: usize,
+    /// Streamlines end where the field is weaker than this.
+    pub min_strength: f32,
+}
+
+impl Default for StreamlineOptions {
+    fn default() -> StreamlineOptions {
+        StreamlineOptions {
+            step: 1.0,
+            separation: 4.0,
+            max_steps: 500,
+            min_strength: 1e-4,
+        }
+    }
+}
+
+/// Traces evenly spaced streamlines through `field`. Seeds are placed on a
+/// regular grid, and a streamline stops when it comes closer than
+/// `separation` to another one. Returns polylines in pixel coordinates.
+pub fn trace_streamlines(field: &FlowField, options: &StreamlineOptions) -> Vec<Vec<(f32, f32)>> {
+    let sep = options.separation.max(options.step).max(f32::EPSILON);
+    let (cols, rows) = (
+        (field.width as f32 / sep).ceil() as usize + 1,
+        (field.height as f32 / sep).ceil() as usize + 1,
+    );
+    // Points of finished streamlines, bucketed by cells of size `sep`.
+    let mut occupied: Vec<Vec<(f32, f32)>> = vec![Vec::new(); cols * rows];
+    let cell_of = |p: (f32, f32)| ((p.0 / sep) as usize, (p.1 / sep) as usize);
+    let is_free = |occupied: &Vec<Vec<(f32, f32)>>, p: (f32, f32)| {
+        let (cx, cy) = cell_of(p);
+        for y in cy.saturating_sub(1)..(cy + 2).min(rows) {
+            for x in cx.saturating_sub(1)..(cx + 2).min(cols) {
+                for q in occupied[y * cols + x].iter() {
+                    let (dx, dy) = (p.0 - q.0, p.1 - q.1);
+                    if dx * dx + dy * dy < sep * sep {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    };
+    let inside = |p: (f32, f32)| {
+        p.0 >= 0.0
+            && p.1 >= 0.0
+            && p.0 <= (field.width - 1) as f32
+            && p.1 <= (field.height - 1) as f32
+    };
+
+    let mut lines = Vec::new();
+    let mut sy = sep / 2.0;
+    while sy < field.height as f32 {
+        let mut sx = sep / 2.0;
+        while sx < field.width as f32 {
+            let seed = (sx, sy);
+            sx += sep;
+            if !inside(seed) || !is_free(&occupied, seed) {
+                continue;
+            }
+
+            let mut halves = Vec::new();
+            for &sign in [1.0f32, -1.0].iter() {
+                let mut half = Vec::new();
+                let mut p = seed;
+                let ((mut dx, mut dy), _) = field.direction(p.0, p.1);
+                dx *= sign;
+                dy *= sign;
+                for _ in 0..options.max_steps {
+                    let ((nx, ny), strength) = field.direction(p.0, p.1);
+                    if strength < options.min_strength {
+                        break;
+                    }
+                    // Keep heading the same way as in the previous step.
+                    let (nx, ny) = if nx * dx + ny * dy < 0.0 {
+                        (-nx, -ny)
+                    } else {
+                        (nx, ny)
+                    };
+                    dx = nx;
+                    dy = ny;
+                    let next = (p.0 + dx * options.step, p.1 + dy * options.step);
+                    if !inside(next) || !is_free(&occupied, next) {
+                        break;
+                    }
+                    half.push(next);
+                    p = next;
+                }
+                halves.push(half);
+            }
+
+            let backward = halves.pop().unwrap();
+            let forward = halves.pop().unwrap();
+            let mut line: Vec<(f32, f32)> = backward.into_iter().rev().collect();
+            line.push(seed);
+            line.extend(forward);
+            if line.len() < 2 {
+                continue;
+            }
+            for &p in line.iter() {
+                let (cx, cy) = cell_of(p);
+                occupied[cy.min(rows - 1) * cols + cx.min(cols - 1)].push(p);
+            }
+            lines.push(line);
+        }
+        sy += sep;
+    }
+    lines
+}
+
+impl Canvas {
+    /// Draws a flow portrait of `image`, with its top-left corner at the
+    /// current turtle position and each pixel `pixel_size` points wide.
+    pub fn draw_flow_portrait(
+        &mut self,
+        image: &GrayImage,
+        smoothing: usize,
+        pixel_size: f32,
+        options: &StreamlineOptions,
+    ) {
+        self.checkpoint();
+        let origin = self.current_state().pos;
+        let field = FlowField::from_gradients(image, smoothing);
+        for line in trace_streamlines(&field, options) {
+            let points = line
+                .into_iter()
+                .map(|(x, y)| Position(origin.0 + x * pixel_size, origin.1 - y * pixel_size))
+                .collect();
+            self.add_polyline(points);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! Design single-stroke fonts with turtle programs.
+//!
+//! Every glyph is drawn by a closure onto a fresh `Canvas` with the turtle
+//! starting at the origin of the glyph (left end of the baseline) and heading
+//! upwards. The recorded paths are then packaged either as SVG font or as JSON
+//! glyph atlas.
+
+use crate::{xml_escape, Canvas, Position};
+use std::io::{self, Write};
+
+/// A single glyph.
+pub struct Glyph {
+    ch: char,
+    advance: f32,
+    paths: Vec<Vec<Position>>,
+}
+
+impl Glyph {
+    /// The character this glyph represents.
+    pub fn ch(&self) -> char {
+        self.ch
+    }
+
+    /// Horizontal advance of the glyph in font units.
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    /// Writes the paths of the glyph as SVG path data.
+    fn write_path_data<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        let mut first = true;
+        for path in self.paths.iter() {
+            for (i, pos) in path.iter().enumerate() {
+                if !first {
+                    write!(wr, " ")?;
+                }
+                first = false;
+                let cmd = if i == 0 { 'M' } else { 'L' };
+                write!(wr, "{}{} {}", cmd, pos.0, pos.1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A set of glyphs, all sharing the same font metrics.
+pub struct GlyphSet {
+    family: String,
+    units_per_em: f32,
+    ascent: f32,
+    descent: f32,
+    glyphs: Vec<Glyph>,
+}
+
+impl GlyphSet {
+    /// Creates an empty glyph set. The ascent defaults to `units_per_em` and
+    /// the descent to zero.
+    pub fn new(family: &str, units_per_em: f32) -> GlyphSet {
+        GlyphSet {
+            family: family.to_string(),
+            units_per_em,
+            ascent: units_per_em,
+            descent: 0.0,
+            glyphs: Vec::new(),
+        }
+    }
+
+    /// Sets ascent (above baseline) and descent (below baseline, positive) of
+    /// the font.
+    pub fn set_metrics(&mut self, ascent: f32, descent: f32) {
+        self.ascent = ascent;
+        self.descent = descent;
+    }
+
+    /// Draws the glyph for `ch` by running `draw` on a fresh canvas. A glyph
+    /// which was added before for the same character is replaced.
+    pub fn add_glyph<F: FnOnce(&mut Canvas)>(&mut self, ch: char, advance: f32, draw: F) {
+        let mut canvas = Canvas::new();
+        draw(&mut canvas);
+        let paths = canvas
+            .paths
+            .into_iter()
+            .filter(|path| path.points.len() > 1)
+            .map(|path| path.points)
+            .collect();
+        let glyph = Glyph { ch, advance, paths };
+        match self.glyphs.iter_mut().find(|g| g.ch == ch) {
+            Some(existing) => *existing = glyph,
+            None => self.glyphs.push(glyph),
+        }
+    }
+
+    /// Returns the glyph for `ch`, if any.
+    pub fn glyph(&self, ch: char) -> Option<&Glyph> {
+        self.glyphs.iter().find(|g| g.ch == ch)
+    }
+
+    pub fn glyphs(&self) -> &[Glyph] {
+        &self.glyphs
+    }
+
+    /// Saves the glyph set as SVG font. Like turtle coordinates, SVG font
+    /// coordinates are from bottom to top, so no conversion is required.
+    pub fn save_svg_font<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        let family = xml_escape(&self.family);
+        writeln!(
+            wr,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.1">
+<defs>
+<font id="{}" horiz-adv-x="{}">
+<font-face font-family="{}" units-per-em="{}" ascent="{}" descent="{}" />
+<missing-glyph horiz-adv-x="{}" />"#,
+            family,
+            self.units_per_em,
+            family,
+            self.units_per_em,
+            self.ascent,
+            0.0 - self.descent,
+            self.units_per_em
+        )?;
+
+        for glyph in self.glyphs.iter() {
+            write!(
+                wr,
+                r#"<glyph unicode="{}" horiz-adv-x="{}" d=""#,
+                xml_escape(&glyph.ch.to_string()),
+                glyph.advance
+            )?;
+            glyph.write_path_data(wr)?;
+            writeln!(wr, r#"" />"#)?;
+        }
+
+        writeln!(wr, "</font>\n</defs>\n</svg>")
+    }
+
+    /// Saves the glyph set as JSON glyph atlas of the form
+    /// `{"family": .., "units_per_em": .., "ascent": .., "descent": ..,
+    /// "glyphs": {"A": {"advance": .., "paths": [[[x, y], ..], ..]}, ..}}`.
+    pub fn save_json<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        writeln!(wr, "{{")?;
+        writeln!(wr, r#"  "family": "{}","#, json_escape(&self.family))?;
+        writeln!(wr, r#"  "units_per_em": {},"#, self.units_per_em)?;
+        writeln!(wr, r#"  "ascent": {},"#, self.ascent)?;
+        writeln!(wr, r#"  "descent": {},"#, self.descent)?;
+        writeln!(wr, r#"  "glyphs": {{"#)?;
+        for (i, glyph) in self.glyphs.iter().enumerate() {
+            write!(
+                wr,
+                r#"    "{}": {{"advance": {}, "paths": ["#,
+                json_escape(&glyph.ch.to_string()),
+                glyph.advance
+            )?;
+            for (j, path) in glyph.paths.iter().enumerate() {
+                if j > 0 {
+                    write!(wr, ", ")?;
+                }
+                write!(wr, "[")?;
+                for (k, pos) in path.iter().enumerate() {
+                    if k > 0 {
+                        write!(wr, ", ")?;
+                    }
+                    write!(wr, "[{}, {}]", pos.0, pos.1)?;
+                }
+                write!(wr, "]")?;
+            }
+            let sep = if i + 1 < self.glyphs.len() { "," } else { "" };
+            writeln!(wr, "]}}{}", sep)?;
+        }
+        writeln!(wr, "  }}")?;
+        writeln!(wr, "}}")
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
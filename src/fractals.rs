@@ -0,0 +1,129 @@
+//! Classic fractal curves, drawn with any `Turtle`.
+//!
+//! Each generator starts at the current position and heading of the turtle
+//! and draws with its current pen state. `order` is the depth of recursion
+//! and `size` the extent of the whole figure, so that increasing the order
+//! adds detail without changing the size. The turtle ends at the far end of
+//! the curve with its original heading.
+
+use crate::Turtle;
+
+/// Draws a Koch snowflake with sides of length `size`, turning clockwise.
+/// The turtle ends where it started.
+pub fn koch_snowflake<T: Turtle>(turtle: &mut T, order: usize, size: f32) {
+    for _ in 0..3 {
+        koch_curve(turtle, order, size);
+        turtle.right(120.0);
+    }
+}
+
+/// Draws a Koch curve from the turtle to the point `size` ahead of it,
+/// bulging to the left.
+pub fn koch_curve<T: Turtle>(turtle: &mut T, order: usize, size: f32) {
+    if order == 0 {
+        turtle.forward(size);
+        return;
+    }
+    let size = size / 3.0;
+    koch_curve(turtle, order - 1, size);
+    turtle.left(60.0);
+    koch_curve(turtle, order - 1, size);
+    turtle.right(120.0);
+    koch_curve(turtle, order - 1, size);
+    turtle.left(60.0);
+    koch_curve(turtle, order - 1, size);
+}
+
+/// Draws a Hilbert curve filling a square with sides of length `size`. The
+/// square lies ahead and to the right of the turtle, which ends at the
+/// corner `size` ahead of it. Order 0 draws nothing.
+pub fn hilbert<T: Turtle>(turtle: &mut T, order: usize, size: f32) {
+    if order == 0 {
+        return;
+    }
+    let cells = (1u32 << order.min(31)) as f32;
+    let heading = turtle.heading();
+    hilbert_step(turtle, order, -90.0, size / (cells - 1.0));
+    turtle.set_heading(heading);
+}
+
+/// Draws one level of the Hilbert curve, turning by `angle` first.
+fn hilbert_step<T: Turtle>(turtle: &mut T, order: usize, angle: f32, step: f32) {
+    if order == 0 {
+        return;
+    }
+    turtle.rotate(angle);
+    hilbert_step(turtle, order - 1, -angle, step);
+    turtle.forward(step);
+    turtle.rotate(-angle);
+    hilbert_step(turtle, order - 1, angle, step);
+    turtle.forward(step);
+    hilbert_step(turtle, order - 1, angle, step);
+    turtle.rotate(-angle);
+    turtle.forward(step);
+    hilbert_step(turtle, order - 1, -angle, step);
+    turtle.rotate(angle);
+}
+
+/// Draws a Heighway dragon curve from the turtle to the point `size` ahead
+/// of it.
+pub fn dragon<T: Turtle>(turtle: &mut T, order: usize, size: f32) {
+    dragon_step(turtle, order, size, 1.0);
+}
+
+fn dragon_step<T: Turtle>(turtle: &mut T, order: usize, size: f32, sign: f32) {
+    if order == 0 {
+        turtle.forward(size);
+        return;
+    }
+    let size = size * std::f32::consts::FRAC_1_SQRT_2;
+    turtle.rotate(45.0 * sign);
+    dragon_step(turtle, order - 1, size, 1.0);
+    turtle.rotate(-90.0 * sign);
+    dragon_step(turtle, order - 1, size, -1.0);
+    turtle.rotate(45.0 * sign);
+}
+
+/// Draws a Sierpinski arrowhead curve from the turtle to the point `size`
+/// ahead of it, approximating a Sierpinski triangle on the left.
+pub fn sierpinski_arrowhead<T: Turtle>(turtle: &mut T, order: usize, size: f32) {
+    let heading = turtle.heading();
+    // Odd orders end turned by 60 degree; start turned the other way.
+    let angle = match order % 2 {
+        0 => 60.0,
+        _ => {
+            turtle.rotate(60.0);
+            -60.0
+        }
+    };
+    arrowhead_step(turtle, order, size, angle);
+    turtle.set_heading(heading);
+}
+
+fn arrowhead_step<T: Turtle>(turtle: &mut T, order: usize, size: f32, angle: f32) {
+    if order == 0 {
+        turtle.forward(size);
+        return;
+    }
+    let size = size / 2.0;
+    arrowhead_step(turtle, order - 1, size, -angle);
+    turtle.rotate(angle);
+    arrowhead_step(turtle, order - 1, size, angle);
+    turtle.rotate(angle);
+    arrowhead_step(turtle, order - 1, size, -angle);
+}
+
+/// Draws a Lévy C curve from the turtle to the point `size` ahead of it,
+/// bulging to the left.
+pub fn levy_c<T: Turtle>(turtle: &mut T, order: usize, size: f32) {
+    if order == 0 {
+        turtle.forward(size);
+        return;
+    }
+    let size = size * std::f32::consts::FRAC_1_SQRT_2;
+    turtle.left(45.0);
+    levy_c(turtle, order - 1, size);
+    turtle.right(90.0);
+    levy_c(turtle, order - 1, size);
+    turtle.left(45.0);
+}
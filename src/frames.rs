@@ -0,0 +1,142 @@
+//! Exporting the drawing process as a sequence of SVG frames, e.g. to
+//! assemble a video with `ffmpeg -i frame_%04d.svg`, or as animated PNG.
+//!
+//! Frames are drawn by replaying recorded commands on a copy of a canvas,
+//! which keeps its configuration and background but not its drawing. The
+//! copy draws with the pen the drawing started with; later changes to the
+//! canvas which aren't turtle commands, like pen colors, are not part of
+//! the frames. All frames share the view box of the
+//! complete drawing, so that they line up.
+
+use crate::animate::{self, TimedCommand};
+use crate::recorder::Command;
+use crate::{png, Canvas};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes frames `frame_0001.svg`, ... into `dir`, `fps` per second of the
+/// timeline of an `Animator`, and returns their number. The last frame
+/// shows the complete drawing.
+pub fn save_svg_frames(
+    canvas: &Canvas,
+    timeline: &[TimedCommand],
+    fps: f32,
+    dir: &Path,
+) -> io::Result<usize> {
+    let duration = timeline.iter().map(|timed| timed.end()).fold(0.0, f32::max);
+    let frames = frame_count(duration, fps);
+    let time = |frame: usize| (frame as f32 / fps).min(duration);
+    save_frames(canvas, frames, dir, |frame, canvas| {
+        animate::replay_until(timeline, time(frame), canvas)
+    })
+}
+
+/// Writes the timeline of an `Animator` as animated PNG `width` pixels
+/// wide, with `fps` frames per second, and returns the number of frames.
+/// Unlike GIF, APNG keeps the full colors of e.g. hue-cycling pens. Dash
+/// patterns and text labels aren't rendered.
+pub fn save_apng<W: Write>(
+    canvas: &Canvas,
+    timeline: &[TimedCommand],
+    fps: f32,
+    width: usize,
+    wr: &mut W,
+) -> io::Result<usize> {
+    let duration = timeline.iter().map(|timed| timed.end()).fold(0.0, f32::max);
+    let frames = frame_count(duration, fps);
+    let time = |frame: usize| (frame as f32 / fps).min(duration);
+    let mut images = Vec::with_capacity(frames);
+    render_frames(
+        canvas,
+        frames,
+        |frame, canvas| animate::replay_until(timeline, time(frame), canvas),
+        |_, canvas| {
+            images.push(canvas.render_image(width.max(1)));
+            Ok(())
+        },
+    )?;
+    png::write_apng(wr, &images, 1.0 / fps)?;
+    Ok(frames)
+}
+
+/// Returns the number of frames needed to show `duration` seconds, at
+/// least one.
+fn frame_count(duration: f32, fps: f32) -> usize {
+    if fps > 0.0 && duration.is_finite() {
+        (duration * fps).ceil() as usize + 1
+    } else {
+        1
+    }
+}
+
+/// Writes frames `frame_0001.svg`, ... into `dir`, each performing
+/// `per_frame` more of `commands` than the one before, and returns their
+/// number. The first frame shows the blank canvas, the last one the
+/// complete drawing.
+pub fn save_svg_frames_by_commands(
+    canvas: &Canvas,
+    commands: &[Command],
+    per_frame: usize,
+    dir: &Path,
+) -> io::Result<usize> {
+    let per_frame = per_frame.max(1);
+    let frames = commands.len().div_ceil(per_frame) + 1;
+    save_frames(canvas, frames, dir, |frame, canvas| {
+        let end = (frame * per_frame).min(commands.len());
+        for command in commands[..end].iter() {
+            command.apply(canvas);
+        }
+    })
+}
+
+/// Writes `frames` SVG frames, drawing each on a blank copy of `canvas`
+/// with `draw`, which gets the frame index from 0.
+fn save_frames<F>(canvas: &Canvas, frames: usize, dir: &Path, draw: F) -> io::Result<usize>
+where
+    F: FnMut(usize, &mut Canvas),
+{
+    render_frames(canvas, frames, draw, |frame, canvas| {
+        let path = dir.join(format!("frame_{:04}.svg", frame + 1));
+        let mut file = BufWriter::new(File::create(path)?);
+        canvas.save_svg(&mut file)?;
+        file.flush()
+    })?;
+    Ok(frames)
+}
+
+/// Draws `frames` frames on blank copies of `canvas` with `draw`, and passes
+/// them to `emit`. Both get the frame index from 0.
+fn render_frames<F, E>(canvas: &Canvas, frames: usize, mut draw: F, mut emit: E) -> io::Result<()>
+where
+    F: FnMut(usize, &mut Canvas),
+    E: FnMut(usize, &Canvas) -> io::Result<()>,
+{
+    let mut blank = canvas.clone();
+    let background = blank.background();
+    let pen = match canvas.paths.first() {
+        Some(path) => path.pen.clone(),
+        None => canvas.pen().clone(),
+    };
+    blank.set_undo_depth(0);
+    blank.reset();
+    if let Some(color) = background {
+        blank.set_background(color);
+    }
+    blank.set_pen(pen);
+
+    let mut last = blank.clone();
+    draw(frames - 1, &mut last);
+    let view = match last.export_copy() {
+        Some(copy) => copy.svg_frame(),
+        None => last.svg_frame(),
+    };
+    blank.svg_view = Some(view);
+
+    for frame in 0..frames {
+        let mut canvas = blank.clone();
+        draw(frame, &mut canvas);
+        emit(frame, &canvas)?;
+    }
+    Ok(())
+}
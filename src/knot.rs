@@ -0,0 +1,230 @@
+//! Celtic knotwork generated from a grid specification.
+//!
+//! The knot is laid out on a grid of `cols` x `rows` cells. Strands run
+//! diagonally through the cells and bounce off the border of the grid as well
+//! as off walls placed on edges of the grid. Where two strands cross, one of
+//! them passes under the other; under-passes are drawn by breaking the strand
+//! with a small gap around the crossing. Over and under alternate along every
+//! strand.
+//!
+//! Internally, the grid is subdivided into a lattice with twice the
+//! resolution. Strands visit the lattice points whose coordinates add up to an
+//! odd number; those inside the grid are crossings, those on walls are
+//! bounce points.
+
+use crate::{Canvas, Position};
+use std::collections::HashSet;
+
+type Point = (i32, i32);
+
+pub struct KnotGrid {
+    cols: i32,
+    rows: i32,
+    /// Lattice points covered by internal walls.
+    walls: HashSet<Point>,
+}
+
+impl KnotGrid {
+    /// Creates a grid of `cols` x `rows` cells without internal walls, which
+    /// results in a plain plait.
+    pub fn new(cols: usize, rows: usize) -> KnotGrid {
+        KnotGrid {
+            cols: cols as i32,
+            rows: rows as i32,
+            walls: HashSet::new(),
+        }
+    }
+
+    /// Parses a grid from rows of text, one character per cell:
+    ///
+    /// * `.` – no walls,
+    /// * `_` – wall along the bottom edge of the cell,
+    /// * `|` – wall along the left edge of the cell,
+    /// * `L` – walls along the left and bottom edges.
+    ///
+    /// The first line is the top row. Returns `None` for unknown characters
+    /// or rows of different length.
+    pub fn parse(spec: &str) -> Option<KnotGrid> {
+        let lines: Vec<&str> = spec
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let cols = lines.first()?.chars().count();
+        let mut grid = KnotGrid::new(cols, lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            if line.chars().count() != cols {
+                return None;
+            }
+            let row = lines.len() - 1 - i;
+            for (col, c) in line.chars().enumerate() {
+                let (left, bottom) = match c {
+                    '.' => (false, false),
+                    '_' => (false, true),
+                    '|' => (true, false),
+                    'L' => (true, true),
+                    _ => return None,
+                };
+                if left {
+                    grid.add_vertical_wall(col, row);
+                }
+                if bottom {
+                    grid.add_horizontal_wall(col, row);
+                }
+            }
+        }
+        Some(grid)
+    }
+
+    /// Places a wall on the bottom edge of cell (`col`, `row`). Row 0 is the
+    /// bottom row.
+    pub fn add_horizontal_wall(&mut self, col: usize, row: usize) {
+        self.walls.insert((2 * col as i32 + 1, 2 * row as i32));
+    }
+
+    /// Places a wall on the left edge of cell (`col`, `row`).
+    pub fn add_vertical_wall(&mut self, col: usize, row: usize) {
+        self.walls.insert((2 * col as i32, 2 * row as i32 + 1));
+    }
+
+    fn is_vertical_wall(&self, p: Point) -> bool {
+        p.0 % 2 == 0 && (p.0 == 0 || p.0 == 2 * self.cols || self.walls.contains(&p))
+    }
+
+    fn is_horizontal_wall(&self, p: Point) -> bool {
+        p.1 % 2 == 0 && (p.1 == 0 || p.1 == 2 * self.rows || self.walls.contains(&p))
+    }
+
+    /// Returns `true` if the strand at crossing `p` running in direction
+    /// `dir` passes over the other strand.
+    fn is_over(p: Point, dir: Point) -> bool {
+        (p.0 % 2 == 0) == (dir.0 == dir.1)
+    }
+
+    /// Traces all strands as closed loops of lattice points, together with
+    /// the outgoing direction at every point.
+    fn strands(&self) -> Vec<Vec<(Point, Point)>> {
+        let mut visited: HashSet<(Point, bool)> = HashSet::new();
+        let mut strands = Vec::new();
+
+        for y in 0..=2 * self.rows {
+            for x in 0..=2 * self.cols {
+                if (x + y) % 2 == 0 {
+                    continue;
+                }
+                for &incoming in [(1, 1), (1, -1), (-1, 1), (-1, -1)].iter() {
+                    let start = (x, y);
+                    if !self.contains((x - incoming.0, y - incoming.1)) {
+                        continue;
+                    }
+                    let start_dir = self.bounce(start, incoming);
+                    if visited.contains(&(start, start_dir.0 == start_dir.1)) {
+                        continue;
+                    }
+                    let mut strand = Vec::new();
+                    let (mut p, mut dir) = (start, start_dir);
+                    loop {
+                        visited.insert((p, dir.0 == dir.1));
+                        strand.push((p, dir));
+                        p = (p.0 + dir.0, p.1 + dir.1);
+                        visited.insert((p, dir.0 == dir.1));
+                        dir = self.bounce(p, dir);
+                        if p == start && dir == start_dir {
+                            break;
+                        }
+                    }
+                    strands.push(strand);
+                }
+            }
+        }
+        strands
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        p.0 >= 0 && p.1 >= 0 && p.0 <= 2 * self.cols && p.1 <= 2 * self.rows
+    }
+
+    /// Reflects `dir` at walls through `p`.
+    fn bounce(&self, p: Point, dir: Point) -> Point {
+        let mut dir = dir;
+        if self.is_vertical_wall(p) {
+            dir.0 = -dir.0;
+        }
+        if self.is_horizontal_wall(p) {
+            dir.1 = -dir.1;
+        }
+        dir
+    }
+
+    fn is_crossing(&self, p: Point) -> bool {
+        !self.is_vertical_wall(p) && !self.is_horizontal_wall(p)
+    }
+
+    /// Returns the number of distinct strands of the knot.
+    pub fn strand_count(&self) -> usize {
+        self.strands().len()
+    }
+
+    /// Draws the knot with its bottom-left corner at the current turtle
+    /// position. `gap` is the length (in points) cut from an under-passing
+    /// strand on each side of a crossing.
+    pub fn draw(&self, canvas: &mut Canvas, cell_size: f32, gap: f32) {
+        canvas.checkpoint();
+        let origin = canvas.current_state().pos;
+        let unit = cell_size / 2.0;
+        let to_pos =
+            |p: Point| Position(origin.0 + p.0 as f32 * unit, origin.1 + p.1 as f32 * unit);
+        // Gap along the diagonal, in lattice units per axis.
+        let offset = gap / unit / 2f32.sqrt();
+
+        for strand in self.strands() {
+            let under: Vec<usize> = (0..strand.len())
+                .filter(|&i| {
+                    let (p, dir) = strand[i];
+                    self.is_crossing(p) && !KnotGrid::is_over(p, dir)
+                })
+                .collect();
+
+            if under.is_empty() {
+                let points: Vec<Position> = strand
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| is_corner(&strand, i))
+                    .map(|(_, &(p, _))| to_pos(p))
+                    .collect();
+                canvas.add_closed_polyline(points);
+                continue;
+            }
+
+            // Draw the pieces between consecutive under-passes.
+            for (k, &from) in under.iter().enumerate() {
+                let to = under[(k + 1) % under.len()];
+                let (p, dir) = strand[from];
+                let mut points = vec![Position(
+                    origin.0 + (p.0 as f32 + dir.0 as f32 * offset) * unit,
+                    origin.1 + (p.1 as f32 + dir.1 as f32 * offset) * unit,
+                )];
+                let mut i = (from + 1) % strand.len();
+                while i != to {
+                    if is_corner(&strand, i) {
+                        points.push(to_pos(strand[i].0));
+                    }
+                    i = (i + 1) % strand.len();
+                }
+                let (q, _) = strand[to];
+                let (_, incoming) = strand[(to + strand.len() - 1) % strand.len()];
+                points.push(Position(
+                    origin.0 + (q.0 as f32 - incoming.0 as f32 * offset) * unit,
+                    origin.1 + (q.1 as f32 - incoming.1 as f32 * offset) * unit,
+                ));
+                canvas.add_polyline(points);
+            }
+        }
+    }
+}
+
+/// Returns `true` if the strand changes direction at index `i`.
+fn is_corner(strand: &[(Point, Point)], i: usize) -> bool {
+    let prev = strand[(i + strand.len() - 1) % strand.len()].1;
+    strand[i].1 != prev
+}
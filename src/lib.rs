@@ -1,15 +1,112 @@
+use std::collections::{HashMap, VecDeque};
 use std::f32::consts::PI;
 use std::io::{self, Write};
 use std::ops::{Add, Neg};
 
+pub use crate::affine::Affine;
+pub use crate::animate::{Animator, TimedCommand};
+pub use crate::color::Color;
+pub use crate::cursor::TurtleCursor;
+pub use crate::cylinder::{Cylinder, SeamEdge, SeamMismatch};
+pub use crate::metrics::DrawingStats;
+pub use crate::pen::Pen;
+pub use crate::raster::RasterOptions;
+pub use crate::recorder::{Command, CommandRecorder};
+pub use crate::shape::Shape;
+pub use crate::stepper::Stepper;
+pub use crate::tee::TeeTurtle;
+pub use crate::text::TextAlign;
+pub use crate::trace::TraceTurtle;
+
+mod affine;
+mod animate;
+mod arcs;
+#[cfg(feature = "axidraw")]
+pub mod axidraw;
+pub mod barcode;
+mod clip;
+mod color;
+mod cursor;
+mod cylinder;
+mod dedup;
+#[cfg(feature = "flowfield")]
+pub mod flowfield;
+pub mod font;
+pub mod fractals;
+pub mod frames;
+pub mod knot;
+pub mod logo;
+pub mod lsystem;
+mod metrics;
+mod pen;
+#[cfg(feature = "plotter")]
+pub mod plotter;
+mod png;
+#[cfg(feature = "preview")]
+mod preview;
+#[cfg(feature = "random")]
+pub mod random;
+mod raster;
+mod recorder;
+mod reorder;
+mod resample;
+pub mod rng;
+#[cfg(feature = "serve")]
+pub mod serve;
+mod shape;
+pub mod spirograph;
+mod stepper;
+pub mod stipple;
+pub mod stream;
+mod stroke;
+mod tee;
+mod text;
+mod trace;
+mod tracker;
+pub mod turtle3d;
+mod undo;
+pub mod viewport;
+#[cfg(feature = "voronoi")]
+pub mod voronoi;
+#[cfg(feature = "web")]
+pub mod web;
+
+/// Escapes the characters which are special in XML text and attribute values.
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct Position(f32, f32);
 
 impl Position {
+    pub fn new(x: f32, y: f32) -> Position {
+        Position(x, y)
+    }
+
     pub fn origin() -> Position {
         Position(0.0, 0.0)
     }
 
+    pub fn x(&self) -> f32 {
+        self.0
+    }
+
+    pub fn y(&self) -> f32 {
+        self.1
+    }
+
     pub fn min(&self, other: &Position) -> Position {
         Position(self.0.min(other.0), self.1.min(other.1))
     }
@@ -23,6 +120,104 @@ impl Position {
     }
 }
 
+/// An axis-aligned rectangle in turtle coordinates (y from bottom to top).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    /// Creates a rectangle with its bottom-left corner at (`x`, `y`).
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns `true` if `pos` lies within the rectangle or on its border.
+    pub fn contains(&self, pos: Position) -> bool {
+        pos.0 >= self.x
+            && pos.0 <= self.x + self.width
+            && pos.1 >= self.y
+            && pos.1 <= self.y + self.height
+    }
+}
+
+/// How the radius of a spiral grows, see `Turtle::spiral`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpiralGrowth {
+    /// Archimedean spiral, starting at the center. The radius grows by the
+    /// given distance per turn.
+    Linear(f32),
+    /// Logarithmic spiral. The radius starts at `start` and is multiplied by
+    /// `factor` per turn.
+    Exponential { start: f32, factor: f32 },
+}
+
+impl SpiralGrowth {
+    /// Returns the radius after `turns` turns.
+    pub fn radius(&self, turns: f32) -> f32 {
+        match *self {
+            SpiralGrowth::Linear(growth) => growth * turns,
+            SpiralGrowth::Exponential { start, factor } => start * factor.powf(turns),
+        }
+    }
+}
+
+/// Convention of absolute headings (`heading`, `set_heading`, `towards`).
+/// Both start with 0 pointing upwards (north); relative turns are not
+/// affected.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum HeadingMode {
+    /// Angles increase counterclockwise.
+    #[default]
+    Counterclockwise,
+    /// Angles increase clockwise, like on a compass and in the "logo" mode
+    /// of Python's turtle.
+    Compass,
+}
+
+/// How movements beyond the area limit are handled.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum AreaMode {
+    /// The movement ends at the border.
+    #[default]
+    Clamp,
+    /// The turtle moves on, but lines are only drawn within the area.
+    Clip,
+    /// The movement is not carried out.
+    Reject,
+}
+
+/// Orientation of exported drawings, applied on export without changing
+/// the canvas. Mirroring is applied before rotation.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ExportOrientation {
+    /// Number of counterclockwise quarter turns.
+    pub quarter_turns: i32,
+    /// Mirror left and right.
+    pub mirror_horizontal: bool,
+    /// Mirror top and bottom.
+    pub mirror_vertical: bool,
+}
+
+/// How `goto` moves the turtle.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum TravelMode {
+    /// Jump to the position without drawing, starting a new path.
+    #[default]
+    Jump,
+    /// Move in a straight line, drawing if the pen is down, like `goto` in
+    /// Python and Logo turtles.
+    Draw,
+}
+
 struct Bounds {
     min_max: Option<(Position, Position)>,
 }
@@ -41,7 +236,6 @@ impl Bounds {
         self.min_max = Some(mm);
     }
 
-    #[allow(dead_code)]
     fn is_bounded(&self) -> bool {
         self.min_max.is_some()
     }
@@ -82,36 +276,41 @@ impl Add<Position> for Position {
     }
 }
 
+/// An angle in degree. Plain `f32` angles passed to turtle commands are
+/// taken as degree.
 #[derive(Copy, Clone, Debug)]
 pub struct Degree(pub f32);
 
+/// An angle in radians. Turtle commands accept it wherever they take an
+/// angle, e.g. `t.left(Radiant(PI / 3.0))`, and `Radiant::from` converts
+/// returned angles like `heading()`.
 #[derive(Copy, Clone, Debug)]
 pub struct Radiant(pub f32);
 
-impl Into<Degree> for Radiant {
-    fn into(self) -> Degree {
-        Degree(self.0 * 180.0 / PI)
+impl From<Radiant> for Degree {
+    fn from(rad: Radiant) -> Degree {
+        Degree(rad.0 * 180.0 / PI)
     }
 }
 
-impl Into<Degree> for f32 {
-    fn into(self) -> Degree {
-        Degree(self)
+impl From<f32> for Degree {
+    fn from(deg: f32) -> Degree {
+        Degree(deg)
     }
 }
 
-impl Into<Radiant> for Degree {
-    fn into(self) -> Radiant {
-        Radiant(self.0 * PI / 180.0)
+impl From<Degree> for Radiant {
+    fn from(deg: Degree) -> Radiant {
+        Radiant(deg.0 * PI / 180.0)
     }
 }
 
 #[derive(Copy, Clone, Debug)]
-pub struct Distance(f32);
+pub struct Distance(pub f32);
 
-impl Into<Distance> for f32 {
-    fn into(self) -> Distance {
-        Distance(self)
+impl From<f32> for Distance {
+    fn from(dist: f32) -> Distance {
+        Distance(dist)
     }
 }
 
@@ -122,6 +321,52 @@ impl Neg for Distance {
     }
 }
 
+impl Degree {
+    /// Returns the same angle in the range `0.0..360.0`.
+    pub fn normalized(self) -> Degree {
+        let angle = self.0.rem_euclid(360.0);
+        // Tiny negative angles round up to 360.
+        Degree(if angle == 360.0 { 0.0 } else { angle })
+    }
+
+    /// Returns the sine and cosine of the angle. Multiples of 90 degree
+    /// give exact results, so that axis-aligned movements don't drift.
+    pub fn sin_cos(self) -> (f32, f32) {
+        match self.normalized().0 {
+            0.0 => (0.0, 1.0),
+            90.0 => (1.0, 0.0),
+            180.0 => (0.0, -1.0),
+            270.0 => (-1.0, 0.0),
+            _ => Radiant::from(self).0.sin_cos(),
+        }
+    }
+
+    /// Returns the angle limited to `min..=max`. NaN becomes `min`.
+    pub fn clamped(self, min: f32, max: f32) -> Degree {
+        if self.0.is_nan() {
+            Degree(min)
+        } else {
+            Degree(self.0.max(min).min(max))
+        }
+    }
+}
+
+impl Distance {
+    /// Returns the distance, with negative values and NaN replaced by 0.
+    pub fn non_negative(self) -> Distance {
+        Distance(self.0.max(0.0))
+    }
+
+    /// Returns the distance limited to `-max..=max`. NaN becomes 0.
+    pub fn clamped(self, max: f32) -> Distance {
+        if self.0.is_nan() {
+            Distance(0.0)
+        } else {
+            Distance(self.0.max(-max).min(max))
+        }
+    }
+}
+
 impl Neg for Degree {
     type Output = Degree;
     fn neg(self) -> Self::Output {
@@ -143,7 +388,7 @@ pub trait Turtle {
 
     /// Rotate around `angle`. If `angle` is positive,
     /// the turtle is turned to the left, if negative,
-    /// to the right.
+    /// to the right. Pass a `Radiant` to give the angle in radians.
     fn rotate<T: Into<Degree>>(&mut self, angle: T);
 
     /// Turn turtle right by `angle` degree.
@@ -156,6 +401,45 @@ pub trait Turtle {
         self.rotate(angle.into());
     }
 
+    /// Returns the current position of the turtle.
+    fn position(&self) -> Position;
+
+    /// Returns the heading of the turtle in degree, in `0.0..360.0`. A
+    /// heading of 0 points upwards, and angles increase counterclockwise
+    /// (clockwise for a `Canvas` in `HeadingMode::Compass`).
+    fn heading(&self) -> Degree;
+
+    /// Turn the turtle to the absolute heading `angle`, see `heading`.
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        let angle: Degree = angle.into();
+        let current = self.heading();
+        self.rotate(angle.0 - current.0);
+    }
+
+    /// Returns the heading from the turtle towards `pos`, see `heading`.
+    /// If the turtle is at `pos`, its current heading is returned.
+    fn towards(&self, pos: Position) -> Degree {
+        let here = self.position();
+        let (dx, dy) = (pos.0 - here.0, pos.1 - here.1);
+        if dx == 0.0 && dy == 0.0 {
+            return self.heading();
+        }
+        let angle: Degree = Radiant((0.0 - dx).atan2(dy)).into();
+        angle.normalized()
+    }
+
+    /// Returns the euclidean distance from the turtle to `pos`.
+    fn distance_to(&self, pos: Position) -> Distance {
+        let here = self.position();
+        Distance((pos.0 - here.0).hypot(pos.1 - here.1))
+    }
+
+    /// Turn the turtle to look at `pos`.
+    fn face(&mut self, pos: Position) {
+        let angle = self.towards(pos);
+        self.set_heading(angle);
+    }
+
     /// Returns `true` if pen is down.
     fn is_pen_down(&self) -> bool;
 
@@ -176,11 +460,142 @@ pub trait Turtle {
         self.goto(Position::origin());
     }
 
+    /// Move the turtle horizontally to `x`, drawing if the pen is down. The
+    /// heading is kept.
+    fn set_x(&mut self, x: f32) {
+        let (pos, heading) = (self.position(), self.heading());
+        let dx = x - pos.0;
+        self.set_heading(if dx < 0.0 { 90.0 } else { -90.0 });
+        if self.is_pen_down() {
+            self.forward(dx.abs());
+        } else {
+            self.move_forward(dx.abs());
+        }
+        self.set_heading(heading);
+    }
+
+    /// Move the turtle vertically to `y`, drawing if the pen is down. The
+    /// heading is kept.
+    fn set_y(&mut self, y: f32) {
+        let (pos, heading) = (self.position(), self.heading());
+        let dy = y - pos.1;
+        self.set_heading(if dy < 0.0 { 180.0 } else { 0.0 });
+        if self.is_pen_down() {
+            self.forward(dy.abs());
+        } else {
+            self.move_forward(dy.abs());
+        }
+        self.set_heading(heading);
+    }
+
+    /// Draw an arc of `extent` degree along a circle with `radius`. The
+    /// center lies `radius` to the left of the turtle, so a positive radius
+    /// draws counterclockwise, a negative one clockwise. Afterwards the
+    /// turtle is heading along the tangent at the end of the arc.
+    ///
+    /// The default implementation approximates the arc with a segment per
+    /// 5 degree.
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        let (radius, extent) = (radius.into().0, extent.into().0);
+        let steps = ((extent.abs() / 5.0).ceil() as usize).max(1);
+        let angle = extent / steps as f32 * radius.signum();
+        let chord = 2.0 * radius.abs() * (Radiant::from(Degree(angle)).0 / 2.0).sin().abs();
+        self.rotate(angle / 2.0);
+        for _ in 0..steps {
+            self.forward(chord.copysign(extent));
+            self.rotate(angle);
+        }
+        self.rotate(-angle / 2.0);
+    }
+
+    /// Draw a full circle with `radius`, see `arc`.
+    fn circle<R: Into<Distance>>(&mut self, radius: R) {
+        self.arc(radius, 360.0);
+    }
+
+    /// Turn left by `angle` while driving along an arc with `radius`, like a
+    /// car does, instead of pivoting on the spot.
+    fn veer_left<A: Into<Degree>, R: Into<Distance>>(&mut self, angle: A, radius: R) {
+        self.arc(radius.into(), angle.into());
+    }
+
+    /// Turn right by `angle` while driving along an arc with `radius`.
+    fn veer_right<A: Into<Degree>, R: Into<Distance>>(&mut self, angle: A, radius: R) {
+        self.arc(-radius.into(), angle.into());
+    }
+
+    /// Draw a spiral of `turns` turns around a center, counterclockwise for
+    /// positive `turns` and clockwise for negative ones. The spiral starts
+    /// in the direction of the turtle's heading and is approximated by a
+    /// segment per `step` degree.
+    fn spiral<T: Into<Degree>>(&mut self, turns: f32, growth: SpiralGrowth, step: T) {
+        let step = step.into().0.abs();
+        if step == 0.0 || !step.is_finite() || !turns.is_finite() {
+            return;
+        }
+        let extent = 360.0 * turns;
+        let steps = (extent.abs() / step).ceil() as usize;
+        let point = |k: usize| {
+            let angle = extent * k as f32 / steps as f32;
+            let radius = growth.radius(angle.abs() / 360.0);
+            let (sin, cos) = Radiant::from(Degree(angle)).0.sin_cos();
+            (radius * cos, radius * sin)
+        };
+        let (mut prev, mut direction) = (point(0), None::<f32>);
+        for k in 1..=steps {
+            let next = point(k);
+            let (dx, dy) = (next.0 - prev.0, next.1 - prev.1);
+            let angle: Degree = Radiant(dy.atan2(dx)).into();
+            if let Some(direction) = direction {
+                self.rotate((angle.0 - direction + 180.0).rem_euclid(360.0) - 180.0);
+            }
+            self.forward(dx.hypot(dy));
+            direction = Some(angle.0);
+            prev = next;
+        }
+    }
+
+    /// Run `body` `n` times, like Logo's `repeat`:
+    /// `t.repeat(4, |t| { t.forward(100.0); t.right(90.0); })`.
+    fn repeat<F: FnMut(&mut Self)>(&mut self, n: usize, mut body: F) {
+        for _ in 0..n {
+            body(self);
+        }
+    }
+
+    /// Draw a regular polygon with `sides` sides of `side_length`, turning
+    /// right at every corner. The turtle ends where it started.
+    fn polygon<T: Into<Distance>>(&mut self, sides: usize, side_length: T) {
+        let side_length = side_length.into();
+        let angle = 360.0 / sides as f32;
+        self.repeat(sides, |t| {
+            t.forward(side_length);
+            t.right(angle);
+        });
+    }
+
+    /// Draw the outline of a star with `points` tips, turning right at the
+    /// tips. Every edge has length `size`, and the tips have an angle of
+    /// `180 / points` degree, which gives the classic pentagram outline for
+    /// five points. The turtle ends where it started.
+    fn star<T: Into<Distance>>(&mut self, points: usize, size: T) {
+        let size = size.into();
+        let tip = 180.0 / points as f32;
+        let inner = 180.0 - tip - 360.0 / points as f32;
+        self.repeat(points, |t| {
+            t.forward(size);
+            t.right(180.0 - tip);
+            t.forward(size);
+            t.left(inner);
+        });
+    }
+
     /// Push current turtle state on stack.
     fn push(&mut self);
 
-    /// Restore previously saved turtle state.
-    fn pop(&mut self);
+    /// Restore previously saved turtle state. Returns `false`, leaving the
+    /// state unchanged, if there is no saved state.
+    fn pop(&mut self) -> bool;
 }
 
 #[derive(Clone)]
@@ -188,26 +603,366 @@ struct TurtleState {
     pos: Position,
     angle: Degree,
     pendown: bool,
+    pen: Pen,
+    fill_color: Color,
+    /// Factor applied to the pen width at newly drawn vertices.
+    pressure: f32,
+    /// Swap left and right turns.
+    mirrored: bool,
+    /// Factor applied to the distances of movements.
+    scale: f32,
+}
+
+impl TurtleState {
+    /// The state of a new turtle: at the origin, heading upwards, with the
+    /// pen down.
+    fn initial() -> TurtleState {
+        TurtleState {
+            pos: Position::origin(),
+            // The coordinate system we use: x from left to right. y from bottom to top.
+            angle: Degree(0.0), // points upwards
+            pendown: true,      // start with pen down
+            pen: Pen::default(),
+            fill_color: Color::BLACK,
+            pressure: 1.0,
+            mirrored: false,
+            scale: 1.0,
+        }
+    }
+}
+
+/// A recorded polyline. Regular paths are stroked with the pen, while paths
+/// with a `fill` color are closed regions which are filled but not stroked.
+#[derive(Clone, Debug)]
+struct Path {
+    points: Vec<Position>,
+    fill: Option<Color>,
+    /// Closed paths have an implicit segment from the last point back to the
+    /// first, which isn't repeated in `points`. Filled paths are always
+    /// closed.
+    closed: bool,
+    pen: Pen,
+    /// Index into `Canvas::layers`.
+    layer: usize,
+    /// `id` attribute in SVG output.
+    id: Option<String>,
+    /// `class` attribute in SVG output.
+    class: Option<String>,
+    /// Runs of `points` which approximate circular arcs.
+    arcs: Vec<ArcSegment>,
+    /// Pen pressure at each of `points`, or empty if the pressure is 1.0
+    /// everywhere.
+    pressures: Vec<f32>,
+    /// Center and radius if the path is a dot, exported as exact circle
+    /// where supported. `points` then hold a polygon approximating it.
+    dot: Option<(Position, f32)>,
+    /// Set if the path is a stamped shape. `points` then hold the outline
+    /// of the shape.
+    stamp: Option<Stamp>,
+    /// Set if the path is a text label. `points` then hold the approximate
+    /// bounding box of the text.
+    label: Option<Label>,
+}
+
+/// A line of text written at a position.
+#[derive(Clone, Debug)]
+struct Label {
+    text: String,
+    pos: Position,
+    size: f32,
+    align: TextAlign,
+    /// Counterclockwise rotation of the baseline.
+    angle: Degree,
+    /// Mirror the text left to right.
+    mirror: bool,
+}
+
+impl Path {
+    /// Returns `true` if the path is a stroked line, not a filled region,
+    /// dot, stamp or text label.
+    fn is_line(&self) -> bool {
+        self.fill.is_none() && self.dot.is_none() && self.stamp.is_none() && self.label.is_none()
+    }
+
+    /// Returns `true` if a line to `dst` continues the last segment straight
+    /// on, so that its end point can be moved to `dst` instead.
+    fn extends_straight(&self, dst: Position) -> bool {
+        let n = self.points.len();
+        if n < 2 || self.closed || !self.pressures.is_empty() || self.label.is_some() {
+            return false;
+        }
+        // Keep the ends of exact arcs.
+        if self.arcs.last().is_some_and(|arc| arc.end >= n - 1) {
+            return false;
+        }
+        let (a, b) = (self.points[n - 2], self.points[n - 1]);
+        let (u, v) = ((b.0 - a.0, b.1 - a.1), (dst.0 - b.0, dst.1 - b.1));
+        let cross = u.0 * v.1 - u.1 * v.0;
+        let dot = u.0 * v.0 + u.1 * v.1;
+        dot > 0.0 && cross.abs() <= 1e-5 * u.0.hypot(u.1) * v.0.hypot(v.1)
+    }
+}
+
+/// A shape stamped at a position and heading.
+#[derive(Copy, Clone, Debug)]
+struct Stamp {
+    /// Index into `Canvas::shapes`.
+    shape: usize,
+    pos: Position,
+    angle: Degree,
+    scale: f32,
+    /// Mirror the shape along its heading.
+    mirror: bool,
+}
+
+/// A run of path points from index `start` to `end` lying on a circular arc
+/// of at most 180 degree, exported as exact arc where supported.
+#[derive(Copy, Clone, Debug)]
+struct ArcSegment {
+    start: usize,
+    end: usize,
+    radius: f32,
+    /// `true` if the arc runs counterclockwise.
+    ccw: bool,
+}
+
+impl ArcSegment {
+    /// Returns the center of the arc running from `from` to `to`.
+    fn center(&self, from: Position, to: Position) -> Position {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let chord = dx.hypot(dy);
+        if chord == 0.0 {
+            return from;
+        }
+        // The center lies on the left of the chord for counterclockwise
+        // arcs of at most 180 degree.
+        let height = (self.radius * self.radius - chord * chord / 4.0)
+            .max(0.0)
+            .sqrt();
+        let side = if self.ccw { 1.0 } else { -1.0 };
+        Position(
+            (from.0 + to.0) / 2.0 - dy / chord * height * side,
+            (from.1 + to.1) / 2.0 + dx / chord * height * side,
+        )
+    }
+}
+
+/// A fill region which is currently being recorded.
+#[derive(Clone)]
+struct Filling {
+    /// Index into `paths` where the filled region is inserted, so that it is
+    /// drawn below the outline traced while filling.
+    path_index: usize,
+    vertices: Vec<Position>,
 }
 
+const DEFAULT_LAYER: &str = "default";
+
+#[derive(Clone)]
 pub struct Canvas {
+    /// State stack of the selected turtle.
     states: Vec<TurtleState>,
-    paths: Vec<Vec<Position>>,
+    /// State stacks of all turtles. The stack of the selected turtle is
+    /// moved to `states`, leaving its slot empty.
+    turtles: Vec<Vec<TurtleState>>,
+    active_turtle: usize,
+    heading_mode: HeadingMode,
+    /// Turtle states saved by `save_state`.
+    bookmarks: HashMap<String, TurtleState>,
+    paths: Vec<Path>,
+    filling: Option<Filling>,
+    background: Option<Color>,
+    /// Layer names, in order of creation.
+    layers: Vec<String>,
+    /// Indices into `layers` in drawing order, from bottom to top.
+    layer_order: Vec<usize>,
+    current_layer: usize,
+    /// Number of line segments approximating a full circle.
+    arc_segments: usize,
+    /// Export arcs as exact arcs instead of line segments.
+    exact_arcs: bool,
+    /// Overlay direction arrows and path numbers in SVG output.
+    debug_overlay: bool,
+    /// Distinct shapes used for stamping, so that SVG output can define
+    /// each of them once.
+    shapes: Vec<Shape>,
+    /// Index into `shapes`.
+    current_shape: usize,
+    font_size: f32,
+    text_align: TextAlign,
+    /// Rotate text along the turtle heading.
+    rotate_text: bool,
+    orientation: ExportOrientation,
+    /// Longest distance a single movement may cover.
+    max_distance: Option<f32>,
+    /// Extend straight continuations of the last segment, see
+    /// `set_merge_collinear`.
+    merge_collinear: bool,
+    /// Rectangle exports are clipped to.
+    export_clip: Option<Rect>,
+    /// Number of rotated copies of everything drawn, see `set_symmetry`.
+    symmetry: usize,
+    symmetry_mirror: bool,
+    /// Area the turtle is confined to.
+    area_limit: Option<Rect>,
+    area_mode: AreaMode,
+    /// Destinations of movements beyond the area limit.
+    area_violations: Vec<Position>,
+    travel_mode: TravelMode,
+    /// Mark the position and heading of the turtles in exports.
+    turtle_visible: bool,
+    /// Cylinder exports are wrapped around.
+    cylinder: Option<Cylinder>,
+    /// World the turtle wraps around in, re-entering at the opposite edge.
+    wrap_around: Option<Rect>,
+    /// Checkpoints before the most recent drawing commands, oldest first.
+    undo_journal: VecDeque<undo::Checkpoint>,
+    undo_depth: usize,
+    /// Incremented whenever paths are rewritten or removed instead of
+    /// appended to, so that SVG streams know to start over.
+    revision: u64,
+    /// View box and stroke width used by `save_svg` instead of fitting the
+    /// drawing, so that all frames of an animation line up.
+    svg_view: Option<(Position, f32, f32, f32)>,
+}
+
+impl Default for Canvas {
+    fn default() -> Canvas {
+        Canvas::new()
+    }
 }
 
 impl Canvas {
     pub fn new() -> Canvas {
-        let init_pos = Position::origin();
-        let init_state = TurtleState {
-            pos: init_pos,
-            // The coordinate system we use: x from left to right. y from bottom to top.
-            angle: Degree(0.0), // points upwards
-            pendown: true,      // start with pen down
-        };
-        Canvas {
+        let init_state = TurtleState::initial();
+        let init_pos = init_state.pos;
+        let mut canvas = Canvas {
             states: vec![init_state],
-            paths: vec![vec![init_pos]],
+            turtles: vec![Vec::new()],
+            active_turtle: 0,
+            heading_mode: HeadingMode::Counterclockwise,
+            bookmarks: HashMap::new(),
+            paths: Vec::new(),
+            filling: None,
+            background: None,
+            layers: vec![DEFAULT_LAYER.to_string()],
+            layer_order: vec![0],
+            current_layer: 0,
+            arc_segments: 72,
+            exact_arcs: false,
+            debug_overlay: false,
+            shapes: vec![Shape::default()],
+            current_shape: 0,
+            font_size: 12.0,
+            text_align: TextAlign::Start,
+            rotate_text: false,
+            orientation: ExportOrientation::default(),
+            max_distance: None,
+            merge_collinear: false,
+            export_clip: None,
+            symmetry: 1,
+            symmetry_mirror: false,
+            area_limit: None,
+            area_mode: AreaMode::Clamp,
+            area_violations: Vec::new(),
+            travel_mode: TravelMode::Jump,
+            turtle_visible: false,
+            cylinder: None,
+            wrap_around: None,
+            undo_journal: VecDeque::new(),
+            undo_depth: 100,
+            revision: 0,
+            svg_view: None,
+        };
+        canvas.move_to(init_pos);
+        canvas
+    }
+
+    /// Creates a canvas using the given convention for absolute headings.
+    pub fn with_heading_mode(mode: HeadingMode) -> Canvas {
+        let mut canvas = Canvas::new();
+        canvas.heading_mode = mode;
+        canvas
+    }
+
+    /// Returns the convention of absolute headings.
+    pub fn heading_mode(&self) -> HeadingMode {
+        self.heading_mode
+    }
+
+    /// Converts a counterclockwise angle to a heading in the heading mode,
+    /// in `0.0..360.0`.
+    fn heading_from_angle(&self, angle: Degree) -> Degree {
+        let angle = match self.heading_mode {
+            HeadingMode::Counterclockwise => angle.0,
+            HeadingMode::Compass => 0.0 - angle.0,
+        };
+        Degree(angle).normalized()
+    }
+
+    /// Converts a heading in the heading mode to a counterclockwise angle.
+    fn angle_from_heading(&self, heading: Degree) -> Degree {
+        match self.heading_mode {
+            HeadingMode::Counterclockwise => heading,
+            HeadingMode::Compass => -heading,
+        }
+    }
+
+    /// Returns the heading from `from` towards `to`, or `None` if they are
+    /// the same position.
+    fn heading_towards(&self, from: Position, to: Position) -> Option<Degree> {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        if dx == 0.0 && dy == 0.0 {
+            return None;
+        }
+        Some(self.heading_from_angle(Radiant((0.0 - dx).atan2(dy)).into()))
+    }
+
+    /// Erases all paths, keeping the turtle state, layers and background.
+    /// A fill in progress starts over at the current position.
+    pub fn clear(&mut self) {
+        self.checkpoint_full();
+        let pos = self.current_state().pos;
+        self.paths.clear();
+        if let Some(ref mut filling) = self.filling {
+            filling.path_index = 0;
+            filling.vertices.clear();
+            filling.vertices.push(pos);
+        }
+        self.move_to(pos);
+    }
+
+    /// Erases the drawing and returns all turtles to their initial state: at
+    /// the origin, heading upwards, with the pen down and default pen, fill
+    /// color and text settings. Layers, background, stamp shapes, saved
+    /// states and recorded area violations are removed as well. Settings like the arc
+    /// resolution, movement limits, export orientation and the undo journal
+    /// are kept.
+    pub fn reset(&mut self) {
+        self.checkpoint_full();
+        self.states.clear();
+        self.states.push(TurtleState::initial());
+        for (id, states) in self.turtles.iter_mut().enumerate() {
+            if id != self.active_turtle {
+                states.clear();
+                states.push(TurtleState::initial());
+            }
         }
+        self.bookmarks.clear();
+        self.paths.clear();
+        self.filling = None;
+        self.background = None;
+        self.layers.truncate(1);
+        self.layer_order.clear();
+        self.layer_order.push(0);
+        self.current_layer = 0;
+        self.shapes.truncate(1);
+        self.current_shape = 0;
+        self.font_size = 12.0;
+        self.text_align = TextAlign::default();
+        self.rotate_text = false;
+        self.area_violations.clear();
+        self.move_to(Position::origin());
     }
 
     #[inline]
@@ -220,175 +975,1818 @@ impl Canvas {
         self.states.last().unwrap()
     }
 
+    /// Turns the turtle counterclockwise by `delta` degree. The stored angle
+    /// is kept in `0.0..360.0`, so that precision isn't lost over many
+    /// rotations.
+    fn turn(&mut self, delta: f32) {
+        let state = self.current_state_mut();
+        state.angle = Degree(state.angle.0 + delta).normalized();
+    }
+
     #[inline]
     fn direction(&self, distance: Distance) -> (f32, f32) {
-        let state = self.current_state();
-        let rad: Radiant = state.angle.into();
-        let (sin, cos) = rad.0.sin_cos();
+        let (sin, cos) = self.current_state().angle.sin_cos();
         let dx = -sin * distance.0;
         let dy = cos * distance.0;
         (dx, dy)
     }
 
     fn line_to(&mut self, dst: Position) {
-        self.paths.last_mut().unwrap().push(dst);
+        let pressure = self.current_state().pressure;
+        let merge = self.merge_collinear;
+        let path = self.paths.last_mut().unwrap();
+        if merge && pressure == 1.0 && path.extends_straight(dst) {
+            *path.points.last_mut().unwrap() = dst;
+            return;
+        }
+        if pressure != 1.0 && path.pressures.is_empty() {
+            path.pressures = vec![1.0; path.points.len()];
+        }
+        path.points.push(dst);
+        if !path.pressures.is_empty() {
+            path.pressures.push(pressure);
+        }
+        if self.current_state().pen.is_cycling_hue() {
+            // Every segment gets its own color, so continue in a new path.
+            self.current_state_mut().pen.advance_hue();
+            self.add_symmetry_images();
+            self.paths.push(self.new_path(vec![dst], None));
+        }
     }
 
-    fn move_to(&mut self, dst: Position) {
-        if self.paths.is_empty() {
-            self.paths.push(vec![dst]);
+    /// Makes lines which continue the last segment of a path straight on
+    /// extend that segment instead of adding a point, so that many short
+    /// moves in the same direction are recorded as a single segment. Off by
+    /// default.
+    pub fn set_merge_collinear(&mut self, merge: bool) {
+        self.merge_collinear = merge;
+    }
+
+    /// Draws every line and filled region `order` times, rotated about the
+    /// origin in equal steps, for kaleidoscope and mandala figures. An order
+    /// of 1 switches rotational symmetry off. Dots, stamps and text aren't
+    /// repeated.
+    ///
+    /// The copies of a path are added once the turtle starts a new one;
+    /// exports include those of the path being drawn anyway.
+    pub fn set_symmetry(&mut self, order: usize) {
+        self.checkpoint();
+        let pos = self.current_state().pos;
+        self.move_to(pos);
+        self.symmetry = order.max(1);
+    }
+
+    /// Additionally mirrors everything drawn across the vertical axis
+    /// through the origin, with every rotation of `set_symmetry`.
+    pub fn set_symmetry_mirror(&mut self, mirror: bool) {
+        self.checkpoint();
+        let pos = self.current_state().pos;
+        self.move_to(pos);
+        self.symmetry_mirror = mirror;
+    }
+
+    /// Returns the rotated and mirrored copies of `path` required by the
+    /// symmetry settings.
+    fn symmetry_images(&self, path: &Path) -> Vec<Path> {
+        if path.stamp.is_some() || path.label.is_some() || path.dot.is_some() {
+            return Vec::new();
+        }
+        let mirrors: &[bool] = if self.symmetry_mirror {
+            &[false, true]
         } else {
-            let begin_new_path = self.paths.last().unwrap().len() > 1;
-            if begin_new_path {
-                self.paths.push(vec![dst]);
-            } else {
-                // Replace first path element with current position
-                self.paths.last_mut().unwrap()[0] = dst;
+            &[false]
+        };
+        let mut images = Vec::new();
+        for k in 0..self.symmetry {
+            let angle = Degree(360.0 * k as f32 / self.symmetry as f32);
+            let (sin, cos) = angle.sin_cos();
+            for &mirror in mirrors {
+                if k == 0 && !mirror {
+                    continue;
+                }
+                let mut image = path.clone();
+                image.id = None;
+                for pos in image.points.iter_mut() {
+                    let x = if mirror { -pos.0 } else { pos.0 };
+                    *pos = Position(x * cos - pos.1 * sin, x * sin + pos.1 * cos);
+                }
+                for arc in image.arcs.iter_mut() {
+                    arc.ccw ^= mirror;
+                }
+                images.push(image);
             }
         }
+        images
     }
 
-    fn foreach_position<F: FnMut(Position)>(&self, mut f: F, scale_x: f32, scale_y: f32) {
-        for path in self.paths.iter() {
-            for pos in path.iter() {
-                f(Position(pos.0 * scale_x, pos.1 * scale_y));
+    /// Adds the symmetric copies of the current path, before continuing in
+    /// a new one.
+    fn add_symmetry_images(&mut self) {
+        if let Some(path) = self.paths.last() {
+            if path.points.len() > 1 && path.fill.is_none() {
+                let images = self.symmetry_images(path);
+                self.paths.extend(images);
             }
         }
     }
 
-    /// Saves the turtle graphic as Embedded Postscript (EPS)
-    pub fn save_eps<W: Write>(&self, wr: &mut W) -> io::Result<()> {
-        // Determine extend of canvas
-        let mut bounds = Bounds::new();
+    /// Limits the distance of every single movement (`forward`, arcs, ...)
+    /// to `max`, or removes the limit for `None`. This includes moves to
+    /// absolute positions with `goto`, `set_x` and `set_y`, which end after
+    /// `max` in the direction of the target. Useful to sandbox untrusted
+    /// turtle programs.
+    pub fn set_max_distance(&mut self, max: Option<f32>) {
+        self.max_distance = max.map(|max| max.abs());
+    }
 
-        // The EPS coordinates are from bottom to top, like turtle coordinates.
-        self.foreach_position(|pos| bounds.add_position(pos), 1.0, 1.0);
+    /// Confines the turtle to `area`, or removes the confinement for `None`.
+    /// Movements beyond the area are handled according to the area mode; by
+    /// default they end at its border.
+    pub fn set_area_limit(&mut self, area: Option<Rect>) {
+        self.area_limit = area;
+    }
 
-        let (min_width, min_height) = (100.0, 100.0);
-        let width = bounds.width().max(min_width);
-        let height = bounds.height().max(min_height);
-        let border_percent = 0.1;
+    /// Sets how movements beyond the area limit are handled. The default is
+    /// `AreaMode::Clamp`.
+    pub fn set_area_mode(&mut self, mode: AreaMode) {
+        self.area_mode = mode;
+    }
 
-        let scale = 1.0 + 2.0 * border_percent;
+    /// Returns the destinations of all movements which went beyond the area
+    /// limit, in any mode, oldest first.
+    pub fn area_violations(&self) -> &[Position] {
+        &self.area_violations
+    }
 
-        writeln!(
-            wr,
-            r#"%%!PS-Adobe-3.0 EPSF-3.0
-%%Creator: https://github.com/mneumann/turtle-graphics-rs
-%%DocumentData: Clean7Bit
-%%Origin: 0 0
-%%BoundingBox: {} {} {} {}
-%%LanguageLevel: 2
-%%Pages: 1
-%%Page: 1 1
-"#,
-            bounds.min_x() - border_percent * width,
-            bounds.min_y() - border_percent * height,
-            bounds.max_x() + border_percent * width,
-            bounds.max_y() + border_percent * height
-        )?;
+    pub fn clear_area_violations(&mut self) {
+        self.area_violations.clear();
+    }
 
-        // use a stroke width of 0.1% of the width or height of the canvas
-        let stroke_width = scale * width.max(height) / 1000.0;
-        writeln!(wr, r#"{} setlinewidth"#, stroke_width)?;
+    /// Sets whether `goto` (and `home`) jump to the position or draw a
+    /// line to it. The default is `TravelMode::Jump`.
+    pub fn set_travel_mode(&mut self, mode: TravelMode) {
+        self.travel_mode = mode;
+    }
 
-        for path in self.paths.iter() {
-            if let Some((head, tail)) = path.split_first() {
-                writeln!(wr, "newpath")?;
-                writeln!(wr, "  {} {} moveto", head.0, head.1)?;
-                for pos in tail {
-                    writeln!(wr, r#"  {} {} lineto"#, pos.0, pos.1)?;
+    /// Applies the scale and the distance limit to the distance of a
+    /// movement.
+    fn movement_distance(&self, distance: Distance) -> Distance {
+        let distance = Distance(distance.0 * self.current_state().scale);
+        match self.max_distance {
+            Some(max) => distance.clamped(max),
+            None => distance,
+        }
+    }
+
+    /// Shortens a movement from the turtle to `dst` to the distance limit.
+    fn limit_distance(&self, dst: Position) -> Position {
+        let max = match self.max_distance {
+            Some(max) => max,
+            None => return dst,
+        };
+        let src = self.current_state().pos;
+        let (dx, dy) = (dst.0 - src.0, dst.1 - src.1);
+        let distance = dx.hypot(dy);
+        if distance.is_nan() || distance <= max {
+            return dst;
+        }
+        let (dx, dy) = if distance.is_infinite() {
+            // Head along the infinite components.
+            let axis = |d: f32| if d.is_infinite() { d.signum() } else { 0.0 };
+            let (x, y) = (axis(dx), axis(dy));
+            let length = x.hypot(y);
+            (x / length, y / length)
+        } else {
+            (dx / distance, dy / distance)
+        };
+        Position(src.0 + dx * max, src.1 + dy * max)
+    }
+
+    /// Applies the area limit to the destination of a movement. Returns
+    /// `None` if the movement is rejected.
+    fn limit_position(&mut self, pos: Position) -> Option<Position> {
+        let area = match self.area_limit {
+            Some(area) if !area.contains(pos) => area,
+            _ => return Some(pos),
+        };
+        self.area_violations.push(pos);
+        let clamp = |v: f32, min: f32, max: f32| {
+            if v.is_nan() {
+                min
+            } else {
+                v.max(min).min(max)
+            }
+        };
+        let clamped = Position(
+            clamp(pos.0, area.x, area.x + area.width),
+            clamp(pos.1, area.y, area.y + area.height),
+        );
+        match self.area_mode {
+            AreaMode::Clip if pos.0.is_finite() && pos.1.is_finite() => Some(pos),
+            AreaMode::Clamp | AreaMode::Clip => Some(clamped),
+            AreaMode::Reject => None,
+        }
+    }
+
+    /// Draws a line from the current position to `dst`. In clipping mode,
+    /// only the part within the area limit is drawn.
+    fn draw_line(&mut self, src: Position, dst: Position) {
+        let area = match self.area_limit {
+            Some(area) if self.area_mode == AreaMode::Clip => area,
+            _ => return self.line_to(dst),
+        };
+        // Liang-Barsky: narrow down the visible part `t0..t1` of the line.
+        let (dx, dy) = (dst.0 - src.0, dst.1 - src.1);
+        let (mut t0, mut t1) = (0.0f32, 1.0f32);
+        for &(p, q) in &[
+            (-dx, src.0 - area.x),
+            (dx, area.x + area.width - src.0),
+            (-dy, src.1 - area.y),
+            (dy, area.y + area.height - src.1),
+        ] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return;
                 }
-                writeln!(wr, r#"stroke"#)?;
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
             }
         }
-        writeln!(wr, "%%EOF")
+        if t0 > t1 || (t0 == t1 && (dx != 0.0 || dy != 0.0)) {
+            return;
+        }
+        let at = |t: f32| Position(src.0 + t * dx, src.1 + t * dy);
+        let start = at(t0);
+        let last = *self.paths.last().unwrap().points.last().unwrap();
+        if last.0 != start.0 || last.1 != start.1 {
+            // Re-entering the area.
+            self.move_to(start);
+        }
+        self.line_to(if t1 < 1.0 { at(t1) } else { dst });
     }
 
-    /// Saves the turtle graphic as Scalable Vector Graphic (SVG).
-    pub fn save_svg<W: Write>(&self, wr: &mut W) -> io::Result<()> {
-        // Determine extend of canvas
-        let mut bounds = Bounds::new();
+    /// Makes the turtle live on a torus: moving off one edge of `world`,
+    /// it re-enters at the opposite edge, and lines are split there. `None`
+    /// switches back to an unbounded plane. The area limit, if any, is
+    /// applied before wrapping.
+    pub fn set_wrap_around(&mut self, world: Option<Rect>) {
+        self.wrap_around = world;
+    }
 
-        // The SVG coordinates are from top to bottom, while turtle coordinates are
-        // bottom to
-        // top. We have to convert between the two. (multiply `y` by -1.0)
-        self.foreach_position(|pos| bounds.add_position(pos), 1.0, -1.0);
+    /// Maps `pos` into the wrap-around world, if any.
+    fn wrap_position(&self, pos: Position) -> Position {
+        match self.wrap_around {
+            Some(world) => Position(
+                world.x + (pos.0 - world.x).rem_euclid(world.width),
+                world.y + (pos.1 - world.y).rem_euclid(world.height),
+            ),
+            None => pos,
+        }
+    }
+
+    /// Moves the turtle to `dst`, drawing a line if the pen is down.
+    fn travel_to(&mut self, dst: Position) {
+        let dst = self.limit_distance(dst);
+        let dst = match self.limit_position(dst) {
+            Some(dst) => dst,
+            None => return,
+        };
+        if let Some(world) = self.wrap_around {
+            self.travel_wrapped(dst, world);
+            return;
+        }
+        if self.is_pen_down() {
+            let src = self.current_state().pos;
+            self.draw_line(src, dst);
+        }
+        self.current_state_mut().pos = dst;
+        self.add_fill_vertex(dst);
+    }
+
+    /// Moves the turtle to `dst` within the wrap-around `world`, splitting
+    /// the line wherever it leaves the world.
+    fn travel_wrapped(&mut self, mut dst: Position, world: Rect) {
+        // Upper bound for the number of times a single movement wraps, after
+        // which the turtle jumps to the destination.
+        const MAX_WRAPS: usize = 10_000;
+
+        let pen_down = self.is_pen_down();
+        let mut pos = self.current_state().pos;
+        let inside = self.wrap_position(pos);
+        if inside.0 != pos.0 || inside.1 != pos.1 {
+            // Started outside of the world, e.g. before wrapping was enabled.
+            dst = Position(dst.0 + inside.0 - pos.0, dst.1 + inside.1 - pos.1);
+            pos = inside;
+            self.current_state_mut().pos = pos;
+            self.move_to(pos);
+        }
+
+        let mut wraps = 0;
+        loop {
+            let (dx, dy) = (dst.0 - pos.0, dst.1 - pos.1);
+            // Fraction of the way to the edge which is crossed first, and the
+            // shift re-entering at the opposite edge.
+            let crossing = |delta: f32, p: f32, min: f32, size: f32| {
+                if delta > 0.0 {
+                    ((min + size - p) / delta, -size, min + size)
+                } else if delta < 0.0 {
+                    ((min - p) / delta, size, min)
+                } else {
+                    (f32::INFINITY, 0.0, p)
+                }
+            };
+            let (tx, shift_x, edge_x) = crossing(dx, pos.0, world.x, world.width);
+            let (ty, shift_y, edge_y) = crossing(dy, pos.1, world.y, world.height);
+            let t = tx.min(ty);
+            if t >= 1.0 || t.is_nan() {
+                break;
+            }
+            if wraps == MAX_WRAPS {
+                dst = self.wrap_position(dst);
+                pos = dst;
+                self.current_state_mut().pos = pos;
+                if pen_down {
+                    self.move_to(pos);
+                }
+                break;
+            }
+            wraps += 1;
+            let (edge, shift) = if tx <= ty {
+                (Position(edge_x, pos.1 + t * dy), Position(shift_x, 0.0))
+            } else {
+                (Position(pos.0 + t * dx, edge_y), Position(0.0, shift_y))
+            };
+            if pen_down {
+                self.draw_line(pos, edge);
+            }
+            self.add_fill_vertex(edge);
+            pos = edge + shift;
+            dst = dst + shift;
+            self.current_state_mut().pos = pos;
+            if pen_down {
+                self.move_to(pos);
+            }
+        }
+
+        if pen_down && (dst.0 != pos.0 || dst.1 != pos.1) {
+            self.draw_line(pos, dst);
+        }
+        self.current_state_mut().pos = dst;
+        self.add_fill_vertex(dst);
+    }
+
+    /// Moves the turtle to `dst` without drawing, starting a new path.
+    fn jump_to(&mut self, dst: Position) {
+        let dst = self.limit_distance(dst);
+        let dst = match self.limit_position(dst) {
+            Some(dst) => self.wrap_position(dst),
+            None => return,
+        };
+        self.current_state_mut().pos = dst;
+        self.move_to(dst);
+        self.add_fill_vertex(dst);
+    }
+
+    fn move_to(&mut self, dst: Position) {
+        if self.paths.is_empty() {
+            self.paths.push(self.new_path(vec![dst], None));
+        } else {
+            let begin_new_path = self.paths.last().unwrap().points.len() > 1;
+            if begin_new_path {
+                self.add_symmetry_images();
+                self.paths.push(self.new_path(vec![dst], None));
+            } else {
+                // Replace first path element with current position
+                let pressure = self.current_state().pressure;
+                let path = self.paths.last_mut().unwrap();
+                path.points[0] = dst;
+                path.pressures = if pressure != 1.0 {
+                    vec![pressure]
+                } else {
+                    Vec::new()
+                };
+            }
+        }
+    }
+
+    /// Applies a changed pen to the current path. If the current path was
+    /// already drawn, continue in a new path.
+    fn restyle_current_path(&mut self) {
+        let pos = self.current_state().pos;
+        self.move_to(pos);
+        self.paths.last_mut().unwrap().pen = self.current_state().pen.clone();
+    }
+
+    /// Sets the pen used for subsequently drawn paths.
+    pub fn set_pen(&mut self, pen: Pen) {
+        self.checkpoint();
+        self.current_state_mut().pen = pen;
+        self.restyle_current_path();
+    }
+
+    /// Returns the current pen.
+    pub fn pen(&self) -> &Pen {
+        &self.current_state().pen
+    }
+
+    /// Changes the color of the current pen.
+    pub fn set_pen_color(&mut self, color: Color) {
+        let pen = self.current_state().pen.clone().with_color(color);
+        self.set_pen(pen);
+    }
+
+    /// Changes the stroke width of the current pen.
+    pub fn set_pen_width(&mut self, width: f32) {
+        let pen = self.current_state().pen.clone().with_width(width);
+        self.set_pen(pen);
+    }
+
+    /// Changes the dash pattern of the current pen.
+    pub fn set_pen_dash(&mut self, pattern: &[f32]) {
+        let pen = self.current_state().pen.clone().with_dash(pattern);
+        self.set_pen(pen);
+    }
+
+    /// Sets the number of line segments used to approximate a full circle
+    /// drawn by `arc` or `circle`. Partial arcs use proportionally fewer.
+    pub fn set_arc_resolution(&mut self, segments_per_circle: usize) {
+        self.arc_segments = segments_per_circle.max(3);
+    }
+
+    /// If `exact` is `true`, arcs drawn by `arc`, `circle` and `veer_left`/
+    /// `veer_right`, or found by `recognize_arcs`, are written as true arcs
+    /// by exporters which support it (SVG and EPS). Other exporters, and
+    /// bounds computation, always use the line segments.
+    pub fn set_exact_arcs(&mut self, exact: bool) {
+        self.exact_arcs = exact;
+    }
+
+    /// Simulates pen pressure: the stroke width at vertices drawn from now on
+    /// is the pen width scaled by `pressure`. Paths with varying pressure are
+    /// exported to SVG as outlined, filled shapes, so that the width
+    /// variation survives in editing tools. Other exporters use the pen
+    /// width.
+    pub fn set_pen_pressure(&mut self, pressure: f32) {
+        self.checkpoint();
+        self.current_state_mut().pressure = pressure.max(0.0);
+    }
+
+    /// Returns the current pen pressure.
+    pub fn pen_pressure(&self) -> f32 {
+        self.current_state().pressure
+    }
+
+    /// Returns the lower left and upper right corner of the rectangle
+    /// enclosing the points of all paths, as used to frame the exports, or
+    /// `None` if there are none. Pen widths aren't taken into account, and
+    /// the start of a path counts even if nothing was drawn from it yet, so
+    /// a new canvas gives the origin as both corners.
+    pub fn bounding_box(&self) -> Option<(Position, Position)> {
+        let mut bounds = Bounds::new();
+        self.foreach_position(|pos| bounds.add_position(pos), 1.0, 1.0);
+        bounds.min_max
+    }
+
+    /// Scales and translates the whole drawing, including the turtle
+    /// positions, to fit into `target` while preserving its aspect ratio.
+    /// The drawing is centered in `target`. Fixed pen widths and dash
+    /// patterns are scaled along.
+    pub fn normalize(&mut self, target: Rect) {
+        self.checkpoint_full();
+        let mut bounds = Bounds::new();
+        self.foreach_position(|pos| bounds.add_position(pos), 1.0, 1.0);
+        if !bounds.is_bounded() {
+            return;
+        }
+        let (width, height) = (bounds.width(), bounds.height());
+        let scale = match (width > 0.0, height > 0.0) {
+            (true, true) => (target.width / width).min(target.height / height),
+            (true, false) => target.width / width,
+            (false, true) => target.height / height,
+            (false, false) => 1.0,
+        };
+        let offset = Position(
+            target.x + (target.width - width * scale) / 2.0 - bounds.min_x() * scale,
+            target.y + (target.height - height * scale) / 2.0 - bounds.min_y() * scale,
+        );
+        self.map_geometry(
+            |pos| Position(pos.0 * scale + offset.0, pos.1 * scale + offset.1),
+            scale,
+            0.0,
+            false,
+        );
+    }
+
+    /// Sets how drawings are mirrored and rotated on export, e.g. mirrored
+    /// for screen-printing film or rotated for a plotter with swapped axes.
+    pub fn set_export_orientation(&mut self, orientation: ExportOrientation) {
+        self.orientation = orientation;
+    }
+
+    /// Returns a copy of the canvas with the symmetric copies of the current
+    /// path, turtle glyphs, clipping, cylinder wrapping and export
+    /// orientation applied, or `None` if there is nothing to apply.
+    fn export_copy(&self) -> Option<Canvas> {
+        let o = self.orientation;
+        // Mirroring top and bottom is mirroring left and right followed by
+        // a half turn.
+        let mirror = o.mirror_horizontal != o.mirror_vertical;
+        let turns = (o.quarter_turns + if o.mirror_vertical { 2 } else { 0 }).rem_euclid(4);
+        let symmetric = self.symmetry > 1 || self.symmetry_mirror;
+        if !mirror
+            && turns == 0
+            && self.cylinder.is_none()
+            && !self.turtle_visible
+            && !symmetric
+            && self.export_clip.is_none()
+        {
+            return None;
+        }
+        let mut canvas = self.clone();
+        canvas.set_undo_depth(0);
+        canvas.orientation = ExportOrientation::default();
+        // Complete the copies of the path being drawn.
+        canvas.add_symmetry_images();
+        canvas.symmetry = 1;
+        canvas.symmetry_mirror = false;
+        if canvas.turtle_visible {
+            canvas.turtle_visible = false;
+            let selected = canvas.active_turtle;
+            for id in 0..canvas.turtles.len() {
+                canvas.select_turtle(id);
+                canvas.stamp();
+            }
+            canvas.select_turtle(selected);
+        }
+        if let Some(rect) = canvas.export_clip.take() {
+            canvas.clip_paths(rect);
+        }
+        if let Some(cylinder) = canvas.cylinder.take() {
+            canvas.wrap_cylinder(cylinder);
+        }
+        canvas.map_geometry(
+            |pos| {
+                let x = if mirror { -pos.0 } else { pos.0 };
+                match turns {
+                    1 => Position(-pos.1, x),
+                    2 => Position(-x, -pos.1),
+                    3 => Position(pos.1, -x),
+                    _ => Position(x, pos.1),
+                }
+            },
+            1.0,
+            90.0 * turns as f32,
+            mirror,
+        );
+        Some(canvas)
+    }
+
+    /// Maps all geometry by `f`, which must be a similarity transform with
+    /// the given `scale`, which rotates counterclockwise by `rotation`
+    /// degree, after mirroring left to right if `mirror` is set. For other
+    /// transforms, these describe the similarity applied to headings, pen
+    /// widths, stamps and text.
+    fn map_geometry<F: Fn(Position) -> Position>(
+        &mut self,
+        f: F,
+        scale: f32,
+        rotation: f32,
+        mirror: bool,
+    ) {
+        // Angles of local frames (headings, stamps, text) are mirrored too.
+        let angle = |a: Degree| Degree(rotation + if mirror { -a.0 } else { a.0 });
+        for path in self.paths.iter_mut() {
+            for pos in path.points.iter_mut() {
+                *pos = f(*pos);
+            }
+            for arc in path.arcs.iter_mut() {
+                arc.radius *= scale;
+                arc.ccw ^= mirror;
+            }
+            if let Some((center, radius)) = path.dot {
+                path.dot = Some((f(center), radius * scale));
+            }
+            if let Some(ref mut stamp) = path.stamp {
+                stamp.pos = f(stamp.pos);
+                stamp.scale *= scale;
+                stamp.angle = angle(stamp.angle);
+                stamp.mirror ^= mirror;
+            }
+            if let Some(ref mut label) = path.label {
+                label.pos = f(label.pos);
+                label.size *= scale;
+                label.angle = angle(label.angle);
+                label.mirror ^= mirror;
+            }
+            path.pen.scale(scale);
+        }
+        for state in self.states.iter_mut() {
+            state.pos = f(state.pos);
+            state.angle = angle(state.angle).normalized();
+            state.pen.scale(scale);
+        }
+        if let Some(ref mut filling) = self.filling {
+            for pos in filling.vertices.iter_mut() {
+                *pos = f(*pos);
+            }
+        }
+    }
+
+    /// Closes the current path with a segment back to its first point, and
+    /// moves the turtle there. Drawing continues in a new path. Has no
+    /// effect on paths with less than three points.
+    pub fn close_path(&mut self) {
+        self.checkpoint();
+        let path = self.paths.last_mut().unwrap();
+        if path.points.len() < 3 {
+            return;
+        }
+        path.closed = true;
+        let start = path.points[0];
+        self.current_state_mut().pos = start;
+        self.move_to(start);
+        self.add_fill_vertex(start);
+    }
+
+    /// If `enabled` is `true`, SVG output is overlaid with arrowheads along
+    /// every stroked path, showing the direction it was drawn in, and the
+    /// number of the path in drawing order at its start. Useful to diagnose
+    /// orientation and travel order.
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = enabled;
+    }
+
+    /// Records a position the turtle moved to as vertex of the fill region.
+    fn add_fill_vertex(&mut self, pos: Position) {
+        if let Some(ref mut filling) = self.filling {
+            filling.vertices.push(pos);
+        }
+    }
+
+    /// Sets the color used for regions filled by `begin_fill`/`end_fill`.
+    pub fn set_fill_color(&mut self, color: Color) {
+        self.checkpoint();
+        self.current_state_mut().fill_color = color;
+    }
+
+    /// Returns the color used for filled regions.
+    pub fn fill_color(&self) -> Color {
+        self.current_state().fill_color
+    }
+
+    /// If `mirror` is `true`, left and right turns (including arcs) are
+    /// swapped for subsequent commands, so that the same code draws the
+    /// mirror image. Absolute headings are not affected. The setting is
+    /// part of the turtle state saved by `push`.
+    pub fn set_mirror(&mut self, mirror: bool) {
+        self.checkpoint();
+        self.current_state_mut().mirrored = mirror;
+    }
+
+    /// Returns `true` if left and right turns are swapped.
+    pub fn is_mirrored(&self) -> bool {
+        self.current_state().mirrored
+    }
+
+    /// Returns the number of states saved by `push` which can be restored
+    /// by `pop`.
+    pub fn stack_depth(&self) -> usize {
+        self.states.len() - 1
+    }
+
+    /// Saves the turtle state (position, heading, pen, ...) under `name`,
+    /// replacing any state saved under the same name before.
+    pub fn save_state(&mut self, name: &str) {
+        let state = self.current_state().clone();
+        self.bookmarks.insert(name.to_string(), state);
+    }
+
+    /// Restores the turtle state saved under `name`, moving the turtle back
+    /// without drawing. Unlike `pop`, the state stays saved and can be
+    /// restored again. Returns `false` if there is no state `name`.
+    pub fn restore_state(&mut self, name: &str) -> bool {
+        let state = match self.bookmarks.get(name) {
+            Some(state) => state.clone(),
+            None => return false,
+        };
+        self.checkpoint();
+        *self.current_state_mut() = state;
+        self.restyle_current_path();
+        true
+    }
+
+    /// Multiplies the distances of subsequent movements (`forward`, arc
+    /// radii, ...) by `scale`. Absolute positions are not affected. The
+    /// scale is part of the turtle state saved by `push`, so recursive
+    /// figures can shrink each level with `push`, `set_scale` and `pop`.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.checkpoint();
+        self.current_state_mut().scale = scale;
+    }
+
+    /// Returns the factor applied to the distances of movements.
+    pub fn scale(&self) -> f32 {
+        self.current_state().scale
+    }
+
+    /// Shows the turtles in exported drawings, stamped with the current
+    /// shape (see `set_shape`) at their final position and heading.
+    pub fn show_turtle(&mut self) {
+        self.turtle_visible = true;
+    }
+
+    /// Hides the turtles in exported drawings, which is the default.
+    pub fn hide_turtle(&mut self) {
+        self.turtle_visible = false;
+    }
+
+    /// Returns `true` if the turtles are shown in exported drawings.
+    pub fn is_turtle_visible(&self) -> bool {
+        self.turtle_visible
+    }
+
+    /// Sets the background color. The background is emitted as filled
+    /// rectangle covering the whole exported page.
+    pub fn set_background(&mut self, color: Color) {
+        self.checkpoint();
+        self.background = Some(color);
+    }
+
+    /// Removes the background color, making exports transparent again.
+    pub fn clear_background(&mut self) {
+        self.checkpoint();
+        self.background = None;
+    }
+
+    /// Returns the background color, if any.
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+
+    /// Returns `true` between calls to `begin_fill` and `end_fill`.
+    pub fn is_filling(&self) -> bool {
+        self.filling.is_some()
+    }
+
+    /// Starts recording a filled region at the current position. All
+    /// positions the turtle moves to (with pen up or down) until `end_fill`
+    /// become the vertices of the region.
+    pub fn begin_fill(&mut self) {
+        self.checkpoint();
+        let pos = self.current_state().pos;
+        self.filling = Some(Filling {
+            path_index: self.paths.len().saturating_sub(1),
+            vertices: vec![pos],
+        });
+    }
+
+    /// Closes the region started by `begin_fill` and fills it with the
+    /// current fill color. Regions with less than three vertices are
+    /// discarded.
+    pub fn end_fill(&mut self) {
+        self.checkpoint_full();
+        if let Some(filling) = self.filling.take() {
+            if filling.vertices.len() > 2 {
+                let fill = Some(self.current_state().fill_color);
+                let path = self.new_path(filling.vertices, fill);
+                let mut paths = self.symmetry_images(&path);
+                paths.insert(0, path);
+                let index = filling.path_index;
+                self.paths.splice(index..index, paths);
+            }
+        }
+    }
+
+    /// Creates a path with the current pen on the current layer.
+    fn new_path(&self, points: Vec<Position>, fill: Option<Color>) -> Path {
+        let pressure = self.current_state().pressure;
+        let pressures = if pressure != 1.0 && fill.is_none() {
+            vec![pressure; points.len()]
+        } else {
+            Vec::new()
+        };
+        Path {
+            pressures,
+            points,
+            closed: fill.is_some(),
+            fill,
+            pen: self.current_state().pen.clone(),
+            layer: self.current_layer,
+            id: None,
+            class: None,
+            arcs: Vec::new(),
+            dot: None,
+            stamp: None,
+            label: None,
+        }
+    }
+
+    /// Tags the current path with an `id`, which the SVG exporter writes as
+    /// `id` attribute. Ids should be unique within the document.
+    pub fn set_path_id(&mut self, id: &str) {
+        self.checkpoint();
+        self.paths.last_mut().unwrap().id = Some(id.to_string());
+    }
+
+    /// Tags the current path with a CSS `class`, which the SVG exporter
+    /// writes as `class` attribute. Several classes are separated by spaces.
+    pub fn set_path_class(&mut self, class: &str) {
+        self.checkpoint();
+        self.paths.last_mut().unwrap().class = Some(class.to_string());
+    }
+
+    /// Switches to the layer `name`, creating it on top of all other layers
+    /// if it doesn't exist yet. Paths are drawn on the current layer. The
+    /// initial layer is named `"default"`.
+    pub fn layer(&mut self, name: &str) {
+        self.checkpoint();
+        self.current_layer = match self.layers.iter().position(|l| l == name) {
+            Some(index) => index,
+            None => {
+                self.layers.push(name.to_string());
+                self.layer_order.push(self.layers.len() - 1);
+                self.layers.len() - 1
+            }
+        };
+        let pos = self.current_state().pos;
+        self.move_to(pos);
+        self.paths.last_mut().unwrap().layer = self.current_layer;
+    }
+
+    /// Returns the name of the current layer.
+    pub fn current_layer(&self) -> &str {
+        &self.layers[self.current_layer]
+    }
+
+    /// Returns the layer names in drawing order, from bottom to top.
+    pub fn layer_names(&self) -> Vec<&str> {
+        self.layer_order
+            .iter()
+            .map(|&i| self.layers[i].as_str())
+            .collect()
+    }
+
+    /// Changes the drawing order of the layers. The layers named in `order`
+    /// are drawn first (bottom-most), in the given order. Remaining layers
+    /// follow in their previous order. Unknown names are ignored.
+    pub fn set_layer_order(&mut self, order: &[&str]) {
+        self.checkpoint();
+        let mut rank: Vec<usize> = vec![usize::MAX; self.layers.len()];
+        for (r, name) in order.iter().enumerate() {
+            if let Some(index) = self.layers.iter().position(|l| l == name) {
+                rank[index] = rank[index].min(r);
+            }
+        }
+        // Stable, so unnamed layers keep their relative order.
+        self.layer_order.sort_by_key(|&i| rank[i]);
+    }
+
+    /// Returns the paths grouped by layer, in drawing order.
+    fn paths_by_layer(&self) -> Vec<(&str, Vec<&Path>)> {
+        self.layer_order
+            .iter()
+            .map(|&i| {
+                let paths = self.paths.iter().filter(|p| p.layer == i).collect();
+                (self.layers[i].as_str(), paths)
+            })
+            .collect()
+    }
+
+    /// Appends a complete path (e.g. generated geometry) without touching the
+    /// turtle state. Drawing continues in a new path afterwards.
+    fn add_path(&mut self, path: Path) {
+        self.add_symmetry_images();
+        let pos = self.current_state().pos;
+        if let Some(last) = self.paths.last() {
+            if last.points.len() < 2 && last.fill.is_none() {
+                self.paths.pop();
+            }
+        }
+        self.paths.push(path);
+        self.paths.push(self.new_path(vec![pos], None));
+    }
+
+    /// Appends an open polyline through `points`.
+    fn add_polyline(&mut self, points: Vec<Position>) {
+        if points.len() > 1 {
+            let path = self.new_path(points, None);
+            self.add_path(path);
+        }
+    }
+
+    /// Appends a closed, stroked polygon through `points`.
+    fn add_closed_polyline(&mut self, points: Vec<Position>) {
+        if points.len() > 2 {
+            let mut path = self.new_path(points, None);
+            path.closed = true;
+            self.add_path(path);
+        }
+    }
+
+    /// Appends a dot of `diameter` at `center`, filled with the pen color.
+    fn add_dot(&mut self, center: Position, diameter: f32) {
+        let radius = diameter / 2.0;
+        let outline = (0..24)
+            .map(|i| {
+                let angle = i as f32 * PI / 12.0;
+                Position(
+                    center.0 + radius * angle.cos(),
+                    center.1 + radius * angle.sin(),
+                )
+            })
+            .collect();
+        let mut path = self.new_path(outline, Some(self.current_state().pen.color()));
+        path.dot = Some((center, radius));
+        self.add_path(path);
+    }
+
+    /// Stamps a filled circle of `diameter` in the pen color at the current
+    /// position, regardless of whether the pen is up or down.
+    pub fn dot(&mut self, diameter: f32) {
+        self.checkpoint();
+        let pos = self.current_state().pos;
+        self.add_dot(pos, diameter);
+    }
+
+    /// Sets the shape drawn by `stamp`.
+    pub fn set_shape(&mut self, shape: Shape) {
+        self.checkpoint();
+        self.current_shape = match self.shapes.iter().position(|s| *s == shape) {
+            Some(index) => index,
+            None => {
+                self.shapes.push(shape);
+                self.shapes.len() - 1
+            }
+        };
+    }
+
+    /// Returns the shape drawn by `stamp`.
+    pub fn shape(&self) -> &Shape {
+        &self.shapes[self.current_shape]
+    }
+
+    /// Stamps the current shape, filled with the pen color, at the current
+    /// position and heading, regardless of whether the pen is up or down.
+    pub fn stamp(&mut self) {
+        self.checkpoint();
+        let state = self.current_state();
+        let (pos, angle) = (state.pos, state.angle);
+        let (sin, cos) = angle.sin_cos();
+        let outline = self.shapes[self.current_shape]
+            .outline()
+            .into_iter()
+            .map(|(x, y)| Position(pos.0 + x * cos - y * sin, pos.1 + x * sin + y * cos))
+            .collect();
+        let mut path = self.new_path(outline, Some(state.pen.color()));
+        path.stamp = Some(Stamp {
+            shape: self.current_shape,
+            pos,
+            angle,
+            scale: 1.0,
+            mirror: false,
+        });
+        self.add_path(path);
+    }
+
+    /// Sets the font size used by `write_text`.
+    pub fn set_font_size(&mut self, size: f32) {
+        self.checkpoint();
+        self.font_size = size;
+    }
+
+    /// Sets the alignment of text written by `write_text`.
+    pub fn set_text_align(&mut self, align: TextAlign) {
+        self.checkpoint();
+        self.text_align = align;
+    }
+
+    /// If `rotate` is `true`, text is written along the turtle heading.
+    /// Otherwise it is written horizontally.
+    pub fn set_text_rotation(&mut self, rotate: bool) {
+        self.checkpoint();
+        self.rotate_text = rotate;
+    }
+
+    /// Writes a line of `text` in the pen color, with its baseline anchored
+    /// at the current position. The turtle doesn't move.
+    pub fn write_text(&mut self, text: &str) {
+        self.checkpoint();
+        let state = self.current_state();
+        let pos = state.pos;
+        let angle = if self.rotate_text {
+            // Unrotated text runs to the right, i.e. at a heading of -90.
+            Degree(state.angle.0 + 90.0)
+        } else {
+            Degree(0.0)
+        };
+        let size = self.font_size;
+        let width = text::CHAR_WIDTH * size * text.chars().count() as f32;
+        let left = -self.text_align.fraction() * width;
+        let (sin, cos) = angle.sin_cos();
+        let corners = [
+            (left, 0.0),
+            (left + width, 0.0),
+            (left + width, size),
+            (left, size),
+        ]
+        .iter()
+        .map(|&(x, y)| Position(pos.0 + x * cos - y * sin, pos.1 + x * sin + y * cos))
+        .collect();
+        let mut path = self.new_path(corners, Some(state.pen.color()));
+        path.label = Some(Label {
+            text: text.to_string(),
+            pos,
+            size,
+            align: self.text_align,
+            angle,
+            mirror: false,
+        });
+        self.add_path(path);
+    }
+
+    /// Appends a closed region through `points`, filled with `color`.
+    fn add_polygon(&mut self, points: Vec<Position>, color: Color) {
+        if points.len() > 2 {
+            let path = self.new_path(points, Some(color));
+            self.add_path(path);
+        }
+    }
+
+    fn foreach_position<F: FnMut(Position)>(&self, mut f: F, scale_x: f32, scale_y: f32) {
+        for path in self.paths.iter() {
+            for pos in path.points.iter() {
+                f(Position(pos.0 * scale_x, pos.1 * scale_y));
+            }
+        }
+    }
+
+    /// Saves the turtle graphic as Embedded Postscript (EPS)
+    pub fn save_eps<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        if let Some(canvas) = self.export_copy() {
+            return canvas.save_eps(wr);
+        }
+        // Determine extend of canvas
+        let mut bounds = Bounds::new();
+
+        // The EPS coordinates are from bottom to top, like turtle coordinates.
+        self.foreach_position(|pos| bounds.add_position(pos), 1.0, 1.0);
 
         let (min_width, min_height) = (100.0, 100.0);
         let width = bounds.width().max(min_width);
         let height = bounds.height().max(min_height);
         let border_percent = 0.1;
 
-        let top_left = Position(
+        let scale = 1.0 + 2.0 * border_percent;
+
+        let (llx, lly) = (
             bounds.min_x() - border_percent * width,
             bounds.min_y() - border_percent * height,
         );
+        let (urx, ury) = (
+            bounds.max_x() + border_percent * width,
+            bounds.max_y() + border_percent * height,
+        );
 
-        let scale = 1.0 + 2.0 * border_percent;
+        writeln!(
+            wr,
+            r#"%%!PS-Adobe-3.0 EPSF-3.0
+%%Creator: https://github.com/mneumann/turtle-graphics-rs
+%%DocumentData: Clean7Bit
+%%Origin: 0 0
+%%BoundingBox: {} {} {} {}
+%%LanguageLevel: 2
+%%Pages: 1
+%%Page: 1 1
+"#,
+            llx, lly, urx, ury
+        )?;
+
+        if let Some(color) = self.background {
+            let (r, g, b) = color.to_unit_rgb();
+            writeln!(
+                wr,
+                "gsave {} {} {} setrgbcolor {} {} {} {} rectfill grestore",
+                r,
+                g,
+                b,
+                llx,
+                lly,
+                urx - llx,
+                ury - lly
+            )?;
+        }
+
+        // use a stroke width of 0.1% of the width or height of the canvas
+        let stroke_width = scale * width.max(height) / 1000.0;
+        writeln!(wr, r#"{} setlinewidth"#, stroke_width)?;
+
+        for path in self
+            .paths_by_layer()
+            .into_iter()
+            .flat_map(|(_, paths)| paths)
+        {
+            if let (Some(label), Some(color)) = (&path.label, path.fill) {
+                let (r, g, b) = color.to_unit_rgb();
+                writeln!(wr, "gsave {} {} {} setrgbcolor", r, g, b)?;
+                writeln!(wr, "  /Helvetica findfont {} scalefont setfont", label.size)?;
+                writeln!(
+                    wr,
+                    "  {} {} translate {} rotate{} 0 0 moveto",
+                    label.pos.0,
+                    label.pos.1,
+                    label.angle.0,
+                    if label.mirror { " -1 1 scale" } else { "" }
+                )?;
+                writeln!(
+                    wr,
+                    "  ({}) dup stringwidth pop {} mul neg 0 rmoveto show",
+                    text::ps_escape(&label.text),
+                    label.align.fraction()
+                )?;
+                writeln!(wr, "grestore")?;
+                continue;
+            }
+            if let (Some((center, radius)), Some(color)) = (path.dot, path.fill) {
+                let (r, g, b) = color.to_unit_rgb();
+                writeln!(wr, "newpath")?;
+                writeln!(wr, "  {} {} {} 0 360 arc", center.0, center.1, radius)?;
+                writeln!(wr, "closepath")?;
+                writeln!(wr, "gsave {} {} {} setrgbcolor fill grestore", r, g, b)?;
+                continue;
+            }
+            if let Some((head, tail)) = path.points.split_first() {
+                writeln!(wr, "newpath")?;
+                writeln!(wr, "  {} {} moveto", head.0, head.1)?;
+                let mut arcs = path.arcs.iter().filter(|_| self.exact_arcs).peekable();
+                let mut i = 0;
+                while i < tail.len() {
+                    // `tail[i]` is the point at index `i + 1` of the path.
+                    if let Some(arc) = arcs.peek() {
+                        if arc.start == i && arc.end > arc.start {
+                            let (from, to) = (path.points[arc.start], path.points[arc.end]);
+                            let center = arc.center(from, to);
+                            let angle = |pos: Position| {
+                                Degree::from(Radiant((pos.1 - center.1).atan2(pos.0 - center.0))).0
+                            };
+                            writeln!(
+                                wr,
+                                "  {} {} {} {} {} {}",
+                                center.0,
+                                center.1,
+                                arc.radius,
+                                angle(from),
+                                angle(to),
+                                if arc.ccw { "arc" } else { "arcn" }
+                            )?;
+                            i = arc.end;
+                            arcs.next();
+                            continue;
+                        }
+                    }
+                    writeln!(wr, r#"  {} {} lineto"#, tail[i].0, tail[i].1)?;
+                    i += 1;
+                }
+                if path.closed {
+                    writeln!(wr, "closepath")?;
+                }
+                match path.fill {
+                    Some(color) => {
+                        let (r, g, b) = color.to_unit_rgb();
+                        writeln!(wr, "gsave {} {} {} setrgbcolor fill grestore", r, g, b)?;
+                    }
+                    None => {
+                        let pen = &path.pen;
+                        if pen.color() == Color::BLACK
+                            && pen.width().is_none()
+                            && pen.dash().is_empty()
+                        {
+                            writeln!(wr, r#"stroke"#)?;
+                        } else {
+                            let (r, g, b) = pen.color().to_unit_rgb();
+                            let width = pen.width().unwrap_or(stroke_width);
+                            let dash: Vec<String> =
+                                pen.dash().iter().map(|d| d.to_string()).collect();
+                            writeln!(
+                                wr,
+                                "gsave {} {} {} setrgbcolor {} setlinewidth [{}] 0 setdash stroke grestore",
+                                r,
+                                g,
+                                b,
+                                width,
+                                dash.join(" ")
+                            )?;
+                        }
+                    }
+                }
+            }
+        }
+        writeln!(wr, "%%EOF")
+    }
+
+    /// Saves the turtle graphic as Scalable Vector Graphic (SVG).
+    pub fn save_svg<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        if let Some(canvas) = self.export_copy() {
+            return canvas.save_svg(wr);
+        }
+        let (top_left, width, height, stroke_width) = self.svg_frame();
 
         writeln!(
             wr,
             r#"<?xml version="1.0" encoding="UTF-8"?>
                 <svg xmlns="http://www.w3.org/2000/svg"
+                xmlns:xlink="http://www.w3.org/1999/xlink"
                 version="1.1" baseProfile="full"
                 viewBox="{} {} {} {}">"#,
-            top_left.0,
-            top_left.1,
-            scale * width,
-            scale * height
+            top_left.0, top_left.1, width, height
         )?;
 
-        // use a stroke width of 0.1% of the width or height of the canvas
-        let stroke_width = scale * width.max(height) / 1000.0;
+        if let Some(color) = self.background {
+            writeln!(
+                wr,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" />"#,
+                top_left.0, top_left.1, width, height, color
+            )?;
+        }
+
+        // Stamped shapes are defined once and referenced by every stamp.
+        if self.paths.iter().any(|path| path.stamp.is_some()) {
+            writeln!(wr, "<defs>")?;
+            for i in 0..self.shapes.len() {
+                self.write_svg_shape(wr, i)?;
+            }
+            writeln!(wr, "</defs>")?;
+        }
+
         writeln!(
             wr,
             r#"<g stroke="black" stroke-width="{}" fill="none">"#,
             stroke_width
         )?;
 
-        for path in self.paths.iter() {
-            if let Some((head, tail)) = path.split_first() {
-                // XXX
-                let head = Position(head.0, -1.0 * head.1);
-
-                write!(wr, r#"<path d="M{} {}"#, head.0, head.1)?;
-                for pos in tail {
-                    let pos = Position(pos.0, -1.0 * pos.1);
-                    write!(wr, r#" L{} {}"#, pos.0, pos.1)?;
-                }
-                writeln!(wr, r#"" />"#)?;
+        for (name, paths) in self.paths_by_layer() {
+            writeln!(wr, r#"<g id="{}">"#, xml_escape(name))?;
+            for path in paths {
+                self.write_svg_path(wr, path, stroke_width)?;
             }
+            writeln!(wr, r#"</g>"#)?;
         }
         writeln!(wr, r#"</g>"#)?;
 
+        if self.debug_overlay {
+            self.write_svg_debug_overlay(wr, stroke_width)?;
+        }
+
         writeln!(wr, "</svg>")
     }
+
+    /// Returns the top-left corner, width and height of the SVG view box,
+    /// and the default stroke width.
+    fn svg_frame(&self) -> (Position, f32, f32, f32) {
+        if let Some(view) = self.svg_view {
+            return view;
+        }
+        // Determine extend of canvas
+        let mut bounds = Bounds::new();
+
+        // The SVG coordinates are from top to bottom, while turtle coordinates are
+        // bottom to
+        // top. We have to convert between the two. (multiply `y` by -1.0)
+        self.foreach_position(|pos| bounds.add_position(pos), 1.0, -1.0);
+
+        let (min_width, min_height) = (100.0, 100.0);
+        let width = bounds.width().max(min_width);
+        let height = bounds.height().max(min_height);
+        let border_percent = 0.1;
+
+        let top_left = Position(
+            bounds.min_x() - border_percent * width,
+            bounds.min_y() - border_percent * height,
+        );
+
+        let scale = 1.0 + 2.0 * border_percent;
+
+        // use a stroke width of 0.1% of the width or height of the canvas
+        let stroke_width = scale * width.max(height) / 1000.0;
+        (top_left, scale * width, scale * height, stroke_width)
+    }
+
+    /// Writes the definition of the stamp shape with index `i`.
+    fn write_svg_shape<W: Write>(&self, wr: &mut W, i: usize) -> io::Result<()> {
+        let outline: Vec<String> = self.shapes[i]
+            .outline()
+            .iter()
+            .map(|&(x, y)| format!("{} {}", x, -y))
+            .collect();
+        writeln!(
+            wr,
+            r#"<path id="shape-{}" d="M{} Z" />"#,
+            i,
+            outline.join(" L")
+        )
+    }
+
+    /// Writes arrowheads showing the direction of every stroked path, and
+    /// path numbers in drawing order.
+    fn write_svg_debug_overlay<W: Write>(&self, wr: &mut W, stroke_width: f32) -> io::Result<()> {
+        let size = 5.0 * stroke_width;
+        writeln!(
+            wr,
+            r#"<g id="debug-overlay" fill="red" stroke="none" font-family="sans-serif" font-size="{}">"#,
+            3.0 * size
+        )?;
+        let stroked = self
+            .paths_by_layer()
+            .into_iter()
+            .flat_map(|(_, paths)| paths)
+            .filter(|path| path.fill.is_none() && path.points.len() > 1);
+        for (number, path) in stroked.enumerate() {
+            let mut points: Vec<Position> = path
+                .points
+                .iter()
+                .map(|pos| Position(pos.0, -pos.1))
+                .collect();
+            if path.closed {
+                points.push(points[0]);
+            }
+            writeln!(
+                wr,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                points[0].0, points[0].1, number
+            )?;
+            for segment in points.windows(2) {
+                let (a, b) = (segment[0], segment[1]);
+                let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                let len = (dx * dx + dy * dy).sqrt();
+                if len < 2.0 * size {
+                    continue;
+                }
+                // Arrowhead at the middle of the segment, pointing towards `b`.
+                let (ux, uy) = (dx / len * size, dy / len * size);
+                let tip = Position((a.0 + b.0) / 2.0 + ux / 2.0, (a.1 + b.1) / 2.0 + uy / 2.0);
+                let base = Position(tip.0 - ux, tip.1 - uy);
+                writeln!(
+                    wr,
+                    r#"<path d="M{} {} L{} {} L{} {} Z" />"#,
+                    tip.0,
+                    tip.1,
+                    base.0 - uy / 2.0,
+                    base.1 + ux / 2.0,
+                    base.0 + uy / 2.0,
+                    base.1 - ux / 2.0
+                )?;
+            }
+        }
+        writeln!(wr, r#"</g>"#)
+    }
+    /// Writes a single path as SVG `<path>` (or `<circle>`) element.
+    fn write_svg_path<W: Write>(
+        &self,
+        wr: &mut W,
+        path: &Path,
+        stroke_width: f32,
+    ) -> io::Result<()> {
+        if let (Some(label), Some(color)) = (&path.label, path.fill) {
+            let pos = Position(label.pos.0, -label.pos.1);
+            write!(wr, "<text")?;
+            if let Some(ref id) = path.id {
+                write!(wr, r#" id="{}""#, xml_escape(id))?;
+            }
+            if let Some(ref class) = path.class {
+                write!(wr, r#" class="{}""#, xml_escape(class))?;
+            }
+            if label.angle.0 != 0.0 || label.mirror {
+                // The y-axis is flipped, which mirrors the rotation.
+                write!(
+                    wr,
+                    r#" transform="translate({} {}) rotate({}){}""#,
+                    pos.0,
+                    pos.1,
+                    -label.angle.0,
+                    if label.mirror { " scale(-1 1)" } else { "" }
+                )?;
+            } else {
+                write!(wr, r#" x="{}" y="{}""#, pos.0, pos.1)?;
+            }
+            write!(wr, r#" font-size="{}""#, label.size)?;
+            match label.align {
+                TextAlign::Start => {}
+                TextAlign::Middle => write!(wr, r#" text-anchor="middle""#)?,
+                TextAlign::End => write!(wr, r#" text-anchor="end""#)?,
+            }
+            return writeln!(
+                wr,
+                r#" fill="{}" stroke="none">{}</text>"#,
+                color,
+                xml_escape(&label.text)
+            );
+        }
+        if let (Some(stamp), Some(color)) = (path.stamp, path.fill) {
+            write!(wr, "<use")?;
+            if let Some(ref id) = path.id {
+                write!(wr, r#" id="{}""#, xml_escape(id))?;
+            }
+            if let Some(ref class) = path.class {
+                write!(wr, r#" class="{}""#, xml_escape(class))?;
+            }
+            // The y-axis is flipped, which mirrors the rotation.
+            return writeln!(
+                wr,
+                r##" xlink:href="#shape-{}" transform="translate({} {}) rotate({}) scale({} {})" fill="{}" stroke="none" />"##,
+                stamp.shape,
+                stamp.pos.0,
+                -stamp.pos.1,
+                -stamp.angle.0,
+                if stamp.mirror {
+                    -stamp.scale
+                } else {
+                    stamp.scale
+                },
+                stamp.scale,
+                color
+            );
+        }
+        if let (Some((center, radius)), Some(color)) = (path.dot, path.fill) {
+            write!(wr, "<circle")?;
+            if let Some(ref id) = path.id {
+                write!(wr, r#" id="{}""#, xml_escape(id))?;
+            }
+            if let Some(ref class) = path.class {
+                write!(wr, r#" class="{}""#, xml_escape(class))?;
+            }
+            return writeln!(
+                wr,
+                r#" cx="{}" cy="{}" r="{}" fill="{}" stroke="none" />"#,
+                center.0, -center.1, radius, color
+            );
+        }
+        if path.fill.is_none() && !path.pressures.is_empty() && path.points.len() > 1 {
+            let width = path.pen.width().unwrap_or(stroke_width);
+            let (mut points, mut pressures) = (path.points.clone(), path.pressures.clone());
+            if path.closed {
+                points.push(points[0]);
+                pressures.push(pressures[0]);
+            }
+            let outline = Path {
+                points: stroke_outline(&points, &pressures, width),
+                fill: Some(path.pen.color()),
+                arcs: Vec::new(),
+                pressures: Vec::new(),
+                ..path.clone()
+            };
+            return self.write_svg_path(wr, &outline, stroke_width);
+        }
+        if let Some((head, tail)) = path.points.split_first() {
+            // XXX
+            let head = Position(head.0, -head.1);
+
+            write!(wr, "<path")?;
+            if let Some(ref id) = path.id {
+                write!(wr, r#" id="{}""#, xml_escape(id))?;
+            }
+            if let Some(ref class) = path.class {
+                write!(wr, r#" class="{}""#, xml_escape(class))?;
+            }
+            write!(wr, r#" d="M{} {}"#, head.0, head.1)?;
+            let mut arcs = path.arcs.iter().filter(|_| self.exact_arcs).peekable();
+            let mut i = 0;
+            while i < tail.len() {
+                // `tail[i]` is the point at index `i + 1` of the path.
+                if let Some(arc) = arcs.peek() {
+                    if arc.start == i && arc.end > arc.start {
+                        let end = Position(tail[arc.end - 1].0, -tail[arc.end - 1].1);
+                        // The y-axis is flipped, which mirrors the sweep.
+                        let sweep = if arc.ccw { 0 } else { 1 };
+                        write!(
+                            wr,
+                            r#" A{} {} 0 0 {} {} {}"#,
+                            arc.radius, arc.radius, sweep, end.0, end.1
+                        )?;
+                        i = arc.end;
+                        arcs.next();
+                        continue;
+                    }
+                }
+                let pos = Position(tail[i].0, -tail[i].1);
+                write!(wr, r#" L{} {}"#, pos.0, pos.1)?;
+                i += 1;
+            }
+            match path.fill {
+                Some(color) => writeln!(wr, r#" Z" fill="{}" stroke="none" />"#, color)?,
+                None => {
+                    if path.closed {
+                        write!(wr, " Z")?;
+                    }
+                    write!(wr, r#"""#)?;
+                    if path.pen.color() != Color::BLACK {
+                        write!(wr, r#" stroke="{}""#, path.pen.color())?;
+                    }
+                    if let Some(width) = path.pen.width() {
+                        write!(wr, r#" stroke-width="{}""#, width)?;
+                    }
+                    if !path.pen.dash().is_empty() {
+                        let dash: Vec<String> =
+                            path.pen.dash().iter().map(|d| d.to_string()).collect();
+                        write!(wr, r#" stroke-dasharray="{}""#, dash.join(","))?;
+                    }
+                    writeln!(wr, r#" />"#)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves the turtle graphic as Draw.io / diagrams.net (mxGraph XML) document.
+    ///
+    /// Every path becomes an unconnected edge cell whose waypoints are the
+    /// vertices of the path, so the drawing can be imported into a diagrams.net
+    /// page and edited there.
+    pub fn save_drawio<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        if let Some(canvas) = self.export_copy() {
+            return canvas.save_drawio(wr);
+        }
+        // Determine extend of canvas
+        let mut bounds = Bounds::new();
+
+        // Like SVG, diagrams.net coordinates are from top to bottom.
+        self.foreach_position(|pos| bounds.add_position(pos), 1.0, -1.0);
+
+        let (min_width, min_height) = (100.0, 100.0);
+        let width = bounds.width().max(min_width);
+        let height = bounds.height().max(min_height);
+        let border_percent = 0.1;
+
+        // diagrams.net pages start at (0, 0), so move the drawing into the
+        // positive quadrant.
+        let offset = Position(
+            border_percent * width - bounds.min_x(),
+            border_percent * height - bounds.min_y(),
+        );
+
+        let scale = 1.0 + 2.0 * border_percent;
+
+        writeln!(
+            wr,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<mxfile host="turtle-graphics-rs">
+  <diagram id="turtle" name="Page-1">
+    <mxGraphModel dx="{}" dy="{}" grid="0" page="1" pageWidth="{}" pageHeight="{}"{}>
+      <root>
+        <mxCell id="0" />"#,
+            scale * width,
+            scale * height,
+            scale * width,
+            scale * height,
+            match self.background {
+                Some(color) => format!(r#" background="{}""#, color),
+                None => String::new(),
+            }
+        )?;
+
+        // Layers are cells with the root cell as parent, in drawing order.
+        let layers = self.paths_by_layer();
+        for (i, (name, _)) in layers.iter().enumerate() {
+            writeln!(
+                wr,
+                r#"        <mxCell id="{}" value="{}" parent="0" />"#,
+                i + 1,
+                xml_escape(name)
+            )?;
+        }
+
+        let mut cell_id = layers.len() + 1;
+        for (layer_id, path) in layers
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (_, paths))| paths.iter().map(move |path| (i + 1, path)))
+        {
+            if let (Some(label), Some(color)) = (&path.label, path.fill) {
+                // A text cell, whose geometry is the unrotated text box with
+                // the baseline at its bottom. diagrams.net rotates around the
+                // center of the box, so rotated text is placed approximately.
+                let width = text::CHAR_WIDTH * label.size * label.text.chars().count() as f32;
+                let pos = Position(label.pos.0, -label.pos.1) + offset;
+                let align = match label.align {
+                    TextAlign::Start => "left",
+                    TextAlign::Middle => "center",
+                    TextAlign::End => "right",
+                };
+                let x = pos.0 - label.align.fraction() * width;
+                writeln!(
+                    wr,
+                    r#"        <mxCell id="{}" value="{}" style="text;align={};verticalAlign=bottom;spacing=0;fontSize={};fontColor={};rotation={};" vertex="1" parent="{}">"#,
+                    cell_id,
+                    xml_escape(&label.text),
+                    align,
+                    label.size,
+                    color,
+                    -label.angle.0,
+                    layer_id
+                )?;
+                writeln!(
+                    wr,
+                    r#"          <mxGeometry x="{}" y="{}" width="{}" height="{}" as="geometry" />"#,
+                    x,
+                    pos.1 - label.size,
+                    width,
+                    label.size
+                )?;
+                writeln!(wr, r#"        </mxCell>"#)?;
+                cell_id += 1;
+                continue;
+            }
+            if path.points.len() < 2 {
+                continue;
+            }
+            let mut points: Vec<Position> = path
+                .points
+                .iter()
+                .map(|pos| Position(pos.0, -pos.1) + offset)
+                .collect();
+            // Edges can't be closed, so repeat the first point instead.
+            if path.closed {
+                points.push(points[0]);
+            }
+            // Edges can't be filled either, so filled regions are emitted as
+            // outline in the fill color.
+            let style = match path.fill {
+                Some(color) => {
+                    format!("endArrow=none;html=1;rounded=0;strokeColor={};", color)
+                }
+                None => {
+                    let mut style = format!(
+                        "endArrow=none;html=1;rounded=0;strokeColor={};",
+                        path.pen.color()
+                    );
+                    if let Some(width) = path.pen.width() {
+                        style.push_str(&format!("strokeWidth={};", width));
+                    }
+                    if !path.pen.dash().is_empty() {
+                        let dash: Vec<String> =
+                            path.pen.dash().iter().map(|d| d.to_string()).collect();
+                        style.push_str(&format!("dashed=1;dashPattern={};", dash.join(" ")));
+                    }
+                    style
+                }
+            };
+            let (head, tail) = points.split_first().unwrap();
+            let (last, middle) = tail.split_last().unwrap();
+
+            writeln!(
+                wr,
+                r#"        <mxCell id="{}" value="" style="{}" edge="1" parent="{}">"#,
+                cell_id, style, layer_id
+            )?;
+            writeln!(wr, r#"          <mxGeometry relative="1" as="geometry">"#)?;
+            writeln!(
+                wr,
+                r#"            <mxPoint x="{}" y="{}" as="sourcePoint" />"#,
+                head.0, head.1
+            )?;
+            writeln!(
+                wr,
+                r#"            <mxPoint x="{}" y="{}" as="targetPoint" />"#,
+                last.0, last.1
+            )?;
+            if !middle.is_empty() {
+                writeln!(wr, r#"            <Array as="points">"#)?;
+                for pos in middle {
+                    writeln!(
+                        wr,
+                        r#"              <mxPoint x="{}" y="{}" />"#,
+                        pos.0, pos.1
+                    )?;
+                }
+                writeln!(wr, r#"            </Array>"#)?;
+            }
+            writeln!(wr, r#"          </mxGeometry>"#)?;
+            writeln!(wr, r#"        </mxCell>"#)?;
+            cell_id += 1;
+        }
+
+        writeln!(
+            wr,
+            r#"      </root>
+    </mxGraphModel>
+  </diagram>
+</mxfile>"#
+        )
+    }
+}
+
+/// Returns the outline of a stroke along `points`, which is
+/// `width * pressures[i]` wide at the `i`-th point, as closed polygon.
+fn stroke_outline(points: &[Position], pressures: &[f32], width: f32) -> Vec<Position> {
+    let normal = |a: Position, b: Position| {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len > 0.0 {
+            Some((-dy / len, dx / len))
+        } else {
+            None
+        }
+    };
+    let n = points.len();
+    let mut left = Vec::with_capacity(n);
+    let mut right = Vec::with_capacity(n);
+    let mut last_normal = (0.0, 0.0);
+    for i in 0..n {
+        // Average the normals of the adjacent segments.
+        let before = if i > 0 {
+            normal(points[i - 1], points[i])
+        } else {
+            None
+        };
+        let after = if i + 1 < n {
+            normal(points[i], points[i + 1])
+        } else {
+            None
+        };
+        let (nx, ny) = match (before, after) {
+            (Some(a), Some(b)) => {
+                let (x, y) = (a.0 + b.0, a.1 + b.1);
+                let len = (x * x + y * y).sqrt();
+                if len > 1e-6 {
+                    (x / len, y / len)
+                } else {
+                    a
+                }
+            }
+            (Some(a), None) | (None, Some(a)) => a,
+            (None, None) => last_normal,
+        };
+        last_normal = (nx, ny);
+        let half = width * pressures[i] / 2.0;
+        let p = points[i];
+        left.push(Position(p.0 + nx * half, p.1 + ny * half));
+        right.push(Position(p.0 - nx * half, p.1 - ny * half));
+    }
+    left.extend(right.into_iter().rev());
+    left
 }
 
 impl Turtle for Canvas {
     /// Move turtle forward by specified `distance`.
     fn forward<T: Into<Distance>>(&mut self, distance: T) {
-        let (dx, dy) = self.direction(distance.into());
+        self.checkpoint();
+        let (dx, dy) = self.direction(self.movement_distance(distance.into()));
         let src: Position = self.current_state().pos;
         let dst = Position(src.0 + dx, src.1 + dy);
-        if self.is_pen_down() {
-            self.line_to(dst);
-        }
-        self.current_state_mut().pos = dst;
+        self.travel_to(dst);
     }
 
     fn rotate<T: Into<Degree>>(&mut self, angle: T) {
-        let angle: Degree = angle.into();
-        self.current_state_mut().angle.0 += angle.0;
+        self.checkpoint();
+        let mut angle: Degree = angle.into();
+        if self.current_state().mirrored {
+            angle = -angle;
+        }
+        self.turn(angle.0);
     }
 
     fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
-        let (dx, dy) = self.direction(distance.into());
+        self.checkpoint();
+        let (dx, dy) = self.direction(self.movement_distance(distance.into()));
         let src: Position = self.current_state().pos;
-        let dst = Position(src.0 + dx, src.1 + dy);
+        let dst = match self.limit_position(Position(src.0 + dx, src.1 + dy)) {
+            Some(dst) => self.wrap_position(dst),
+            None => return,
+        };
         self.move_to(dst);
         self.current_state_mut().pos = dst;
+        self.add_fill_vertex(dst);
+    }
+
+    fn position(&self) -> Position {
+        self.current_state().pos
+    }
+
+    fn set_x(&mut self, x: f32) {
+        self.checkpoint();
+        let pos = self.current_state().pos;
+        self.travel_to(Position(x, pos.1));
+    }
+
+    fn set_y(&mut self, y: f32) {
+        self.checkpoint();
+        let pos = self.current_state().pos;
+        self.travel_to(Position(pos.0, y));
+    }
+
+    fn heading(&self) -> Degree {
+        self.heading_from_angle(self.current_state().angle)
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        self.checkpoint();
+        let angle = self.angle_from_heading(angle.into());
+        self.current_state_mut().angle = angle.normalized();
+    }
+
+    fn towards(&self, pos: Position) -> Degree {
+        self.heading_towards(self.current_state().pos, pos)
+            .unwrap_or_else(|| self.heading())
     }
 
     fn is_pen_down(&self) -> bool {
@@ -397,6 +2795,7 @@ impl Turtle for Canvas {
 
     /// Put the pen down.
     fn pen_down(&mut self) {
+        self.checkpoint();
         let pos = self.current_state().pos;
         self.move_to(pos);
         self.current_state_mut().pendown = true;
@@ -404,25 +2803,118 @@ impl Turtle for Canvas {
 
     /// Put the pen up.
     fn pen_up(&mut self) {
+        self.checkpoint();
         self.current_state_mut().pendown = false;
     }
 
-    /// Positions the turtle exactly at `position`.
+    /// Positions the turtle exactly at `position`, within the movement
+    /// limits. Depending on the travel mode, the turtle jumps there or draws
+    /// a line if the pen is down.
     fn goto(&mut self, position: Position) {
-        self.current_state_mut().pos = position;
-        self.move_to(position);
+        self.checkpoint();
+        if self.travel_mode == TravelMode::Draw {
+            self.travel_to(position);
+        } else {
+            self.jump_to(position);
+        }
+    }
+
+    /// Draws an arc, tessellated according to `set_arc_resolution`.
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        self.checkpoint();
+        let mut radius = self.movement_distance(radius.into()).0;
+        if self.current_state().mirrored {
+            radius = -radius;
+        }
+        let mut extent = extent.into().0;
+        if let Some(max) = self.max_distance {
+            // Limit the length of the arc.
+            let max_extent = Degree::from(Radiant(max / radius.abs())).0;
+            extent = Degree(extent).clamped(-max_extent, max_extent).0;
+        }
+        if radius == 0.0 || extent == 0.0 || !radius.is_finite() || !extent.is_finite() {
+            self.turn(extent);
+            return;
+        }
+        let state = self.current_state();
+        let pos = state.pos;
+        let (sin, cos) = state.angle.sin_cos();
+        let center = Position(pos.0 - radius * cos, pos.1 - radius * sin);
+        let start_angle = (pos.1 - center.1).atan2(pos.0 - center.0);
+        // Signed angle swept around the center; positive is counterclockwise.
+        let sweep = Radiant::from(Degree(extent)).0 * radius.signum();
+        let steps = ((extent.abs() / 360.0 * self.arc_segments as f32).ceil() as usize).max(1);
+        let point_at = |step: usize| {
+            let angle = start_angle + sweep * step as f32 / steps as f32;
+            Position(
+                center.0 + radius.abs() * angle.cos(),
+                center.1 + radius.abs() * angle.sin(),
+            )
+        };
+
+        let pen_down = self.is_pen_down();
+        let mut record_arcs = pen_down && !self.current_state().pen.is_cycling_hue();
+        let first_index = self.paths.last().unwrap().points.len() - 1;
+        if let (Some(area), AreaMode::Reject) = (self.area_limit, self.area_mode) {
+            // Reject the arc as a whole.
+            if let Some(outside) = (1..=steps).map(point_at).find(|&p| !area.contains(p)) {
+                self.area_violations.push(outside);
+                return;
+            }
+        }
+        for step in 1..=steps {
+            let exact = point_at(step);
+            if self.area_limit.is_some_and(|area| !area.contains(exact)) {
+                // Cut off by the area limit, so no longer an exact arc.
+                record_arcs = false;
+            }
+            self.travel_to(exact);
+        }
+        if self.wrap_around.is_some() {
+            // May be split wherever the arc leaves the world.
+            record_arcs = false;
+        }
+        if self.paths.last().unwrap().points.len() != first_index + 1 + steps {
+            // Points were merged with collinear segments.
+            record_arcs = false;
+        }
+        if !pen_down {
+            let dst = self.current_state().pos;
+            self.move_to(dst);
+        }
+        self.turn(extent * radius.signum());
+
+        if record_arcs {
+            // Split into pieces of at most 180 degree, so that an arc never
+            // starts and ends at the same point.
+            let pieces = ((extent.abs() / 180.0).ceil() as usize).max(1);
+            let path = self.paths.last_mut().unwrap();
+            for k in 0..pieces {
+                path.arcs.push(ArcSegment {
+                    start: first_index + steps * k / pieces,
+                    end: first_index + steps * (k + 1) / pieces,
+                    radius: radius.abs(),
+                    ccw: sweep > 0.0,
+                });
+            }
+        }
     }
 
     /// Push current turtle state on stack.
     fn push(&mut self) {
+        self.checkpoint();
         let state = self.current_state_mut().clone();
         self.states.push(state);
     }
 
-    /// Restore previously saved turtle state.
-    fn pop(&mut self) {
+    /// Restore previously saved turtle state, including the pen.
+    fn pop(&mut self) -> bool {
+        if self.states.len() < 2 {
+            return false;
+        }
+        self.checkpoint();
         self.states.pop();
-        let pos = self.current_state().pos;
-        self.move_to(pos);
+        self.restyle_current_path();
+        true
     }
 }
@@ -0,0 +1,881 @@
+//! An interpreter for a subset of the Logo language, drawing with any
+//! `Turtle`.
+//!
+//! Supported are the turtle commands `FORWARD` (`FD`), `BACK` (`BK`),
+//! `LEFT` (`LT`), `RIGHT` (`RT`), `PENUP` (`PU`), `PENDOWN` (`PD`), `HOME`,
+//! `SETXY`, `SETX`, `SETY` and `SETHEADING` (`SETH`), the control
+//! structures `REPEAT`, `IF`, `IFELSE`, `STOP` and `OUTPUT` (`OP`),
+//! procedures defined with `TO ... END`, and variables set with `MAKE` or
+//! passed as inputs. Expressions are numbers, `:name`, the infix operators
+//! `+ - * / < > =` and the reporters `REPCOUNT`, `XCOR`, `YCOR`, `HEADING`,
+//! `SQRT`, `SIN`, `COS` and `ABS`. Names are case insensitive and `;`
+//! starts a comment.
+//!
+//! All values are numbers; comparisons give 1 for true and 0 for false, and
+//! `IF` takes any number other than 0 as true. Headings are those of the
+//! turtle, so draw on a `Canvas` in `HeadingMode::Compass` for Logo's
+//! clockwise headings.
+//!
+//! For scripting a host program, `ScriptFile` reloads a program from a file
+//! when it changes, and `Program::run_with` passes parameters as variables.
+//! Untrusted programs, e.g. in a web playground, can be run with `Limits`
+//! on the steps, time and lines they may take.
+
+use crate::{Position, Turtle};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Maximum nesting of brackets, parentheses and operators in the source,
+/// to reject absurd programs instead of overflowing the stack.
+const MAX_NESTING: usize = 100;
+
+/// Maximum nesting of blocks and expressions while running, including those
+/// of procedure calls, to report runaway recursion instead of overflowing
+/// the stack. Small enough for the stack of a thread in a debug build.
+const MAX_DEPTH: usize = 256;
+
+/// Largest count of a `REPEAT`.
+const MAX_REPEAT: f32 = 1e9;
+
+/// Limits on running a program, so that untrusted programs can't run
+/// forever or draw until memory runs out. Exceeding one stops the program
+/// with an error for which `LogoError::is_limit_exceeded` is true; what was
+/// drawn until then is kept. All limits are off by default.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Limits {
+    /// Maximum number of statements and `REPEAT` iterations run.
+    pub max_steps: Option<u64>,
+    /// Maximum time the program may run.
+    pub max_time: Option<Duration>,
+    /// Maximum number of moves with the pen down, each adding at most one
+    /// line segment to the drawing.
+    pub max_segments: Option<usize>,
+}
+
+/// An error while parsing or running a Logo program.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogoError {
+    pub message: String,
+    incomplete: bool,
+    limit_exceeded: bool,
+}
+
+impl LogoError {
+    fn new(message: String) -> LogoError {
+        LogoError {
+            message,
+            incomplete: false,
+            limit_exceeded: false,
+        }
+    }
+
+    fn incomplete(message: String) -> LogoError {
+        LogoError {
+            message,
+            incomplete: true,
+            limit_exceeded: false,
+        }
+    }
+
+    fn limit_exceeded(message: String) -> LogoError {
+        LogoError {
+            message,
+            incomplete: false,
+            limit_exceeded: true,
+        }
+    }
+
+    /// Returns true if the program ended within a `[` block or a procedure
+    /// definition, so that more input may complete it.
+    pub fn is_incomplete(&self) -> bool {
+        self.incomplete
+    }
+
+    /// Returns true if the program was stopped for exceeding its `Limits`.
+    pub fn is_limit_exceeded(&self) -> bool {
+        self.limit_exceeded
+    }
+}
+
+impl fmt::Display for LogoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for LogoError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// A command or procedure name, upper case.
+    Word(String),
+    Number(f32),
+    /// `:name`
+    Var(String),
+    /// `"name`
+    Quoted(String),
+    Open,
+    Close,
+    LParen,
+    RParen,
+    Op(char),
+    /// A `-` directly in front of an operand, after whitespace.
+    Neg,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, LogoError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_delimiter = |c: char| c.is_whitespace() || "[]()+-*/<>=;".contains(c);
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        i += 1;
+        match c {
+            ';' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            c if c.is_whitespace() => {}
+            '[' => tokens.push(Token::Open),
+            ']' => tokens.push(Token::Close),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            '-' if (start == 0
+                || chars[start - 1].is_whitespace()
+                || "[(".contains(chars[start - 1]))
+                && chars.get(i).is_some_and(|c| !c.is_whitespace()) =>
+            {
+                tokens.push(Token::Neg)
+            }
+            '+' | '-' | '*' | '/' | '<' | '>' | '=' => tokens.push(Token::Op(c)),
+            _ => {
+                while i < chars.len() && !is_delimiter(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let token = if let Some(name) = word.strip_prefix(':') {
+                    Token::Var(name.to_uppercase())
+                } else if let Some(name) = word.strip_prefix('"') {
+                    Token::Quoted(name.to_uppercase())
+                } else if c.is_ascii_digit() || c == '.' {
+                    let number = word
+                        .parse()
+                        .map_err(|_| LogoError::new(format!("invalid number {}", word)))?;
+                    Token::Number(number)
+                } else {
+                    Token::Word(word.to_uppercase())
+                };
+                tokens.push(token);
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(f32),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Clone, Debug)]
+enum Stmt {
+    Call(String, Vec<Expr>),
+    Repeat(Expr, Vec<Stmt>),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    Make(String, Expr),
+    Stop,
+    Output(Expr),
+}
+
+#[derive(Clone, Debug)]
+struct Procedure {
+    params: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+/// Number of inputs of the built-in commands and reporters.
+fn builtin_arity(name: &str) -> Option<usize> {
+    Some(match name {
+        "FORWARD" | "FD" | "BACK" | "BK" | "LEFT" | "LT" | "RIGHT" | "RT" => 1,
+        "SETX" | "SETY" | "SETHEADING" | "SETH" => 1,
+        "SETXY" => 2,
+        "PENUP" | "PU" | "PENDOWN" | "PD" | "HOME" => 0,
+        "REPCOUNT" | "XCOR" | "YCOR" | "HEADING" => 0,
+        "SQRT" | "SIN" | "COS" | "ABS" => 1,
+        _ => return None,
+    })
+}
+
+fn is_reporter(name: &str) -> bool {
+    matches!(
+        name,
+        "REPCOUNT" | "XCOR" | "YCOR" | "HEADING" | "SQRT" | "SIN" | "COS" | "ABS"
+    )
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Number of inputs of every procedure defined in the program.
+    arities: HashMap<String, usize>,
+    /// Current nesting, see `MAX_NESTING`.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn arity(&self, name: &str) -> Option<usize> {
+        builtin_arity(name).or_else(|| self.arities.get(name).cloned())
+    }
+
+    /// Goes one level deeper, failing beyond `MAX_NESTING`. Callers reset
+    /// `depth` when leaving; on errors parsing stops anyway.
+    fn enter(&mut self) -> Result<(), LogoError> {
+        if self.depth >= MAX_NESTING {
+            return Err(LogoError::limit_exceeded("too deeply nested".to_string()));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, LogoError> {
+        if self.next() != Some(Token::Open) {
+            return Err(LogoError::new("expected [".to_string()));
+        }
+        self.enter()?;
+        let mut body = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Close) => {
+                    self.pos += 1;
+                    self.depth -= 1;
+                    return Ok(body);
+                }
+                None => return Err(LogoError::incomplete("missing ]".to_string())),
+                _ => body.push(self.statement()?),
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Stmt, LogoError> {
+        let name = match self.next() {
+            Some(Token::Word(name)) => name,
+            Some(token) => {
+                return Err(LogoError::new(format!(
+                    "expected a command, found {:?}",
+                    token
+                )))
+            }
+            None => return Err(LogoError::new("expected a command".to_string())),
+        };
+        Ok(match name.as_str() {
+            "REPEAT" => Stmt::Repeat(self.expr()?, self.block()?),
+            "IF" => Stmt::If(self.expr()?, self.block()?, Vec::new()),
+            "IFELSE" => Stmt::If(self.expr()?, self.block()?, self.block()?),
+            "MAKE" => match self.next() {
+                Some(Token::Quoted(var)) => Stmt::Make(var, self.expr()?),
+                _ => return Err(LogoError::new("MAKE expects a quoted name".to_string())),
+            },
+            "STOP" => Stmt::Stop,
+            "OUTPUT" | "OP" => Stmt::Output(self.expr()?),
+            "TO" | "END" => {
+                return Err(LogoError::new(format!(
+                    "{} is only allowed at the top level",
+                    name
+                )))
+            }
+            _ if is_reporter(&name) => {
+                return Err(LogoError::new(format!(
+                    "don't know what to do with {}",
+                    name
+                )))
+            }
+            _ => Stmt::Call(name.clone(), self.args(&name)?),
+        })
+    }
+
+    fn args(&mut self, name: &str) -> Result<Vec<Expr>, LogoError> {
+        let arity = self
+            .arity(name)
+            .ok_or_else(|| LogoError::new(format!("I don't know how to {}", name)))?;
+        (0..arity).map(|_| self.expr()).collect()
+    }
+
+    /// Parses a procedure definition after `TO`.
+    fn procedure(&mut self) -> Result<(String, Procedure), LogoError> {
+        let name = match self.next() {
+            Some(Token::Word(name)) => name,
+            _ => return Err(LogoError::new("TO expects a name".to_string())),
+        };
+        let mut params = Vec::new();
+        while let Some(Token::Var(param)) = self.peek() {
+            params.push(param.clone());
+            self.pos += 1;
+        }
+        let mut body = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Word(word)) if word == "END" => {
+                    self.pos += 1;
+                    return Ok((name, Procedure { params, body }));
+                }
+                None => return Err(LogoError::incomplete(format!("missing END of {}", name))),
+                _ => body.push(self.statement()?),
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, LogoError> {
+        let lhs = self.sum()?;
+        match self.peek() {
+            Some(&Token::Op(op)) if "<>=".contains(op) => {
+                self.pos += 1;
+                self.enter()?;
+                let rhs = self.sum()?;
+                self.depth -= 1;
+                Ok(Expr::Binary(op, Box::new(lhs), Box::new(rhs)))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    /// Every operator of a chain nests the expression one level deeper, as
+    /// it is evaluated recursively.
+    fn sum(&mut self) -> Result<Expr, LogoError> {
+        let depth = self.depth;
+        let mut lhs = self.product()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op != '+' && op != '-' {
+                break;
+            }
+            self.pos += 1;
+            self.enter()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.product()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn product(&mut self) -> Result<Expr, LogoError> {
+        let depth = self.depth;
+        let mut lhs = self.unary()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op != '*' && op != '/' {
+                break;
+            }
+            self.pos += 1;
+            self.enter()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, LogoError> {
+        self.enter()?;
+        let expr = self.operand();
+        self.depth -= 1;
+        expr
+    }
+
+    fn operand(&mut self) -> Result<Expr, LogoError> {
+        match self.next() {
+            Some(Token::Neg) | Some(Token::Op('-')) => Ok(Expr::Neg(Box::new(self.unary()?))),
+            Some(Token::Number(x)) => Ok(Expr::Number(x)),
+            Some(Token::Var(name)) => Ok(Expr::Var(name)),
+            Some(Token::LParen) => {
+                let expr = self.expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => Err(LogoError::new("missing )".to_string())),
+                }
+            }
+            Some(Token::Word(name)) => {
+                let args = self.args(&name)?;
+                Ok(Expr::Call(name, args))
+            }
+            Some(token) => Err(LogoError::new(format!(
+                "expected a value, found {:?}",
+                token
+            ))),
+            None => Err(LogoError::new("expected a value".to_string())),
+        }
+    }
+}
+
+/// A parsed Logo program.
+#[derive(Clone, Debug)]
+pub struct Program {
+    procedures: HashMap<String, Procedure>,
+    body: Vec<Stmt>,
+}
+
+impl Program {
+    /// Parses a Logo program. Procedures may be used before they are
+    /// defined.
+    pub fn parse(source: &str) -> Result<Program, LogoError> {
+        Program::parse_with(source, HashMap::new())
+    }
+
+    /// Parses a Logo program which may call procedures with the given
+    /// numbers of inputs, defined elsewhere.
+    fn parse_with(source: &str, mut arities: HashMap<String, usize>) -> Result<Program, LogoError> {
+        let tokens = tokenize(source)?;
+
+        // Procedure inputs are needed to parse calls.
+        for (i, token) in tokens.iter().enumerate() {
+            if let (Token::Word(to), Some(Token::Word(name))) = (token, tokens.get(i + 1)) {
+                if to == "TO" {
+                    let params = tokens[i + 2..]
+                        .iter()
+                        .take_while(|token| matches!(token, Token::Var(_)))
+                        .count();
+                    arities.insert(name.clone(), params);
+                }
+            }
+        }
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            arities,
+            depth: 0,
+        };
+        let mut program = Program {
+            procedures: HashMap::new(),
+            body: Vec::new(),
+        };
+        while let Some(token) = parser.peek() {
+            if *token == Token::Word("TO".to_string()) {
+                parser.pos += 1;
+                let (name, procedure) = parser.procedure()?;
+                if builtin_arity(&name).is_some() {
+                    return Err(LogoError::new(format!("{} is a primitive", name)));
+                }
+                program.procedures.insert(name, procedure);
+            } else {
+                program.body.push(parser.statement()?);
+            }
+        }
+        Ok(program)
+    }
+
+    /// Runs the program, drawing with `turtle`. Stops at the first error;
+    /// what was drawn until then is kept.
+    pub fn run<T: Turtle>(&self, turtle: &mut T) -> Result<(), LogoError> {
+        self.run_with(turtle, &[])
+    }
+
+    /// Runs the program like `run`, with the global variables `vars` set,
+    /// e.g. to pass parameters from the host program.
+    pub fn run_with<T: Turtle>(
+        &self,
+        turtle: &mut T,
+        vars: &[(&str, f32)],
+    ) -> Result<(), LogoError> {
+        self.run_limited(turtle, vars, &Limits::default())
+    }
+
+    /// Runs the program like `run_with`, stopping it once it exceeds
+    /// `limits`.
+    pub fn run_limited<T: Turtle>(
+        &self,
+        turtle: &mut T,
+        vars: &[(&str, f32)],
+        limits: &Limits,
+    ) -> Result<(), LogoError> {
+        let globals = vars
+            .iter()
+            .map(|&(name, value)| (name.to_uppercase(), value))
+            .collect();
+        let mut machine = Machine::new(&self.procedures, turtle, globals, limits);
+        machine.block(&self.body)?;
+        Ok(())
+    }
+}
+
+/// A Logo program read from a file, so that a drawing can be edited and
+/// re-run while the host program keeps running.
+#[derive(Clone, Debug)]
+pub struct ScriptFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    program: Program,
+}
+
+impl ScriptFile {
+    /// Reads and parses the program in the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ScriptFile, LogoError> {
+        let path = path.as_ref().to_path_buf();
+        let (modified, program) = ScriptFile::read(&path)?;
+        Ok(ScriptFile {
+            path,
+            modified,
+            program,
+        })
+    }
+
+    /// Reads and parses the file again if it was modified since it was
+    /// last read, and returns whether it was. On errors, the previous
+    /// program is kept.
+    pub fn reload(&mut self) -> Result<bool, LogoError> {
+        let modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        if modified.is_some() && modified == self.modified {
+            return Ok(false);
+        }
+        let (modified, program) = ScriptFile::read(&self.path)?;
+        self.modified = modified;
+        self.program = program;
+        Ok(true)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the program as last read successfully.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    fn read(path: &Path) -> Result<(Option<SystemTime>, Program), LogoError> {
+        let error = |err: io::Error| LogoError::new(format!("{}: {}", path.display(), err));
+        let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        let source = fs::read_to_string(path).map_err(error)?;
+        Ok((modified, Program::parse(&source)?))
+    }
+}
+
+/// Procedures and global variables kept from one program to the next, e.g.
+/// for entering programs line by line.
+#[derive(Clone, Debug, Default)]
+pub struct Workspace {
+    procedures: HashMap<String, Procedure>,
+    globals: HashMap<String, f32>,
+    limits: Limits,
+}
+
+impl Workspace {
+    pub fn new() -> Workspace {
+        Workspace::default()
+    }
+
+    /// Sets the limits every program is run with.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Parses and runs a Logo program, which may use the procedures and
+    /// variables of earlier programs, drawing with `turtle`. Procedures
+    /// defined by the program and variables it set are kept, even if running
+    /// it fails.
+    pub fn run<T: Turtle>(&mut self, source: &str, turtle: &mut T) -> Result<(), LogoError> {
+        let program = self.parse(source)?;
+        self.execute(program, turtle)
+    }
+
+    /// Parses a Logo program, which may call the procedures of earlier
+    /// programs. Together with `execute`, this does what `run` does, e.g. to
+    /// time both steps separately.
+    pub fn parse(&self, source: &str) -> Result<Program, LogoError> {
+        let arities = self
+            .procedures
+            .iter()
+            .map(|(name, procedure)| (name.clone(), procedure.params.len()))
+            .collect();
+        Program::parse_with(source, arities)
+    }
+
+    /// Runs a program returned by `parse`, like `run`.
+    pub fn execute<T: Turtle>(
+        &mut self,
+        program: Program,
+        turtle: &mut T,
+    ) -> Result<(), LogoError> {
+        self.procedures.extend(program.procedures);
+        let globals = mem::take(&mut self.globals);
+        let mut machine = Machine::new(&self.procedures, turtle, globals, &self.limits);
+        let result = machine.block(&program.body);
+        self.globals = mem::take(&mut machine.scopes[0]);
+        result.map(|_| ())
+    }
+}
+
+/// Parses and runs a Logo program, drawing with `turtle`.
+pub fn run<T: Turtle>(source: &str, turtle: &mut T) -> Result<(), LogoError> {
+    Program::parse(source)?.run(turtle)
+}
+
+/// How a block of statements finished.
+enum Flow {
+    Next,
+    Stop,
+    Output(f32),
+}
+
+struct Machine<'a, T: Turtle> {
+    procedures: &'a HashMap<String, Procedure>,
+    turtle: &'a mut T,
+    /// Variables of the procedure calls, the global ones first. Lookups are
+    /// dynamically scoped, as in Logo.
+    scopes: Vec<HashMap<String, f32>>,
+    /// Iteration of the innermost `REPEAT`, from 1.
+    repcount: usize,
+    limits: &'a Limits,
+    deadline: Option<Instant>,
+    steps: u64,
+    segments: usize,
+    /// Current nesting, see `MAX_DEPTH`.
+    depth: usize,
+}
+
+impl<'a, T: Turtle> Machine<'a, T> {
+    fn new(
+        procedures: &'a HashMap<String, Procedure>,
+        turtle: &'a mut T,
+        globals: HashMap<String, f32>,
+        limits: &'a Limits,
+    ) -> Machine<'a, T> {
+        Machine {
+            procedures,
+            turtle,
+            scopes: vec![globals],
+            repcount: 0,
+            limits,
+            deadline: limits.max_time.map(|time| Instant::now() + time),
+            steps: 0,
+            segments: 0,
+            depth: 0,
+        }
+    }
+
+    /// Counts a step, failing once the step or time limit is exceeded.
+    fn step(&mut self) -> Result<(), LogoError> {
+        self.steps += 1;
+        if self.limits.max_steps.is_some_and(|max| self.steps > max) {
+            return Err(LogoError::limit_exceeded(
+                "too many steps, stopped".to_string(),
+            ));
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
+        {
+            return Err(LogoError::limit_exceeded(
+                "ran out of time, stopped".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Counts a move, failing before it would exceed the segment limit if
+    /// the pen is down.
+    fn segment(&mut self) -> Result<(), LogoError> {
+        if self.turtle.is_pen_down() {
+            if self
+                .limits
+                .max_segments
+                .is_some_and(|max| self.segments >= max)
+            {
+                return Err(LogoError::limit_exceeded(
+                    "too many lines, stopped".to_string(),
+                ));
+            }
+            self.segments += 1;
+        }
+        Ok(())
+    }
+
+    /// Goes one level deeper, failing beyond `MAX_DEPTH`.
+    fn enter(&mut self) -> Result<(), LogoError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(LogoError::limit_exceeded(
+                "too deeply nested, stopped".to_string(),
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn block(&mut self, body: &[Stmt]) -> Result<Flow, LogoError> {
+        self.enter()?;
+        let flow = self.statements(body);
+        self.depth -= 1;
+        flow
+    }
+
+    fn statements(&mut self, body: &[Stmt]) -> Result<Flow, LogoError> {
+        for stmt in body {
+            match self.statement(stmt)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<Flow, LogoError> {
+        self.step()?;
+        match *stmt {
+            Stmt::Call(ref name, ref args) => {
+                self.call(name, args)?;
+            }
+            Stmt::Repeat(ref count, ref body) => {
+                let count = self.eval(count)?;
+                if !count.is_finite() || count.round() > MAX_REPEAT {
+                    return Err(LogoError::new(format!(
+                        "REPEAT doesn't like {} as input",
+                        count
+                    )));
+                }
+                let outer = self.repcount;
+                for i in 1..=count.round().max(0.0) as usize {
+                    self.step()?;
+                    self.repcount = i;
+                    match self.block(body)? {
+                        Flow::Next => {}
+                        flow => {
+                            self.repcount = outer;
+                            return Ok(flow);
+                        }
+                    }
+                }
+                self.repcount = outer;
+            }
+            Stmt::If(ref condition, ref then, ref otherwise) => {
+                let body = if self.eval(condition)? != 0.0 {
+                    then
+                } else {
+                    otherwise
+                };
+                return self.block(body);
+            }
+            Stmt::Make(ref name, ref value) => {
+                let value = self.eval(value)?;
+                // Unknown variables become global.
+                let scope = self
+                    .scopes
+                    .iter()
+                    .rposition(|scope| scope.contains_key(name))
+                    .unwrap_or(0);
+                self.scopes[scope].insert(name.clone(), value);
+            }
+            Stmt::Stop => return Ok(Flow::Stop),
+            Stmt::Output(ref value) => return Ok(Flow::Output(self.eval(value)?)),
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Calls a command or reporter, returning its output if any.
+    fn call(&mut self, name: &str, args: &[Expr]) -> Result<Option<f32>, LogoError> {
+        let args = args
+            .iter()
+            .map(|arg| self.eval(arg))
+            .collect::<Result<Vec<f32>, LogoError>>()?;
+        if let "FORWARD" | "FD" | "BACK" | "BK" | "HOME" | "SETXY" | "SETX" | "SETY" = name {
+            self.segment()?;
+        }
+        let turtle = &mut *self.turtle;
+        match name {
+            "FORWARD" | "FD" => turtle.forward(args[0]),
+            "BACK" | "BK" => turtle.backward(args[0]),
+            "LEFT" | "LT" => turtle.left(args[0]),
+            "RIGHT" | "RT" => turtle.right(args[0]),
+            "PENUP" | "PU" => turtle.pen_up(),
+            "PENDOWN" | "PD" => turtle.pen_down(),
+            "HOME" => turtle.home(),
+            "SETXY" => turtle.goto(Position(args[0], args[1])),
+            "SETX" => turtle.set_x(args[0]),
+            "SETY" => turtle.set_y(args[0]),
+            "SETHEADING" | "SETH" => turtle.set_heading(args[0]),
+            "REPCOUNT" => return Ok(Some(self.repcount as f32)),
+            "XCOR" => return Ok(Some(turtle.position().0)),
+            "YCOR" => return Ok(Some(turtle.position().1)),
+            "HEADING" => return Ok(Some(turtle.heading().0)),
+            "SQRT" => return Ok(Some(args[0].sqrt())),
+            "SIN" => return Ok(Some(args[0].to_radians().sin())),
+            "COS" => return Ok(Some(args[0].to_radians().cos())),
+            "ABS" => return Ok(Some(args[0].abs())),
+            _ => return self.call_procedure(name, args),
+        }
+        Ok(None)
+    }
+
+    fn call_procedure(&mut self, name: &str, args: Vec<f32>) -> Result<Option<f32>, LogoError> {
+        let procedure = self
+            .procedures
+            .get(name)
+            .ok_or_else(|| LogoError::new(format!("I don't know how to {}", name)))?;
+        self.scopes
+            .push(procedure.params.iter().cloned().zip(args).collect());
+        let outer = self.repcount;
+        let flow = self.block(&procedure.body);
+        self.repcount = outer;
+        self.scopes.pop();
+        match flow? {
+            Flow::Output(value) => Ok(Some(value)),
+            _ => Ok(None),
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<f32, LogoError> {
+        self.enter()?;
+        let value = self.value(expr);
+        self.depth -= 1;
+        value
+    }
+
+    fn value(&mut self, expr: &Expr) -> Result<f32, LogoError> {
+        Ok(match *expr {
+            Expr::Number(x) => x,
+            Expr::Var(ref name) => *self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))
+                .ok_or_else(|| LogoError::new(format!("{} has no value", name)))?,
+            Expr::Neg(ref a) => -self.eval(a)?,
+            Expr::Binary(op, ref a, ref b) => {
+                let (a, b) = (self.eval(a)?, self.eval(b)?);
+                let truth = |holds: bool| if holds { 1.0 } else { 0.0 };
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    '<' => truth(a < b),
+                    '>' => truth(a > b),
+                    '=' => truth(a == b),
+                    _ => unreachable!(),
+                }
+            }
+            Expr::Call(ref name, ref args) => self
+                .call(name, args)?
+                .ok_or_else(|| LogoError::new(format!("{} didn't output", name)))?,
+        })
+    }
+}
@@ -0,0 +1,733 @@
+//! Lindenmayer systems, drawn with a turtle.
+//!
+//! An L-system rewrites every symbol of a word by its rule in each
+//! iteration, starting with the axiom. The resulting word is interpreted
+//! as a sequence of turtle commands by an `Interpreter`, e.g. the Koch
+//! curve is `LSystem::parse("F", &["F -> F+F--F+F"])` drawn with an angle
+//! of 60 degree.
+//!
+//! Stochastic L-systems have several weighted successors for a symbol, of
+//! which one is picked at random for every occurrence. The random choices
+//! come from a `RandomSource`, so equal seeds give equal plants.
+//! Context-sensitive rules only apply next to given neighbours, which lets
+//! signals propagate through a plant.
+
+use crate::rng::RandomSource;
+use crate::Turtle;
+use std::collections::HashMap;
+
+/// A production of an L-system.
+#[derive(Clone, Debug)]
+struct Rule {
+    /// Symbols which must precede the rewritten one, or empty.
+    left: Vec<char>,
+    /// Symbols which must follow the rewritten one, or empty.
+    right: Vec<char>,
+    weight: f32,
+    successor: String,
+}
+
+impl Rule {
+    fn has_context(&self) -> bool {
+        !self.left.is_empty() || !self.right.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct LSystem {
+    axiom: String,
+    /// Rules of each symbol, in the order they were added.
+    rules: HashMap<char, Vec<Rule>>,
+    /// Symbols skipped when matching contexts.
+    ignored: Vec<char>,
+}
+
+impl LSystem {
+    /// Creates an L-system without rules, which keeps all symbols.
+    pub fn new(axiom: &str) -> LSystem {
+        LSystem {
+            axiom: axiom.to_string(),
+            rules: HashMap::new(),
+            ignored: Vec::new(),
+        }
+    }
+
+    /// Creates an L-system from rules written as `F -> F+F--F+F`, with a
+    /// single symbol left of the arrow. A weight in parentheses after the
+    /// symbol, e.g. `F (0.3) -> F[+F]F`, makes the rule one of several
+    /// alternatives; rules for the same symbol without a weight have a
+    /// weight of 1. Contexts are written as `A < B > C -> ...`, where either
+    /// side may be left out and may consist of several symbols, see
+    /// `add_context_rule`; a weight follows the symbol, as in
+    /// `A < B (0.5) > C -> ...`. Whitespace is ignored. Returns `None` if a rule
+    /// is malformed or its weight isn't a positive number.
+    pub fn parse(axiom: &str, rules: &[&str]) -> Option<LSystem> {
+        let mut system = LSystem::new(axiom);
+        for rule in rules {
+            let (predecessor, successor) = rule.split_once("->")?;
+            let (left, predecessor) = match predecessor.split_once('<') {
+                Some((left, predecessor)) => (without_whitespace(left), predecessor),
+                None => (Vec::new(), predecessor),
+            };
+            let (predecessor, right) = match predecessor.split_once('>') {
+                Some((predecessor, right)) => (predecessor, without_whitespace(right)),
+                None => (predecessor, Vec::new()),
+            };
+            if left.iter().chain(&right).any(|&c| c == '(' || c == ')') {
+                return None;
+            }
+            let (symbol, weight) = match predecessor.split_once('(') {
+                Some((symbol, weight)) => {
+                    let weight: f32 = weight.trim().strip_suffix(')')?.trim().parse().ok()?;
+                    if !(weight > 0.0 && weight.is_finite()) {
+                        return None;
+                    }
+                    (symbol, weight)
+                }
+                None => (predecessor, 1.0),
+            };
+            let mut symbol = symbol.trim().chars();
+            match (symbol.next(), symbol.next()) {
+                (Some(symbol), None) => {
+                    system.rules.entry(symbol).or_default().push(Rule {
+                        left,
+                        right,
+                        weight,
+                        successor: without_whitespace(successor).into_iter().collect(),
+                    });
+                }
+                _ => return None,
+            }
+        }
+        Some(system)
+    }
+
+    /// Rewrites `symbol` by `successor`, replacing any previous rules for
+    /// `symbol` without context.
+    pub fn add_rule(&mut self, symbol: char, successor: &str) {
+        let rules = self.rules.entry(symbol).or_default();
+        rules.retain(Rule::has_context);
+        rules.push(Rule {
+            left: Vec::new(),
+            right: Vec::new(),
+            weight: 1.0,
+            successor: successor.to_string(),
+        });
+    }
+
+    /// Adds `successor` as an alternative for `symbol`, chosen with a
+    /// probability proportional to `weight` by `iterate_random`. Successors
+    /// with a weight of zero or less are never chosen.
+    pub fn add_weighted_rule(&mut self, symbol: char, weight: f32, successor: &str) {
+        self.rules.entry(symbol).or_default().push(Rule {
+            left: Vec::new(),
+            right: Vec::new(),
+            weight,
+            successor: successor.to_string(),
+        });
+    }
+
+    /// Rewrites `symbol` by `successor` only where it is preceded by the
+    /// symbols `left` and followed by the symbols `right`; an empty context
+    /// always matches. Where a rule with context matches, it takes
+    /// precedence over the rules without.
+    ///
+    /// Contexts follow the branching structure of the word: the left
+    /// context continues before the `[` of the branch a symbol is in, and
+    /// skips branches in between, as does the right context. A right context
+    /// doesn't extend past the end of the branch. Symbols set with
+    /// `set_ignored` are skipped.
+    pub fn add_context_rule(&mut self, left: &str, symbol: char, right: &str, successor: &str) {
+        self.rules.entry(symbol).or_default().push(Rule {
+            left: left.chars().collect(),
+            right: right.chars().collect(),
+            weight: 1.0,
+            successor: successor.to_string(),
+        });
+    }
+
+    /// Sets the symbols skipped when matching contexts, typically the turns,
+    /// e.g. `"+-F"`.
+    pub fn set_ignored(&mut self, symbols: &str) {
+        self.ignored = symbols.chars().collect();
+    }
+
+    /// Returns true if any symbol has more than one successor.
+    pub fn is_stochastic(&self) -> bool {
+        self.rules.values().any(|rules| {
+            let free = rules.iter().filter(|rule| !rule.has_context()).count();
+            free > 1 || rules.len() - free > 1
+        })
+    }
+
+    pub fn axiom(&self) -> &str {
+        &self.axiom
+    }
+
+    /// Returns the word after rewriting the axiom `n` times. Symbols with
+    /// several successors are always rewritten by the first one.
+    pub fn iterate(&self, n: usize) -> String {
+        self.rewrite(n, |rules| rules.first().map(|rule| &rule.successor))
+    }
+
+    /// Returns the word after rewriting the axiom `n` times, choosing among
+    /// the successors of a symbol at random, by weight.
+    pub fn iterate_random<R: RandomSource + ?Sized>(&self, n: usize, rng: &mut R) -> String {
+        self.rewrite(n, |rules| {
+            let total: f32 = rules.iter().map(|rule| rule.weight.max(0.0)).sum();
+            if total <= 0.0 {
+                return None;
+            }
+            let mut x = rng.range(0.0, total);
+            let mut chosen = None;
+            for rule in rules.iter().filter(|rule| rule.weight > 0.0) {
+                chosen = Some(&rule.successor);
+                x -= rule.weight;
+                if x < 0.0 {
+                    break;
+                }
+            }
+            chosen
+        })
+    }
+
+    /// Rewrites the axiom `n` times, by the successor `choose` picks among
+    /// the rules matching each symbol. Symbols are kept if it picks none.
+    fn rewrite<'a, F>(&'a self, n: usize, mut choose: F) -> String
+    where
+        F: FnMut(&[&'a Rule]) -> Option<&'a String>,
+    {
+        let mut word: Vec<char> = self.axiom.chars().collect();
+        let mut matching = Vec::new();
+        for _ in 0..n {
+            let mut next = String::with_capacity(word.len());
+            for (i, &c) in word.iter().enumerate() {
+                matching.clear();
+                if let Some(rules) = self.rules.get(&c) {
+                    matching.extend(rules.iter().filter(|rule| {
+                        rule.has_context()
+                            && self.left_matches(&word, i, &rule.left)
+                            && self.right_matches(&word, i, &rule.right)
+                    }));
+                    if matching.is_empty() {
+                        matching.extend(rules.iter().filter(|rule| !rule.has_context()));
+                    }
+                }
+                match choose(&matching) {
+                    Some(successor) => next.push_str(successor),
+                    None => next.push(c),
+                }
+            }
+            word = next.chars().collect();
+        }
+        word.into_iter().collect()
+    }
+
+    /// Returns true if the symbols before `word[i]` end with `context`.
+    fn left_matches(&self, word: &[char], i: usize, context: &[char]) -> bool {
+        let mut pos = i;
+        for &expected in context.iter().rev() {
+            loop {
+                if pos == 0 {
+                    return false;
+                }
+                pos -= 1;
+                match word[pos] {
+                    '[' => {}
+                    ']' => pos = matching_bracket(word, pos, false),
+                    c if self.ignored.contains(&c) => {}
+                    c if c == expected => break,
+                    _ => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns true if the symbols after `word[i]` start with `context`.
+    fn right_matches(&self, word: &[char], i: usize, context: &[char]) -> bool {
+        let mut pos = i;
+        for &expected in context {
+            loop {
+                pos += 1;
+                match word.get(pos) {
+                    None | Some(']') => return false,
+                    Some('[') => pos = matching_bracket(word, pos, true),
+                    Some(c) if self.ignored.contains(c) => {}
+                    Some(&c) if c == expected => break,
+                    Some(_) => return false,
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Returns the position of the bracket matching the one at `pos`, searching
+/// forward for `[` and backward for `]`. Returns the end of the word if
+/// there is none.
+fn matching_bracket(word: &[char], pos: usize, forward: bool) -> usize {
+    let mut depth = 0;
+    let mut pos = pos;
+    loop {
+        match word[pos] {
+            '[' if forward => depth += 1,
+            ']' if forward => depth -= 1,
+            ']' => depth += 1,
+            '[' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return pos;
+        }
+        if forward {
+            if pos + 1 == word.len() {
+                return pos;
+            }
+            pos += 1;
+        } else {
+            if pos == 0 {
+                return 0;
+            }
+            pos -= 1;
+        }
+    }
+}
+
+/// A turtle command a symbol stands for.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Move forward by the step length, drawing.
+    Forward,
+    /// Move forward by the step length without drawing.
+    Move,
+    /// Turn left by the angle.
+    Left,
+    /// Turn right by the angle.
+    Right,
+    /// Turn by 180 degree.
+    TurnAround,
+    /// Save the turtle state.
+    Push,
+    /// Restore the turtle state saved last.
+    Pop,
+    PenUp,
+    PenDown,
+}
+
+/// Maps the symbols of a word to turtle commands.
+#[derive(Clone, Debug)]
+pub struct Interpreter {
+    step: f32,
+    angle: f32,
+    actions: HashMap<char, Action>,
+}
+
+impl Interpreter {
+    /// Creates an interpreter with the usual symbols:
+    ///
+    /// * `F`, `G` – forward by `step`,
+    /// * `f` – move forward by `step` without drawing,
+    /// * `+` – turn left by `angle`,
+    /// * `-` – turn right by `angle`,
+    /// * `|` – turn around,
+    /// * `[` – push the turtle state,
+    /// * `]` – pop the turtle state.
+    ///
+    /// Other symbols are ignored.
+    pub fn new(step: f32, angle: f32) -> Interpreter {
+        let actions = [
+            ('F', Action::Forward),
+            ('G', Action::Forward),
+            ('f', Action::Move),
+            ('+', Action::Left),
+            ('-', Action::Right),
+            ('|', Action::TurnAround),
+            ('[', Action::Push),
+            (']', Action::Pop),
+        ];
+        Interpreter {
+            step,
+            angle,
+            actions: actions.iter().cloned().collect(),
+        }
+    }
+
+    /// Makes `symbol` stand for `action`, or for nothing if `None`.
+    pub fn set_action(&mut self, symbol: char, action: Option<Action>) {
+        match action {
+            Some(action) => self.actions.insert(symbol, action),
+            None => self.actions.remove(&symbol),
+        };
+    }
+
+    /// Draws `word` with `turtle`.
+    pub fn run<T: Turtle>(&self, turtle: &mut T, word: &str) {
+        for c in word.chars() {
+            self.perform(turtle, c, None);
+        }
+    }
+
+    /// Draws the word of a parametric L-system with `turtle`. The first
+    /// parameter of a module is used as the distance or angle instead of
+    /// `step` or `angle`.
+    pub fn run_modules<T: Turtle>(&self, turtle: &mut T, word: &[Module]) {
+        for module in word {
+            self.perform(turtle, module.symbol, module.params.first().cloned());
+        }
+    }
+
+    fn perform<T: Turtle>(&self, turtle: &mut T, symbol: char, param: Option<f32>) {
+        let step = param.unwrap_or(self.step);
+        let angle = param.unwrap_or(self.angle);
+        match self.actions.get(&symbol) {
+            Some(Action::Forward) => turtle.forward(step),
+            Some(Action::Move) => turtle.move_forward(step),
+            Some(Action::Left) => turtle.left(angle),
+            Some(Action::Right) => turtle.right(angle),
+            Some(Action::TurnAround) => turtle.rotate(180.0),
+            Some(Action::Push) => turtle.push(),
+            Some(Action::Pop) => {
+                turtle.pop();
+            }
+            Some(Action::PenUp) => turtle.pen_up(),
+            Some(Action::PenDown) => turtle.pen_down(),
+            None => {}
+        }
+    }
+}
+
+/// A symbol with numeric parameters, the letters of the words of a
+/// parametric L-system, e.g. `F(10)` or `+(30)`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Module {
+    pub symbol: char,
+    pub params: Vec<f32>,
+}
+
+impl Module {
+    pub fn new(symbol: char, params: &[f32]) -> Module {
+        Module {
+            symbol,
+            params: params.to_vec(),
+        }
+    }
+}
+
+/// An arithmetic expression over the parameters of a rule's predecessor.
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(f32),
+    /// The parameter at this index.
+    Param(usize),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, params: &[f32]) -> f32 {
+        match *self {
+            Expr::Number(x) => x,
+            Expr::Param(i) => params[i],
+            Expr::Neg(ref a) => -a.eval(params),
+            Expr::Binary(op, ref a, ref b) => {
+                let (a, b) = (a.eval(params), b.eval(params));
+                let truth = |holds: bool| if holds { 1.0 } else { 0.0 };
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    '/' => a / b,
+                    '^' => a.powf(b),
+                    '<' => truth(a < b),
+                    '>' => truth(a > b),
+                    'l' => truth(a <= b),
+                    'g' => truth(a >= b),
+                    '=' => truth(a == b),
+                    '!' => truth(a != b),
+                    '&' => truth(a != 0.0 && b != 0.0),
+                    '|' => truth(a != 0.0 || b != 0.0),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent parser for expressions and module lists, on input
+/// without whitespace.
+struct Parser<'a> {
+    input: &'a [char],
+    pos: usize,
+    /// Names of the parameters of the predecessor.
+    params: &'a [String],
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let len = token.chars().count();
+        let matches = self.input.len() >= self.pos + len
+            && token
+                .chars()
+                .zip(&self.input[self.pos..])
+                .all(|(a, &b)| a == b);
+        if matches {
+            self.pos += len;
+        }
+        matches
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    /// Parses operators of increasing precedence, from `||` to `^`.
+    fn expr(&mut self) -> Option<Expr> {
+        self.binary(0)
+    }
+
+    fn binary(&mut self, level: usize) -> Option<Expr> {
+        const LEVELS: &[&[(&str, char)]] = &[
+            &[("||", '|')],
+            &[("&&", '&')],
+            &[("==", '='), ("!=", '!')],
+            &[("<=", 'l'), (">=", 'g'), ("<", '<'), (">", '>')],
+            &[("+", '+'), ("-", '-')],
+            &[("*", '*'), ("/", '/')],
+        ];
+        if level == LEVELS.len() {
+            return self.power();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        'operators: loop {
+            for &(token, op) in LEVELS[level] {
+                if self.eat(token) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+                    continue 'operators;
+                }
+            }
+            return Some(lhs);
+        }
+    }
+
+    /// `^` is right associative and binds tighter than unary minus.
+    fn power(&mut self) -> Option<Expr> {
+        if self.eat("-") {
+            return Some(Expr::Neg(Box::new(self.power()?)));
+        }
+        let base = self.atom()?;
+        if self.eat("^") {
+            let exponent = self.power()?;
+            return Some(Expr::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<Expr> {
+        let c = self.peek()?;
+        if self.eat("(") {
+            let expr = self.expr()?;
+            return if self.eat(")") { Some(expr) } else { None };
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                self.pos += 1;
+            }
+            let number: String = self.input[start..self.pos].iter().collect();
+            return number.parse().ok().map(Expr::Number);
+        }
+        if c.is_alphabetic() || c == '_' {
+            let start = self.pos;
+            while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                self.pos += 1;
+            }
+            let name: String = self.input[start..self.pos].iter().collect();
+            return self.params.iter().position(|p| *p == name).map(Expr::Param);
+        }
+        None
+    }
+
+    /// Parses a list of symbols, each optionally followed by parenthesized,
+    /// comma separated parameter expressions.
+    fn modules(&mut self) -> Option<Vec<(char, Vec<Expr>)>> {
+        let mut modules = Vec::new();
+        while let Some(symbol) = self.peek() {
+            if symbol == '(' || symbol == ')' || symbol == ',' {
+                return None;
+            }
+            self.pos += 1;
+            let mut args = Vec::new();
+            if self.eat("(") {
+                loop {
+                    args.push(self.expr()?);
+                    if self.eat(")") {
+                        break;
+                    }
+                    if !self.eat(",") {
+                        return None;
+                    }
+                }
+            }
+            modules.push((symbol, args));
+        }
+        Some(modules)
+    }
+}
+
+fn without_whitespace(s: &str) -> Vec<char> {
+    s.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Parses a list of modules with constant parameters, e.g. an axiom.
+fn parse_modules(s: &str) -> Option<Vec<Module>> {
+    let input = without_whitespace(s);
+    let mut parser = Parser {
+        input: &input,
+        pos: 0,
+        params: &[],
+    };
+    let modules = parser.modules()?;
+    Some(
+        modules
+            .into_iter()
+            .map(|(symbol, args)| Module {
+                symbol,
+                params: args.iter().map(|arg| arg.eval(&[])).collect(),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Clone, Debug)]
+struct ParametricRule {
+    arity: usize,
+    condition: Option<Expr>,
+    successor: Vec<(char, Vec<Expr>)>,
+}
+
+/// An L-system whose symbols carry numeric parameters, which the rules
+/// compute from the parameters of the rewritten module.
+#[derive(Clone, Debug)]
+pub struct ParametricLSystem {
+    axiom: Vec<Module>,
+    rules: HashMap<char, Vec<ParametricRule>>,
+}
+
+impl ParametricLSystem {
+    /// Creates a parametric L-system from an axiom like `F(1)+(30)A(2,3)`
+    /// and rules like `F(x) -> F(x*0.7)`. Parameters are named in the
+    /// predecessor and may be used in the successor's expressions, which
+    /// support numbers, `+ - * / ^` and parentheses. A rule may have a
+    /// condition after a colon, e.g. `A(x,y) : x < y && y >= 1 -> ...`,
+    /// comparing with `< > <= >= == !=`, combined by `&&` and `||`.
+    ///
+    /// A module is rewritten by the first rule with the same symbol and
+    /// number of parameters whose condition holds, and kept otherwise.
+    /// Whitespace is ignored. Returns `None` if the axiom or a rule is
+    /// malformed or a rule uses an unknown parameter.
+    pub fn parse(axiom: &str, rules: &[&str]) -> Option<ParametricLSystem> {
+        let mut system = ParametricLSystem {
+            axiom: parse_modules(axiom)?,
+            rules: HashMap::new(),
+        };
+        for rule in rules {
+            let (head, successor) = rule.split_once("->")?;
+            let (predecessor, condition) = match head.split_once(':') {
+                Some((predecessor, condition)) => (predecessor, Some(condition)),
+                None => (head, None),
+            };
+
+            let predecessor = without_whitespace(predecessor);
+            let (&symbol, rest) = predecessor.split_first()?;
+            let params: Vec<String> = match rest {
+                [] => Vec::new(),
+                ['(', names @ .., ')'] => names
+                    .split(|&c| c == ',')
+                    .map(|name| name.iter().collect())
+                    .collect(),
+                _ => return None,
+            };
+            let valid_name = |name: &String| {
+                name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                    && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            };
+            if !params.iter().all(valid_name) {
+                return None;
+            }
+
+            let condition = match condition {
+                Some(condition) => {
+                    let input = without_whitespace(condition);
+                    let mut parser = Parser {
+                        input: &input,
+                        pos: 0,
+                        params: &params,
+                    };
+                    let expr = parser.expr()?;
+                    if !parser.at_end() {
+                        return None;
+                    }
+                    Some(expr)
+                }
+                None => None,
+            };
+
+            let input = without_whitespace(successor);
+            let mut parser = Parser {
+                input: &input,
+                pos: 0,
+                params: &params,
+            };
+            let successor = parser.modules()?;
+
+            system
+                .rules
+                .entry(symbol)
+                .or_default()
+                .push(ParametricRule {
+                    arity: params.len(),
+                    condition,
+                    successor,
+                });
+        }
+        Some(system)
+    }
+
+    pub fn axiom(&self) -> &[Module] {
+        &self.axiom
+    }
+
+    /// Returns the word after rewriting the axiom `n` times.
+    pub fn iterate(&self, n: usize) -> Vec<Module> {
+        let mut word = self.axiom.clone();
+        for _ in 0..n {
+            let mut next = Vec::with_capacity(word.len());
+            for module in word {
+                let rule = self.rules.get(&module.symbol).and_then(|rules| {
+                    rules.iter().find(|rule| {
+                        rule.arity == module.params.len()
+                            && rule
+                                .condition
+                                .as_ref()
+                                .is_none_or(|c| c.eval(&module.params) != 0.0)
+                    })
+                });
+                match rule {
+                    Some(rule) => next.extend(rule.successor.iter().map(|(symbol, args)| Module {
+                        symbol: *symbol,
+                        params: args.iter().map(|arg| arg.eval(&module.params)).collect(),
+                    })),
+                    None => next.push(module),
+                }
+            }
+            word = next;
+        }
+        word
+    }
+}
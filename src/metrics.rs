@@ -0,0 +1,475 @@
+//! Analysis of the recorded paths, e.g. to validate generated shapes.
+//!
+//! Paths are numbered in drawing order, starting at 0. The path currently
+//! being drawn is the last one. Arcs are measured along their line segments.
+
+use crate::rng::{RandomSource, SplitMix64};
+use crate::{Canvas, Degree, Position, Radiant};
+use std::f32::consts::PI;
+
+/// Totals of a drawing, e.g. to estimate the time a plotter takes.
+#[derive(Copy, Clone, Debug)]
+pub struct DrawingStats {
+    /// Length of all drawn segments.
+    pub drawn_length: f32,
+    /// Length of the straight pen-up moves from the origin to the first
+    /// path and from the end of each path to the start of the next.
+    pub travel_length: f32,
+    /// Number of paths with at least one segment.
+    pub paths: usize,
+    pub segments: usize,
+    /// See `Canvas::bounding_box`.
+    pub bounding_box: Option<(Position, Position)>,
+}
+
+impl Canvas {
+    /// Returns the totals of the drawing, visiting the paths in drawing
+    /// order. Text labels aren't included.
+    pub fn stats(&self) -> DrawingStats {
+        let mut stats = DrawingStats {
+            drawn_length: 0.0,
+            travel_length: 0.0,
+            paths: 0,
+            segments: 0,
+            bounding_box: self.bounding_box(),
+        };
+        let mut pen = Position::origin();
+        for path in self.paths.iter() {
+            if path.label.is_some() || path.points.len() < 2 {
+                continue;
+            }
+            let start = path.points[0];
+            stats.travel_length += distance(pen, start);
+            stats.paths += 1;
+            for (p, q) in segments(&path.points, path.closed) {
+                stats.drawn_length += distance(p, q);
+                stats.segments += 1;
+            }
+            pen = if path.closed {
+                start
+            } else {
+                *path.points.last().unwrap()
+            };
+        }
+        stats
+    }
+
+    /// Returns the points where a path crosses or touches itself, one per
+    /// pair of meeting segments, e.g. twice where three segments meet.
+    /// Consecutive segments, including the last and first of a path ending
+    /// at its start, only count if the path turns back on itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no path `path`.
+    pub fn self_intersections(&self, path: usize) -> Vec<Position> {
+        let path = &self.paths[path];
+        intersections(&[(0, &path.points, path.closed)])
+    }
+
+    /// Returns the points where the paths cross or touch each other or
+    /// themselves, like `self_intersections`. Text labels aren't included.
+    pub fn intersections(&self) -> Vec<Position> {
+        let paths: Vec<(usize, &[Position], bool)> = self
+            .paths
+            .iter()
+            .enumerate()
+            .filter(|(_, path)| path.label.is_none())
+            .map(|(i, path)| (i, &path.points[..], path.closed))
+            .collect();
+        intersections(&paths)
+    }
+
+    /// Returns the convex hull of the points of all paths, counterclockwise
+    /// and starting with the lowest leftmost point. Collinear points on its
+    /// border, and points which aren't finite, are left out.
+    pub fn convex_hull(&self) -> Vec<Position> {
+        let mut points = Vec::new();
+        self.foreach_position(|pos| points.push(pos), 1.0, 1.0);
+        convex_hull(points)
+    }
+
+    /// Returns the center and radius of the smallest circle enclosing the
+    /// points of all paths, or `None` if there are none.
+    pub fn enclosing_circle(&self) -> Option<(Position, f32)> {
+        let mut hull = self.convex_hull();
+        // Taking the points in random order makes the expected time linear.
+        // The generator is seeded the same every time, so results are
+        // reproducible.
+        let mut rng = SplitMix64::new(0);
+        for i in (1..hull.len()).rev() {
+            hull.swap(i, rng.index(i + 1));
+        }
+        let mut circle = (*hull.first()?, 0.0);
+        // Incremental construction: whenever a point lies outside, it is on
+        // the border of the circle enclosing the points so far.
+        for (i, &p) in hull.iter().enumerate() {
+            if encloses(circle, p) {
+                continue;
+            }
+            circle = (p, 0.0);
+            for (j, &q) in hull[..i].iter().enumerate() {
+                if encloses(circle, q) {
+                    continue;
+                }
+                circle = circle_through_two(p, q);
+                for &r in hull[..j].iter() {
+                    if !encloses(circle, r) {
+                        circle = circle_through_three(p, q, r);
+                    }
+                }
+            }
+        }
+        Some(circle)
+    }
+
+    /// Returns the number of recorded paths, including the one being drawn.
+    pub fn path_count(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns the sum of the signed turns between the segments of a path,
+    /// counterclockwise positive. Closed paths include the turns at the
+    /// closing segment, so that a simple closed shape turns by ±360°.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no path `path`.
+    pub fn total_turning(&self, path: usize) -> Degree {
+        let path = &self.paths[path];
+        let mut directions: Vec<f32> = segments(&path.points, path.closed)
+            .filter(|&(p, q)| p.0 != q.0 || p.1 != q.1)
+            .map(|(p, q)| (q.1 - p.1).atan2(q.0 - p.0))
+            .collect();
+        if path.closed && !directions.is_empty() {
+            directions.push(directions[0]);
+        }
+        let turning: f32 = directions.windows(2).map(|w| turn(w[0], w[1])).sum();
+        Radiant(turning).into()
+    }
+
+    /// Returns how many times a path winds counterclockwise around `point`,
+    /// negative for clockwise. Open paths are treated as if closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no path `path`.
+    pub fn winding_number(&self, path: usize, point: Position) -> i32 {
+        let mut winding = 0;
+        for (p, q) in segments(&self.paths[path].points, true) {
+            // Which side of the segment `point` is on.
+            let side = (q.0 - p.0) * (point.1 - p.1) - (point.0 - p.0) * (q.1 - p.1);
+            if p.1 <= point.1 {
+                if q.1 > point.1 && side > 0.0 {
+                    winding += 1;
+                }
+            } else if q.1 <= point.1 && side < 0.0 {
+                winding -= 1;
+            }
+        }
+        winding
+    }
+
+    /// Returns the signed area enclosed by a path, positive if it runs
+    /// counterclockwise. Open paths are treated as if closed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no path `path`.
+    pub fn area(&self, path: usize) -> f32 {
+        // Shoelace formula, relative to the first point for precision.
+        let points = &self.paths[path].points;
+        let origin = match points.first() {
+            Some(&pos) => pos,
+            None => return 0.0,
+        };
+        let twice: f32 = segments(points, true)
+            .map(|(p, q)| (p.0 - origin.0) * (q.1 - origin.1) - (q.0 - origin.0) * (p.1 - origin.1))
+            .sum();
+        twice / 2.0
+    }
+
+    /// Returns the sum of the areas of all closed paths, including filled
+    /// regions, dots and stamps but not text. Overlapping areas are counted
+    /// once per path.
+    pub fn total_area(&self) -> f32 {
+        (0..self.paths.len())
+            .filter(|&i| self.paths[i].closed && self.paths[i].label.is_none())
+            .map(|i| self.area(i).abs())
+            .sum()
+    }
+
+    /// Returns `true` if `point` lies inside a closed path by the nonzero
+    /// rule, which fills use as well. Open paths contain no points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no path `path`.
+    pub fn contains_point(&self, path: usize, point: Position) -> bool {
+        self.paths[path].closed && self.winding_number(path, point) != 0
+    }
+
+    /// Returns the path drawn at `point`, and the index of its segment
+    /// nearest to it, if any lies within `tolerance`. Segment `i` runs from
+    /// point `i` to the next, the closing segment of a closed path last.
+    /// Filled regions, dots, stamps and text are also hit anywhere inside.
+    /// Of paths at the same distance, the one on top as exported is
+    /// returned, i.e. the one drawn last on the top-most layer. Pen widths
+    /// aren't taken into account.
+    pub fn hit_test(&self, point: Position, tolerance: f32) -> Option<(usize, usize)> {
+        let mut hit = None;
+        let mut nearest = tolerance;
+        let in_render_order = self.layer_order.iter().flat_map(|&layer| {
+            self.paths
+                .iter()
+                .enumerate()
+                .filter(move |(_, path)| path.layer == layer)
+        });
+        for (index, path) in in_render_order {
+            if path.points.len() < 2 {
+                continue;
+            }
+            let closed = path.closed || !path.is_line();
+            let (segment, mut d) = segments(&path.points, closed)
+                .map(|(p, q)| distance_to_segment(point, p, q))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            if !path.is_line() && self.winding_number(index, point) != 0 {
+                d = 0.0;
+            }
+            if d <= nearest {
+                nearest = d;
+                hit = Some((index, segment));
+            }
+        }
+        hit
+    }
+
+    /// Returns the discrete curvature at every point of a path: the turn at
+    /// the point (in radians, counterclockwise positive) divided by the mean
+    /// length of the adjacent segments. The ends of open paths, and points
+    /// with a zero-length adjacent segment, have a curvature of 0.0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no path `path`.
+    pub fn curvature(&self, path: usize) -> Vec<f32> {
+        let path = &self.paths[path];
+        let points = &path.points;
+        let n = points.len();
+        (0..n)
+            .map(|i| {
+                if !path.closed && (i == 0 || i == n - 1) {
+                    return 0.0;
+                }
+                let (prev, pos, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+                let (a, b) = (distance(prev, pos), distance(pos, next));
+                if a == 0.0 || b == 0.0 {
+                    return 0.0;
+                }
+                let before = (pos.1 - prev.1).atan2(pos.0 - prev.0);
+                let after = (next.1 - pos.1).atan2(next.0 - pos.0);
+                turn(before, after) / ((a + b) / 2.0)
+            })
+            .collect()
+    }
+}
+
+/// Andrew's monotone chain algorithm.
+fn convex_hull(mut points: Vec<Position>) -> Vec<Position> {
+    points.retain(|p| p.0.is_finite() && p.1.is_finite());
+    points.sort_by(|p, q| p.1.total_cmp(&q.1).then(p.0.total_cmp(&q.0)));
+    points.dedup_by(|p, q| p.0 == q.0 && p.1 == q.1);
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: Position, a: Position, b: Position| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let mut hull: Vec<Position> = Vec::with_capacity(points.len() + 1);
+    // The right chain upwards, then the left chain downwards.
+    for pass in 0..2 {
+        let start = hull.len();
+        let chain: Box<dyn Iterator<Item = &Position>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for &p in chain {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // The last point starts the other chain.
+        hull.pop();
+    }
+    hull
+}
+
+fn encloses(circle: (Position, f32), p: Position) -> bool {
+    distance(circle.0, p) <= circle.1 * (1.0 + 1e-5) + 1e-6
+}
+
+fn circle_through_two(p: Position, q: Position) -> (Position, f32) {
+    let center = Position((p.0 + q.0) / 2.0, (p.1 + q.1) / 2.0);
+    (center, distance(center, p))
+}
+
+/// Returns the circumcircle of `p`, `q` and `r`, or the circle over the
+/// two points farthest apart if they are collinear.
+fn circle_through_three(p: Position, q: Position, r: Position) -> (Position, f32) {
+    let (bx, by) = (q.0 - p.0, q.1 - p.1);
+    let (cx, cy) = (r.0 - p.0, r.1 - p.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-12 {
+        let pairs = [(p, q), (q, r), (p, r)];
+        let &(a, b) = pairs
+            .iter()
+            .max_by(|s, t| distance(s.0, s.1).total_cmp(&distance(t.0, t.1)))
+            .unwrap();
+        return circle_through_two(a, b);
+    }
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let center = Position(p.0 + (cy * b2 - by * c2) / d, p.1 + (bx * c2 - cx * b2) / d);
+    (center, distance(center, p))
+}
+
+/// A segment of a path, numbered along the path without zero-length
+/// segments.
+struct Segment {
+    a: Position,
+    b: Position,
+    path: usize,
+    index: usize,
+    /// Number of segments of the path, if closed.
+    closed: Option<usize>,
+}
+
+impl Segment {
+    fn is_adjacent(&self, other: &Segment) -> bool {
+        let (i, j) = (self.index.min(other.index), self.index.max(other.index));
+        self.path == other.path
+            && (j == i + 1 || self.closed.is_some_and(|n| n > 2 && i == 0 && j == n - 1))
+    }
+}
+
+/// Returns a point where the paths cross or touch, one per pair of meeting
+/// segments. Adjacent segments only count if they overlap.
+fn intersections(paths: &[(usize, &[Position], bool)]) -> Vec<Position> {
+    let mut segs = Vec::new();
+    for &(path, points, closed) in paths {
+        let first = segs.len();
+        for (a, b) in segments(points, closed).filter(|&(a, b)| a.0 != b.0 || a.1 != b.1) {
+            let index = segs.len() - first;
+            segs.push(Segment {
+                a,
+                b,
+                path,
+                index,
+                closed: None,
+            });
+        }
+        // A path ending at its start, up to rounding errors, is closed as
+        // well.
+        let length: f32 = segs[first..].iter().map(|s| distance(s.a, s.b)).sum();
+        let ends_at_start = match (points.first(), points.last()) {
+            (Some(&p), Some(&q)) => distance(p, q) <= 1e-5 * length,
+            _ => false,
+        };
+        if closed || ends_at_start {
+            let n = segs.len() - first;
+            segs[first..].iter_mut().for_each(|s| s.closed = Some(n));
+        }
+    }
+
+    // Sweep from left to right, only testing segments overlapping in x.
+    let min_x = |s: &Segment| s.a.0.min(s.b.0);
+    segs.sort_by(|s, t| min_x(s).total_cmp(&min_x(t)));
+    let mut points = Vec::new();
+    for (i, s) in segs.iter().enumerate() {
+        let max_x = s.a.0.max(s.b.0);
+        for t in segs[i + 1..].iter().take_while(|t| min_x(t) <= max_x) {
+            if s.a.1.max(s.b.1) < t.a.1.min(t.b.1) || t.a.1.max(t.b.1) < s.a.1.min(s.b.1) {
+                continue;
+            }
+            match intersect((s.a, s.b), (t.a, t.b)) {
+                Some((_, false)) if s.is_adjacent(t) => {}
+                Some((pos, _)) => points.push(pos),
+                None => {}
+            }
+        }
+    }
+    points
+}
+
+/// Returns a point where the segments `s` and `t` meet, and whether they
+/// overlap along a stretch of positive length.
+fn intersect(s: (Position, Position), t: (Position, Position)) -> Option<(Position, bool)> {
+    const EPSILON: f32 = 1e-5;
+    let cross = |u: (f32, f32), v: (f32, f32)| u.0 * v.1 - u.1 * v.0;
+    let dot = |u: (f32, f32), v: (f32, f32)| u.0 * v.0 + u.1 * v.1;
+    let r = (s.1 .0 - s.0 .0, s.1 .1 - s.0 .1);
+    let q = (t.1 .0 - t.0 .0, t.1 .1 - t.0 .1);
+    let d = (t.0 .0 - s.0 .0, t.0 .1 - s.0 .1);
+    let at = |f: f32| Position(s.0 .0 + f * r.0, s.0 .1 + f * r.1);
+    let (rr, qq) = (dot(r, r), dot(q, q));
+    let denom = cross(r, q);
+    if denom.abs() > EPSILON * (rr * qq).sqrt() {
+        let f = cross(d, q) / denom;
+        let g = cross(d, r) / denom;
+        let range = -EPSILON..=1.0 + EPSILON;
+        if range.contains(&f) && range.contains(&g) {
+            return Some((at(f.clamp(0.0, 1.0)), false));
+        }
+        return None;
+    }
+    // Parallel; they meet only if on the same line.
+    if cross(d, r).abs() > EPSILON * rr {
+        return None;
+    }
+    let f0 = dot(d, r) / rr;
+    let f1 = f0 + dot(q, r) / rr;
+    let (low, high) = (f0.min(f1).max(0.0), f0.max(f1).min(1.0));
+    if low > high + EPSILON {
+        return None;
+    }
+    Some((at(low.min(1.0)), high - low > EPSILON))
+}
+
+/// Returns the segments of a polyline, including the closing segment if
+/// `closed`.
+fn segments(points: &[Position], closed: bool) -> impl Iterator<Item = (Position, Position)> + '_ {
+    let closing = match (closed, points.first(), points.last()) {
+        (true, Some(&first), Some(&last)) if points.len() > 1 => Some((last, first)),
+        _ => None,
+    };
+    points.windows(2).map(|w| (w[0], w[1])).chain(closing)
+}
+
+/// Returns the signed angle from direction `from` to `to`, in `-PI..=PI`.
+fn turn(from: f32, to: f32) -> f32 {
+    let angle = (to - from).rem_euclid(2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+fn distance(p: Position, q: Position) -> f32 {
+    (q.0 - p.0).hypot(q.1 - p.1)
+}
+
+fn distance_to_segment(pos: Position, p: Position, q: Position) -> f32 {
+    let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+    let length2 = dx * dx + dy * dy;
+    if length2 == 0.0 {
+        return distance(pos, p);
+    }
+    let t = (((pos.0 - p.0) * dx + (pos.1 - p.1) * dy) / length2).clamp(0.0, 1.0);
+    distance(pos, Position(p.0 + dx * t, p.1 + dy * t))
+}
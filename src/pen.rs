@@ -0,0 +1,103 @@
+use crate::Color;
+
+/// The pen used to stroke paths.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pen {
+    color: Color,
+    width: Option<f32>,
+    dash: Vec<f32>,
+    hue_cycle: Option<HueCycle>,
+}
+
+/// The exact hue, saturation and value of a hue-cycling pen, so that rounding
+/// of `Color` components doesn't accumulate.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct HueCycle {
+    step: f32,
+    hsv: (f32, f32, f32),
+}
+
+impl Default for Pen {
+    fn default() -> Pen {
+        Pen::new(Color::BLACK)
+    }
+}
+
+impl Pen {
+    pub fn new(color: Color) -> Pen {
+        Pen {
+            color,
+            width: None,
+            dash: Vec::new(),
+            hue_cycle: None,
+        }
+    }
+
+    /// Returns the pen with a fixed stroke `width`. Without a width, the
+    /// stroke width is derived from the size of the drawing on export.
+    pub fn with_width(mut self, width: f32) -> Pen {
+        self.width = Some(width);
+        self
+    }
+
+    /// Returns the pen with another color. Keeps hue-cycling mode, which
+    /// continues from the new color.
+    pub fn with_color(mut self, color: Color) -> Pen {
+        self.color = color;
+        if let Some(ref mut cycle) = self.hue_cycle {
+            cycle.hsv = color.to_hsv();
+        }
+        self
+    }
+
+    /// Returns the pen with a dash pattern of alternating dash and gap
+    /// lengths. An empty pattern draws solid lines.
+    pub fn with_dash(mut self, pattern: &[f32]) -> Pen {
+        self.dash = pattern.to_vec();
+        self
+    }
+
+    /// Returns the pen in hue-cycling mode, where the hue of the color
+    /// advances by `step` degree after every drawn segment. Saturation and
+    /// value are kept, so start with a saturated color, e.g.
+    /// `Pen::new(Color::rgb(255, 0, 0)).cycle_hue(5.0)`.
+    pub fn cycle_hue(mut self, step: f32) -> Pen {
+        let hsv = self.color.to_hsv();
+        self.hue_cycle = Some(HueCycle { step, hsv });
+        self
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn width(&self) -> Option<f32> {
+        self.width
+    }
+
+    pub fn dash(&self) -> &[f32] {
+        &self.dash
+    }
+
+    /// Returns `true` if the pen is in hue-cycling mode.
+    pub fn is_cycling_hue(&self) -> bool {
+        self.hue_cycle.is_some()
+    }
+
+    /// Scales the stroke width and dash pattern by `factor`.
+    pub(crate) fn scale(&mut self, factor: f32) {
+        self.width = self.width.map(|width| width * factor);
+        for d in self.dash.iter_mut() {
+            *d *= factor;
+        }
+    }
+
+    /// Advances the hue of a hue-cycling pen by one step.
+    pub(crate) fn advance_hue(&mut self) {
+        if let Some(ref mut cycle) = self.hue_cycle {
+            let (hue, saturation, value) = cycle.hsv;
+            cycle.hsv.0 = (hue + cycle.step).rem_euclid(360.0);
+            self.color = Color::from_hsv(cycle.hsv.0, saturation, value);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Driving an HPGL pen plotter live.
+//!
+//! A `PlotterTurtle` sends an HPGL command for every move as it happens,
+//! instead of exporting a finished drawing. It writes to anything that
+//! implements `Write`; on Unix, a serial port set up with e.g.
+//! `stty -F /dev/ttyUSB0 9600 raw crtscts` can be opened as a file:
+//!
+//! ```text
+//! let port = OpenOptions::new().write(true).open("/dev/ttyUSB0")?;
+//! let mut plotter = PlotterTurtle::new(port, PlotterConfig::default())?;
+//! ```
+//!
+//! Commands are flushed one by one, so the plotter starts drawing at once;
+//! flow control is left to the port.
+
+use crate::tracker::Tracker;
+use crate::{Degree, Distance, Position, Turtle};
+use std::io::{self, Write};
+
+/// Setup of a `PlotterTurtle`. Plotter coordinates are in plotter units
+/// of 0.025 mm.
+#[derive(Copy, Clone, Debug)]
+pub struct PlotterConfig {
+    /// Plotter units per turtle unit. The default of 40 draws one
+    /// millimeter per unit.
+    pub scale: f32,
+    /// Plotter coordinates of the turtle origin.
+    pub origin: (i32, i32),
+    /// Pen velocity in cm/s, or `None` for the plotter's default.
+    pub velocity: Option<f32>,
+    /// Pen to select at the start.
+    pub pen: u8,
+    /// Lower left and upper right corner of the area the plotter may draw
+    /// in, in plotter coordinates. Lines outside are clipped by the plotter.
+    pub page: Option<((i32, i32), (i32, i32))>,
+}
+
+impl Default for PlotterConfig {
+    fn default() -> PlotterConfig {
+        PlotterConfig {
+            scale: 40.0,
+            origin: (0, 0),
+            velocity: None,
+            pen: 1,
+            page: None,
+        }
+    }
+}
+
+/// A turtle drawing on an HPGL plotter as it moves.
+///
+/// The physical pen is only lowered for lines; moves without drawing are
+/// sent once the next line starts. Write errors are kept until `finish`,
+/// which parks the pen; nothing is sent after the first one.
+pub struct PlotterTurtle<W: Write> {
+    writer: W,
+    config: PlotterConfig,
+    tracker: Tracker<()>,
+    /// Plotter coordinates of the pen, if known.
+    plotter: Option<(i32, i32)>,
+    /// Whether the pen is on the paper.
+    lowered: bool,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> PlotterTurtle<W> {
+    /// Initializes the plotter, selects the pen and applies the velocity and
+    /// page limits of `config`.
+    pub fn new(writer: W, config: PlotterConfig) -> io::Result<PlotterTurtle<W>> {
+        let mut plotter = PlotterTurtle {
+            writer,
+            config,
+            tracker: Tracker::new(()),
+            plotter: None,
+            lowered: false,
+            error: None,
+        };
+        plotter.send(format_args!("IN;SP{};", config.pen));
+        if let Some(velocity) = config.velocity {
+            plotter.send(format_args!("VS{};", velocity));
+        }
+        if let Some(((x1, y1), (x2, y2))) = config.page {
+            plotter.send(format_args!("IW{},{},{},{};", x1, y1, x2, y2));
+        }
+        plotter.send(format_args!("PA;"));
+        match plotter.error.take() {
+            Some(error) => Err(error),
+            None => Ok(plotter),
+        }
+    }
+
+    /// Sets the pen velocity in cm/s.
+    pub fn set_velocity(&mut self, velocity: f32) {
+        self.config.velocity = Some(velocity);
+        self.send(format_args!("VS{};", velocity));
+    }
+
+    /// Selects pen `pen` from the carousel, or puts the pen away for 0.
+    pub fn select_pen(&mut self, pen: u8) {
+        self.config.pen = pen;
+        self.lowered = false;
+        self.send(format_args!("SP{};", pen));
+    }
+
+    /// Raises and puts away the pen, and returns the writer, or the first
+    /// write error.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.send(format_args!("PU;SP0;"));
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(self.writer),
+        }
+    }
+
+    /// Sends a command and flushes it to the plotter.
+    fn send(&mut self, args: std::fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(error) = self
+                .writer
+                .write_fmt(args)
+                .and_then(|_| self.writer.flush())
+            {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    fn to_plotter(&self, pos: Position) -> (i32, i32) {
+        (
+            self.config.origin.0 + (pos.0 * self.config.scale).round() as i32,
+            self.config.origin.1 + (pos.1 * self.config.scale).round() as i32,
+        )
+    }
+
+    /// Sends a line from `from` to `to`.
+    fn line(&mut self, from: Position, to: Position) {
+        let (from, to) = (self.to_plotter(from), self.to_plotter(to));
+        if self.plotter != Some(from) {
+            self.send(format_args!("PU{},{};", from.0, from.1));
+            self.lowered = false;
+        }
+        if to != from || !self.lowered {
+            self.send(format_args!("PD{},{};", to.0, to.1));
+        }
+        self.plotter = Some(to);
+        self.lowered = true;
+    }
+}
+
+impl<W: Write> Turtle for PlotterTurtle<W> {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        if let Some(from) = self.tracker.forward(distance.into().0) {
+            let to = self.tracker.current().pos;
+            self.line(from.pos, to);
+        }
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        self.tracker.move_forward(distance.into().0);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.rotate(angle.into());
+    }
+
+    fn position(&self) -> Position {
+        self.tracker.current().pos
+    }
+
+    fn heading(&self) -> Degree {
+        self.tracker.current().heading
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.set_heading(angle.into());
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.tracker.current().pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.tracker.state().pendown = true;
+    }
+
+    fn pen_up(&mut self) {
+        self.tracker.state().pendown = false;
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.tracker.goto(pos);
+    }
+
+    fn push(&mut self) {
+        self.tracker.push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.tracker.pop()
+    }
+}
@@ -0,0 +1,197 @@
+//! Writing PNG and animated PNG (APNG) files.
+//!
+//! Image data is compressed with the fixed Huffman codes of deflate, using
+//! only runs of repeated bytes. After the sub filter, uniform areas like the
+//! background become runs of zeros, which is where most of the size of a
+//! line drawing goes.
+
+use crate::raster::Image;
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Writes a sequence of equally sized images as APNG, showing each for
+/// `delay` seconds and looping forever. The first image is also what
+/// viewers without APNG support show.
+pub(crate) fn write_apng<W: Write>(wr: &mut W, frames: &[Image], delay: f32) -> io::Result<()> {
+    let (width, height) = match frames.first() {
+        Some(image) => (image.width as u32, image.height as u32),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "no frames")),
+    };
+    wr.write_all(&SIGNATURE)?;
+    write_header(wr, width, height)?;
+
+    let mut actl = Vec::new();
+    actl.extend(&(frames.len() as u32).to_be_bytes());
+    actl.extend(&0u32.to_be_bytes());
+    write_chunk(wr, b"acTL", &actl)?;
+
+    // Delays are given as fraction of seconds.
+    let delay_num = (delay * 1000.0).round().clamp(0.0, u16::MAX as f32) as u16;
+    let mut sequence = 0u32;
+    for (i, image) in frames.iter().enumerate() {
+        let mut fctl = Vec::new();
+        fctl.extend(&sequence.to_be_bytes());
+        fctl.extend(&width.to_be_bytes());
+        fctl.extend(&height.to_be_bytes());
+        fctl.extend(&0u32.to_be_bytes());
+        fctl.extend(&0u32.to_be_bytes());
+        fctl.extend(&delay_num.to_be_bytes());
+        fctl.extend(&1000u16.to_be_bytes());
+        // Keep the previous frame as background, replace it by this one.
+        fctl.extend(&[0, 0]);
+        write_chunk(wr, b"fcTL", &fctl)?;
+        sequence += 1;
+
+        let data = zlib(&filter(image));
+        if i == 0 {
+            write_chunk(wr, b"IDAT", &data)?;
+        } else {
+            let mut fdat = sequence.to_be_bytes().to_vec();
+            fdat.extend(&data);
+            write_chunk(wr, b"fdAT", &fdat)?;
+            sequence += 1;
+        }
+    }
+    write_chunk(wr, b"IEND", &[])
+}
+
+fn write_header<W: Write>(wr: &mut W, width: u32, height: u32) -> io::Result<()> {
+    let mut ihdr = Vec::new();
+    ihdr.extend(&width.to_be_bytes());
+    ihdr.extend(&height.to_be_bytes());
+    // 8 bit RGBA, deflate, adaptive filtering, no interlace.
+    ihdr.extend(&[8, 6, 0, 0, 0]);
+    write_chunk(wr, b"IHDR", &ihdr)
+}
+
+fn write_chunk<W: Write>(wr: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    wr.write_all(&(data.len() as u32).to_be_bytes())?;
+    wr.write_all(kind)?;
+    wr.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    wr.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a, I: Iterator<Item = &'a u8>>(bytes: I) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Returns the scanlines of `image`, each with the sub filter applied.
+fn filter(image: &Image) -> Vec<u8> {
+    let stride = 4 * image.width;
+    let mut filtered = Vec::with_capacity((stride + 1) * image.height);
+    for row in image.data.chunks(stride) {
+        filtered.push(1);
+        for (i, &byte) in row.iter().enumerate() {
+            let left = if i >= 4 { row[i - 4] } else { 0 };
+            filtered.push(byte.wrapping_sub(left));
+        }
+    }
+    filtered
+}
+
+/// Writes bits least significant first, as deflate expects.
+struct BitWriter {
+    bytes: Vec<u8>,
+    bit: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.bit |= value << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.bytes.push(self.bit as u8);
+            self.bit >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which is stored most significant bit first.
+    fn write_code(&mut self, code: u32, bits: u32) {
+        self.write(code.reverse_bits() >> (32 - bits), bits);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.bit as u8);
+        }
+        self.bytes
+    }
+}
+
+const LENGTH_BASE: [u32; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u32; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Writes a literal/length symbol with the fixed Huffman code.
+fn write_symbol(bits: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => bits.write_code(0x30 + symbol, 8),
+        144..=255 => bits.write_code(0x190 + symbol - 144, 9),
+        256..=279 => bits.write_code(symbol - 256, 7),
+        _ => bits.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+/// Compresses `data` into a zlib stream of a single fixed Huffman block.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter {
+        bytes: vec![0x78, 0x01],
+        bit: 0,
+        count: 0,
+    };
+    // Final block, fixed Huffman codes.
+    bits.write(1, 1);
+    bits.write(1, 2);
+    let mut i = 0;
+    while i < data.len() {
+        let mut run = 0;
+        if i > 0 {
+            while run < 258 && i + run < data.len() && data[i + run] == data[i - 1] {
+                run += 1;
+            }
+        }
+        if run >= 3 {
+            // Repeat the previous byte, at distance 1.
+            let code = LENGTH_BASE
+                .iter()
+                .rposition(|&base| base <= run as u32)
+                .unwrap();
+            write_symbol(&mut bits, 257 + code as u32);
+            bits.write(run as u32 - LENGTH_BASE[code], LENGTH_EXTRA[code]);
+            bits.write_code(0, 5);
+            i += run;
+        } else {
+            write_symbol(&mut bits, data[i] as u32);
+            i += 1;
+        }
+    }
+    write_symbol(&mut bits, 256);
+    let mut bytes = bits.finish();
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    bytes.extend(&((b << 16) | a).to_be_bytes());
+    bytes
+}
@@ -0,0 +1,58 @@
+//! Previewing a drawing in the system's image viewer.
+//!
+//! The drawing is written as SVG to the temporary directory and opened with
+//! the program registered for SVG files, usually a browser, which offers
+//! panning and zooming.
+
+use crate::Canvas;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+impl Canvas {
+    /// Opens the current drawing in the default viewer for SVG files.
+    /// Every call writes a new file, so that viewers reloading on change
+    /// don't mix up drawings. Returns the path of the file.
+    pub fn show(&self) -> io::Result<PathBuf> {
+        let path = preview_path();
+        self.save_svg(&mut File::create(&path)?)?;
+        let status = open_command(&path).status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "failed to open {}: {}",
+                path.display(),
+                status
+            )));
+        }
+        Ok(path)
+    }
+}
+
+fn preview_path() -> PathBuf {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("turtle-{}-{}.svg", std::process::id(), n))
+}
+
+#[cfg(target_os = "macos")]
+fn open_command(path: &Path) -> Command {
+    let mut command = Command::new("open");
+    command.arg(path);
+    command
+}
+
+#[cfg(windows)]
+fn open_command(path: &Path) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(&["/C", "start", ""]).arg(path);
+    command
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn open_command(path: &Path) -> Command {
+    let mut command = Command::new("xdg-open");
+    command.arg(path);
+    command
+}
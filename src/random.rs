@@ -0,0 +1,164 @@
+//! Random walks for generative art.
+//!
+//! A `RandomWalk` turns and moves a turtle by amounts drawn from a
+//! `Distribution` at every step, optionally bouncing off the walls of a
+//! bounding box. All randomness comes from the passed generator, so equal
+//! seeds give equal drawings.
+
+use crate::rng::RandomSource;
+use crate::{Position, Radiant, Turtle};
+use std::f32::consts::PI;
+
+/// Most wall bounces per step, which only matters for steps much longer than
+/// the bounding box.
+const MAX_BOUNCES: usize = 16;
+
+/// Distance to the points used to tell and set the direction of the turtle
+/// through `towards` and `face`, far enough not to vanish when added to its
+/// coordinates.
+const REACH: f32 = 1e6;
+
+/// A distribution of step lengths or turning angles.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Distribution {
+    /// Always the same value.
+    Constant(f32),
+    /// Uniformly distributed in `low..high`.
+    Uniform(f32, f32),
+    /// Normally distributed.
+    Normal { mean: f32, std_dev: f32 },
+    /// One of the values, each equally likely.
+    Choice(Vec<f32>),
+}
+
+impl Distribution {
+    /// Draws a value. A `Choice` without values gives `0.0`.
+    pub fn sample<R: RandomSource + ?Sized>(&self, rng: &mut R) -> f32 {
+        match *self {
+            Distribution::Constant(value) => value,
+            Distribution::Uniform(low, high) => rng.range(low, high),
+            Distribution::Normal { mean, std_dev } => {
+                // Box-Muller transform; 1 - u avoids the logarithm of zero.
+                let u = 1.0 - rng.next_f32();
+                let v = rng.next_f32();
+                mean + std_dev * (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+            }
+            Distribution::Choice(ref values) if values.is_empty() => 0.0,
+            Distribution::Choice(ref values) => values[rng.index(values.len())],
+        }
+    }
+}
+
+/// A random walk: at every step the turtle turns left by an angle in degree
+/// drawn from `turn`, then moves forward by a distance drawn from `step`.
+#[derive(Clone, Debug)]
+pub struct RandomWalk {
+    step: Distribution,
+    turn: Distribution,
+    bounds: Option<(Position, Position)>,
+}
+
+impl RandomWalk {
+    pub fn new(step: Distribution, turn: Distribution) -> RandomWalk {
+        RandomWalk {
+            step,
+            turn,
+            bounds: None,
+        }
+    }
+
+    /// Brownian-style wandering: steps of `step_length` in uniformly random
+    /// directions.
+    pub fn brownian(step_length: f32) -> RandomWalk {
+        RandomWalk::new(
+            Distribution::Constant(step_length),
+            Distribution::Uniform(-180.0, 180.0),
+        )
+    }
+
+    /// A walk on a square lattice: steps of `step_length`, turning by a
+    /// multiple of 90 degree. Start with a heading that is a multiple of 90
+    /// degree to stay on the lattice.
+    pub fn lattice(step_length: f32) -> RandomWalk {
+        RandomWalk::new(
+            Distribution::Constant(step_length),
+            Distribution::Choice(vec![0.0, 90.0, 180.0, 270.0]),
+        )
+    }
+
+    /// Confines the walk to the rectangle `min`-`max`; the turtle bounces
+    /// off its walls like a billiard ball. The turtle should start inside.
+    pub fn with_bounds(mut self, min: Position, max: Position) -> RandomWalk {
+        self.bounds = Some((min.min(&max), min.max(&max)));
+        self
+    }
+
+    /// Makes `steps` steps with `turtle`, drawing with its current pen
+    /// state.
+    pub fn walk<T: Turtle, R: RandomSource + ?Sized>(
+        &self,
+        turtle: &mut T,
+        steps: usize,
+        rng: &mut R,
+    ) {
+        for _ in 0..steps {
+            turtle.rotate(self.turn.sample(rng));
+            let distance = self.step.sample(rng);
+            match self.bounds {
+                Some((min, max)) => bounce(turtle, distance, min, max),
+                None => turtle.forward(distance),
+            }
+        }
+    }
+}
+
+/// Moves `turtle` forward by `distance`, reflecting it off the walls of the
+/// rectangle `min`-`max`.
+fn bounce<T: Turtle>(turtle: &mut T, distance: f32, min: Position, max: Position) {
+    if distance < 0.0 {
+        turtle.rotate(180.0);
+        bounce(turtle, -distance, min, max);
+        turtle.rotate(180.0);
+        return;
+    }
+    let mut left = distance;
+    for _ in 0..MAX_BOUNCES {
+        let pos = turtle.position();
+        let (dx, dy) = direction(turtle);
+        let until = |p: f32, d: f32, low: f32, high: f32| {
+            if d > 0.0 {
+                ((high - p) / d).max(0.0)
+            } else if d < 0.0 {
+                ((low - p) / d).max(0.0)
+            } else {
+                f32::INFINITY
+            }
+        };
+        let tx = until(pos.0, dx, min.0, max.0);
+        let ty = until(pos.1, dy, min.1, max.1);
+        let t = tx.min(ty);
+        if t >= left {
+            break;
+        }
+        turtle.forward(t);
+        left -= t;
+        let (dx, dy) = (
+            if tx <= ty { -dx } else { dx },
+            if ty <= tx { -dy } else { dy },
+        );
+        let pos = turtle.position();
+        turtle.face(Position(pos.0 + dx * REACH, pos.1 + dy * REACH));
+    }
+    turtle.forward(left);
+}
+
+/// Returns the direction `turtle` is heading in, as a unit vector.
+fn direction<T: Turtle>(turtle: &T) -> (f32, f32) {
+    // Headings count clockwise on a `Canvas` in `HeadingMode::Compass`,
+    // which shows in the heading towards a point to the left.
+    let pos = turtle.position();
+    let west = Position(pos.0 - REACH, pos.1);
+    let clockwise = turtle.towards(west).0 > 180.0;
+    let (sin, cos) = Radiant::from(turtle.heading()).0.sin_cos();
+    (if clockwise { sin } else { -sin }, cos)
+}
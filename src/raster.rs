@@ -0,0 +1,340 @@
+//! A small anti-aliasing rasterizer for pixel exports.
+//!
+//! Strokes are drawn with round caps and joins, or as one pixel wide lines
+//! with Xiaolin Wu's algorithm, fills with the nonzero rule. Dash patterns
+//! and text labels aren't rendered.
+
+use crate::{stroke_outline, Canvas, Color, Position};
+
+/// An RGBA image, row by row from the top, stored in `D`.
+pub(crate) struct Image<D = Vec<u8>> {
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) data: D,
+}
+
+impl Image {
+    fn new(width: usize, height: usize, background: Option<Color>) -> Image {
+        Image {
+            width,
+            height,
+            data: background_pixel(background).repeat(width * height),
+        }
+    }
+}
+
+fn background_pixel(background: Option<Color>) -> [u8; 4] {
+    match background {
+        Some(c) => [c.r, c.g, c.b, 255],
+        None => [0, 0, 0, 0],
+    }
+}
+
+impl<D: AsMut<[u8]>> Image<D> {
+    /// Paints `color` over the pixel at `x`, `y` with `coverage` in
+    /// `0.0..=1.0`.
+    fn blend(&mut self, x: usize, y: usize, color: Color, coverage: f32) {
+        let i = 4 * (y * self.width + x);
+        let pixel = &mut self.data.as_mut()[i..i + 4];
+        let dst_alpha = pixel[3] as f32 / 255.0;
+        let alpha = coverage + dst_alpha * (1.0 - coverage);
+        if alpha <= 0.0 {
+            return;
+        }
+        for (channel, src) in pixel[..3].iter_mut().zip(&[color.r, color.g, color.b]) {
+            let mixed =
+                (*src as f32 * coverage + *channel as f32 * dst_alpha * (1.0 - coverage)) / alpha;
+            *channel = mixed.round() as u8;
+        }
+        pixel[3] = (alpha * 255.0).round() as u8;
+    }
+
+    /// Draws a line from `p` to `q` of `width` pixels, with round ends.
+    fn stroke_segment(&mut self, p: (f32, f32), q: (f32, f32), width: f32, color: Color) {
+        let r = width / 2.0;
+        let (x0, x1) = (p.0.min(q.0) - r - 1.0, p.0.max(q.0) + r + 1.0);
+        let (y0, y1) = (p.1.min(q.1) - r - 1.0, p.1.max(q.1) + r + 1.0);
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let len2 = dx * dx + dy * dy;
+        for y in self.rows(y0, y1) {
+            for x in self.columns(x0, x1) {
+                let (cx, cy) = (x as f32 + 0.5, y as f32 + 0.5);
+                let t = if len2 > 0.0 {
+                    (((cx - p.0) * dx + (cy - p.1) * dy) / len2).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let distance = (cx - p.0 - t * dx).hypot(cy - p.1 - t * dy);
+                let coverage = (r + 0.5 - distance).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend(x, y, color, coverage);
+                }
+            }
+        }
+    }
+
+    /// Draws a one pixel wide line from `p` to `q` with Xiaolin Wu's
+    /// algorithm.
+    fn wu_line(&mut self, p: (f32, f32), q: (f32, f32), color: Color) {
+        // Pixel centers lie at half-integer coordinates.
+        let (mut x0, mut y0, mut x1, mut y1) = (p.0 - 0.5, p.1 - 0.5, q.0 - 0.5, q.1 - 0.5);
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            std::mem::swap(&mut x0, &mut y0);
+            std::mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+        let gradient = if x1 > x0 { (y1 - y0) / (x1 - x0) } else { 0.0 };
+        let fract = |v: f32| v - v.floor();
+        let limit = if steep { self.height } else { self.width } as f32;
+        // Plots the two pixels straddling `y` in column `x`.
+        let mut plot = |x: f32, y: f32, weight: f32| {
+            let row = y.floor();
+            for (row, coverage) in [(row, 1.0 - fract(y)), (row + 1.0, fract(y))] {
+                let (px, py) = if steep { (row, x) } else { (x, row) };
+                let coverage = coverage * weight;
+                if px >= 0.0
+                    && py >= 0.0
+                    && (px as usize) < self.width
+                    && (py as usize) < self.height
+                    && coverage > 0.0
+                {
+                    self.blend(px as usize, py as usize, color, coverage.min(1.0));
+                }
+            }
+        };
+        let y_at = |x: f32| y0 + gradient * (x - x0);
+
+        // The end pixels are weighted by how much of them the line covers.
+        let (start, end) = (x0.round(), x1.round());
+        plot(start, y_at(start), 1.0 - fract(x0 + 0.5));
+        if end > start {
+            plot(end, y_at(end), fract(x1 + 0.5));
+        }
+        let mut x = (start + 1.0).max(0.0);
+        while x < end.min(limit) {
+            plot(x, y_at(x), 1.0);
+            x += 1.0;
+        }
+    }
+
+    /// Fills a polygon with the nonzero rule, sampling four scanlines per
+    /// pixel row.
+    fn fill_polygon(&mut self, points: &[(f32, f32)], color: Color) {
+        const SAMPLES: usize = 4;
+        if points.len() < 3 {
+            return;
+        }
+        let y0 = points.iter().map(|p| p.1).fold(f32::INFINITY, f32::min);
+        let y1 = points.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+        let mut coverage = vec![0.0; self.width];
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+        for y in self.rows(y0, y1) {
+            coverage.iter_mut().for_each(|c| *c = 0.0);
+            for sample in 0..SAMPLES {
+                let sy = y as f32 + (sample as f32 + 0.5) / SAMPLES as f32;
+                crossings.clear();
+                for (i, &p) in points.iter().enumerate() {
+                    let q = points[(i + 1) % points.len()];
+                    if (p.1 <= sy) != (q.1 <= sy) {
+                        let x = p.0 + (sy - p.1) / (q.1 - p.1) * (q.0 - p.0);
+                        crossings.push((x, if q.1 > p.1 { 1 } else { -1 }));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                let mut winding = 0;
+                for w in crossings.windows(2) {
+                    winding += w[0].1;
+                    if winding != 0 {
+                        self.add_span(&mut coverage, w[0].0, w[1].0, 1.0 / SAMPLES as f32);
+                    }
+                }
+            }
+            for (x, &c) in coverage.iter().enumerate() {
+                if c > 0.0 {
+                    self.blend(x, y, color, c.min(1.0));
+                }
+            }
+        }
+    }
+
+    /// Adds `weight` times the covered part of each pixel between `a` and
+    /// `b` to `coverage`.
+    fn add_span(&self, coverage: &mut [f32], a: f32, b: f32, weight: f32) {
+        let (a, b) = (a.max(0.0), b.min(self.width as f32));
+        if a >= b {
+            return;
+        }
+        let (start, end) = (a.floor() as usize, (b.ceil() as usize).min(self.width));
+        for (x, c) in coverage.iter_mut().enumerate().take(end).skip(start) {
+            let overlap = (b.min(x as f32 + 1.0) - a.max(x as f32)).max(0.0);
+            *c += overlap * weight;
+        }
+    }
+
+    fn rows(&self, y0: f32, y1: f32) -> std::ops::Range<usize> {
+        clamp_range(y0, y1, self.height)
+    }
+
+    fn columns(&self, x0: f32, x1: f32) -> std::ops::Range<usize> {
+        clamp_range(x0, x1, self.width)
+    }
+}
+
+/// Returns the pixel indices from `a` to `b`, within `0..n`.
+fn clamp_range(a: f32, b: f32, n: usize) -> std::ops::Range<usize> {
+    if a.is_nan() || b.is_nan() || a > b {
+        return 0..0;
+    }
+    let start = a.floor().max(0.0).min(n as f32) as usize;
+    let end = (b.ceil() + 1.0).max(0.0).min(n as f32) as usize;
+    start..end
+}
+
+/// Settings for `Canvas::rasterize`.
+#[derive(Copy, Clone, Debug)]
+pub struct RasterOptions {
+    /// Fill the buffer with the background color, or make it transparent,
+    /// before drawing. Otherwise the drawing is painted over what the
+    /// buffer holds.
+    pub clear: bool,
+    /// Draw every line one pixel wide with Xiaolin Wu's algorithm, which is
+    /// faster, instead of with the width of its pen.
+    pub hairlines: bool,
+}
+
+impl Default for RasterOptions {
+    fn default() -> RasterOptions {
+        RasterOptions {
+            clear: true,
+            hairlines: true,
+        }
+    }
+}
+
+impl Canvas {
+    /// Draws the canvas into `buffer`, an RGBA image of `width` by `height`
+    /// pixels with 8 bits per channel, row by row from the top, like a
+    /// framebuffer. The area of the SVG export is scaled to fit and
+    /// centered. Lines are anti-aliased; dash patterns and text labels
+    /// aren't rendered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buffer` is shorter than `4 * width * height` bytes.
+    pub fn rasterize(
+        &self,
+        buffer: &mut [u8],
+        width: usize,
+        height: usize,
+        options: &RasterOptions,
+    ) {
+        let len = 4 * width * height;
+        assert!(
+            buffer.len() >= len,
+            "buffer too small for {}x{} pixels",
+            width,
+            height
+        );
+        let data = &mut buffer[..len];
+        if options.clear {
+            let pixel = background_pixel(self.background);
+            for chunk in data.chunks_exact_mut(4) {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+        let mut image = Image {
+            width,
+            height,
+            data,
+        };
+        self.draw(&mut image, options.hairlines);
+    }
+
+    /// Renders the canvas into an RGBA image of `width` by `height` pixels,
+    /// with 8 bits per channel, row by row from the top. This is the layout
+    /// expected by e.g. `image::RgbaImage::from_raw`. The area of the SVG
+    /// export is scaled to fit and centered; pixels outside it are filled
+    /// with the background color, or transparent. Strokes are anti-aliased;
+    /// dash patterns and text labels aren't rendered.
+    pub fn render_rgba(&self, width: usize, height: usize) -> Vec<u8> {
+        let mut image = Image::new(width, height, self.background);
+        self.draw(&mut image, false);
+        image.data
+    }
+
+    /// Renders the canvas `width` pixels wide, showing the same area as the
+    /// SVG export.
+    pub(crate) fn render_image(&self, width: usize) -> Image {
+        let (_, view_width, view_height, _) = self.svg_frame();
+        let height = ((view_height * width as f32 / view_width).round() as usize).max(1);
+        let mut image = Image::new(width, height, self.background);
+        self.draw(&mut image, false);
+        image
+    }
+
+    /// Draws the canvas into `image`, fitting in the area of the SVG export.
+    /// With `hairlines`, lines are drawn one pixel wide.
+    fn draw<D: AsMut<[u8]>>(&self, image: &mut Image<D>, hairlines: bool) {
+        if let Some(canvas) = self.export_copy() {
+            return canvas.draw(image, hairlines);
+        }
+        let (width, height) = (image.width, image.height);
+        let (top_left, view_width, view_height, stroke_width) = self.svg_frame();
+        let scale = (width as f32 / view_width).min(height as f32 / view_height);
+        let offset = (
+            (width as f32 - view_width * scale) / 2.0,
+            (height as f32 - view_height * scale) / 2.0,
+        );
+        let to_pixel = |pos: &Position| {
+            (
+                (pos.0 - top_left.0) * scale + offset.0,
+                (-pos.1 - top_left.1) * scale + offset.1,
+            )
+        };
+
+        for (_, paths) in self.paths_by_layer() {
+            for path in paths {
+                if path.label.is_some() {
+                    continue;
+                }
+                if let Some(color) = path.fill {
+                    let points: Vec<(f32, f32)> = path.points.iter().map(to_pixel).collect();
+                    image.fill_polygon(&points, color);
+                    continue;
+                }
+                let width = path.pen.width().unwrap_or(stroke_width);
+                let mut points = path.points.clone();
+                if path.closed && !points.is_empty() {
+                    points.push(points[0]);
+                }
+                if hairlines {
+                    for w in points.windows(2) {
+                        image.wu_line(to_pixel(&w[0]), to_pixel(&w[1]), path.pen.color());
+                    }
+                    continue;
+                }
+                if !path.pressures.is_empty() && points.len() > 1 {
+                    let mut pressures = path.pressures.clone();
+                    if path.closed {
+                        pressures.push(pressures[0]);
+                    }
+                    let outline: Vec<(f32, f32)> = stroke_outline(&points, &pressures, width)
+                        .iter()
+                        .map(to_pixel)
+                        .collect();
+                    image.fill_polygon(&outline, path.pen.color());
+                    continue;
+                }
+                // Keep thin lines visible.
+                let width = (width * scale).max(1.0);
+                for w in points.windows(2) {
+                    image.stroke_segment(to_pixel(&w[0]), to_pixel(&w[1]), width, path.pen.color());
+                }
+            }
+        }
+    }
+}
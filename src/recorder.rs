@@ -0,0 +1,175 @@
+//! Recording turtle commands instead of drawing them.
+//!
+//! A `CommandRecorder` keeps the commands of a turtle program, so that it
+//! can be inspected, transformed or replayed later on any `Turtle`, e.g. on
+//! canvases with different scales.
+
+use crate::tracker::Tracker;
+use crate::{Degree, Distance, Position, Radiant, Turtle};
+
+/// A recorded turtle command. Distances and angles are given as passed to
+/// the turtle.
+#[derive(Copy, Clone, Debug)]
+pub enum Command {
+    Forward(f32),
+    MoveForward(f32),
+    Rotate(f32),
+    SetHeading(f32),
+    Goto(Position),
+    SetX(f32),
+    SetY(f32),
+    PenUp,
+    PenDown,
+    Arc { radius: f32, extent: f32 },
+    Push,
+    Pop,
+}
+
+impl Command {
+    /// Performs the command with `turtle`.
+    pub fn apply<T: Turtle>(&self, turtle: &mut T) {
+        match *self {
+            Command::Forward(distance) => turtle.forward(distance),
+            Command::MoveForward(distance) => turtle.move_forward(distance),
+            Command::Rotate(angle) => turtle.rotate(angle),
+            Command::SetHeading(angle) => turtle.set_heading(angle),
+            Command::Goto(pos) => turtle.goto(pos),
+            Command::SetX(x) => turtle.set_x(x),
+            Command::SetY(y) => turtle.set_y(y),
+            Command::PenUp => turtle.pen_up(),
+            Command::PenDown => turtle.pen_down(),
+            Command::Arc { radius, extent } => turtle.arc(radius, extent),
+            Command::Push => turtle.push(),
+            Command::Pop => {
+                turtle.pop();
+            }
+        }
+    }
+}
+
+/// A turtle which records its commands. It keeps track of its position,
+/// heading and pen like a `Canvas` without limits, so that programs can
+/// query them while being recorded.
+#[derive(Clone, Debug)]
+pub struct CommandRecorder {
+    commands: Vec<Command>,
+    tracker: Tracker<()>,
+}
+
+impl Default for CommandRecorder {
+    fn default() -> CommandRecorder {
+        CommandRecorder::new()
+    }
+}
+
+impl CommandRecorder {
+    pub fn new() -> CommandRecorder {
+        CommandRecorder {
+            commands: Vec::new(),
+            tracker: Tracker::new(()),
+        }
+    }
+
+    /// Returns the recorded commands, in order.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Returns the recorded commands, consuming the recorder.
+    pub fn into_commands(self) -> Vec<Command> {
+        self.commands
+    }
+
+    /// Performs the recorded commands with `turtle`.
+    pub fn replay<T: Turtle>(&self, turtle: &mut T) {
+        for command in self.commands.iter() {
+            command.apply(turtle);
+        }
+    }
+}
+
+impl Turtle for CommandRecorder {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        let distance = distance.into().0;
+        self.commands.push(Command::Forward(distance));
+        self.tracker.move_forward(distance);
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        let distance = distance.into().0;
+        self.commands.push(Command::MoveForward(distance));
+        self.tracker.move_forward(distance);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        let angle = angle.into();
+        self.commands.push(Command::Rotate(angle.0));
+        self.tracker.rotate(angle);
+    }
+
+    fn position(&self) -> Position {
+        self.tracker.current().pos
+    }
+
+    fn set_x(&mut self, x: f32) {
+        self.commands.push(Command::SetX(x));
+        self.tracker.state().pos.0 = x;
+    }
+
+    fn set_y(&mut self, y: f32) {
+        self.commands.push(Command::SetY(y));
+        self.tracker.state().pos.1 = y;
+    }
+
+    fn heading(&self) -> Degree {
+        self.tracker.current().heading
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        let angle = angle.into();
+        self.commands.push(Command::SetHeading(angle.0));
+        self.tracker.set_heading(angle);
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.tracker.current().pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.commands.push(Command::PenDown);
+        self.tracker.state().pendown = true;
+    }
+
+    fn pen_up(&mut self) {
+        self.commands.push(Command::PenUp);
+        self.tracker.state().pendown = false;
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.commands.push(Command::Goto(pos));
+        self.tracker.goto(pos);
+    }
+
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        let (radius, extent) = (radius.into().0, extent.into().0);
+        self.commands.push(Command::Arc { radius, extent });
+        // The end of the arc lies along the chord, halfway between the
+        // headings at both ends.
+        let turn = extent * radius.signum();
+        let chord = 2.0 * radius.abs() * (Radiant::from(Degree(extent.abs())).0 / 2.0).sin();
+        let heading = self.tracker.current().heading;
+        self.tracker
+            .advance(Degree(heading.0 + turn / 2.0), chord * extent.signum());
+        self.tracker.set_heading(Degree(heading.0 + turn));
+    }
+
+    fn push(&mut self) {
+        self.commands.push(Command::Push);
+        self.tracker.push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.commands.push(Command::Pop);
+        self.tracker.pop()
+    }
+}
@@ -0,0 +1,437 @@
+//! Reordering and joining paths to shorten pen-up travel.
+//!
+//! Plotters spend much of their time moving the raised pen between lines,
+//! in whatever order the turtle happened to draw them. Visiting the nearest
+//! line next, entered from whichever end is closer, cuts most of that, and
+//! lines continuing each other can be drawn without lifting the pen at all.
+//!
+//! Both passes are deterministic and use no randomness, so there is no seed:
+//! the same drawing is always rearranged the same way.
+
+use crate::{ArcSegment, Canvas, Path, Position};
+use std::collections::HashMap;
+use std::mem;
+
+impl Canvas {
+    /// Reorders lines to shorten the pen-up travel between them, greedily
+    /// drawing the nearest line next. Open lines may be reversed, and closed
+    /// ones started at their vertex nearest to the pen. Lines are only moved
+    /// between filled regions, dots, stamps and text, so that what covers
+    /// what stays the same, and are kept in groups by layer. The path being
+    /// drawn and a region being filled keep their places.
+    ///
+    /// Of lines equally near, the one drawn first is taken, entered at its
+    /// start rather than its end, or at its first such vertex if closed.
+    /// Layers are visited in the order they are first drawn on.
+    ///
+    /// Returns by how much the travel length, as in `Canvas::stats`, was
+    /// shortened.
+    pub fn optimize_travel(&mut self) -> f32 {
+        let before = self.stats().travel_length;
+        let mut pen = Position::origin();
+        self.rearrange_lines(|canvas, run, next| {
+            pen = canvas.push_ordered(run, pen);
+            if let Some(path) = next {
+                if path.label.is_none() && path.points.len() > 1 {
+                    pen = end_of(path);
+                }
+            }
+        });
+        before - self.stats().travel_length
+    }
+
+    /// Joins lines where one ends at the start or end of another, within
+    /// `epsilon`, if they are drawn with the same pen on the same layer.
+    /// Lines may be reversed to continue each other, and those which end up
+    /// where they started are closed. Like with `optimize_travel`, lines
+    /// are not joined across filled regions, dots, stamps and text, and the
+    /// path being drawn and a region being filled are left alone.
+    ///
+    /// Lines are extended in drawing order. Where several lines continue a
+    /// chain, the one drawn first is appended, joined at its start rather
+    /// than its end.
+    ///
+    /// Returns the number of joins.
+    pub fn join_paths(&mut self, epsilon: f32) -> usize {
+        let epsilon = epsilon.max(0.0);
+        let mut joins = 0;
+        self.rearrange_lines(|canvas, run, _| {
+            let lines = run.len();
+            let joined = join_run(run, epsilon);
+            joins += lines - joined.len();
+            canvas.paths.extend(joined);
+        });
+        joins
+    }
+
+    /// Replaces runs of lines between other paths by what `f` appends to
+    /// `paths` for them. `f` is also passed the path following the run, if
+    /// any. Lines without segments are dropped, as they draw nothing.
+    fn rearrange_lines<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Canvas, Vec<Path>, Option<&Path>),
+    {
+        self.checkpoint_full();
+        let limit = match self.filling {
+            Some(ref filling) => filling.path_index,
+            None => self.paths.len(),
+        }
+        .min(self.paths.len().saturating_sub(1));
+        let fixed = self.paths.split_off(limit);
+        let paths = mem::take(&mut self.paths);
+
+        let mut run = Vec::new();
+        for path in paths {
+            if path.is_line() {
+                if path.points.len() > 1 {
+                    run.push(path);
+                }
+                continue;
+            }
+            f(self, mem::take(&mut run), Some(&path));
+            self.paths.push(path);
+        }
+        f(self, run, None);
+
+        if let Some(ref mut filling) = self.filling {
+            filling.path_index = self.paths.len();
+        }
+        self.paths.extend(fixed);
+    }
+
+    /// Appends `run` in nearest-neighbor order, layer by layer, starting
+    /// from `pen`. Returns where the pen ends up.
+    fn push_ordered(&mut self, mut run: Vec<Path>, mut pen: Position) -> Position {
+        while let Some(layer) = run.first().map(|path| path.layer) {
+            let (remaining, rest): (Vec<Path>, Vec<Path>) =
+                run.into_iter().partition(|path| path.layer == layer);
+            run = rest;
+            let mut grid = Grid::spanning(&remaining);
+            for (i, path) in remaining.iter().enumerate() {
+                for_each_start(path, |slot, pos| grid.insert(pos, i, slot));
+            }
+            let mut remaining: Vec<Option<Path>> = remaining.into_iter().map(Some).collect();
+            for _ in 0..remaining.len() {
+                // Only lines with non-finite points are beyond any distance.
+                let (index, slot) = grid
+                    .nearest(pen)
+                    .unwrap_or_else(|| (remaining.iter().position(Option::is_some).unwrap(), 0));
+                let mut path = remaining[index].take().unwrap();
+                for_each_start(&path, |_, pos| grid.remove(pos, index));
+                if !path.closed && slot == 1 {
+                    reverse(&mut path);
+                } else if path.closed && slot > 0 {
+                    rotate(&mut path, slot);
+                }
+                pen = end_of(&path);
+                self.paths.push(path);
+            }
+        }
+        pen
+    }
+}
+
+/// A vertex a line may be started at.
+struct Vertex {
+    /// Index of the line.
+    line: usize,
+    /// Which vertex of the line it is, see `for_each_start`.
+    slot: usize,
+    pos: Position,
+}
+
+/// Vertices of lines bucketed into square cells, to find those near a
+/// position without checking every line.
+struct Grid {
+    cell: f32,
+    cells: HashMap<(i64, i64), Vec<Vertex>>,
+}
+
+impl Grid {
+    fn new(cell: f32) -> Grid {
+        Grid {
+            cell,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Returns a grid with cells sized to hold about one start of `paths`
+    /// each.
+    fn spanning(paths: &[Path]) -> Grid {
+        let mut bounds: Option<(Position, Position)> = None;
+        let mut count = 0;
+        for path in paths {
+            for_each_start(path, |_, pos| {
+                count += 1;
+                bounds = Some(match bounds {
+                    Some(bounds) => pos.min_max(&bounds),
+                    None => (pos, pos),
+                });
+            });
+        }
+        let cell = match bounds {
+            Some((min, max)) => (max.0 - min.0).max(max.1 - min.1) / (count as f32).sqrt(),
+            None => 1.0,
+        };
+        Grid::new(if cell > 0.0 && cell.is_finite() {
+            cell
+        } else {
+            1.0
+        })
+    }
+
+    fn key(&self, pos: Position) -> (i64, i64) {
+        (
+            (pos.0 / self.cell).floor() as i64,
+            (pos.1 / self.cell).floor() as i64,
+        )
+    }
+
+    fn insert(&mut self, pos: Position, line: usize, slot: usize) {
+        let key = self.key(pos);
+        self.cells
+            .entry(key)
+            .or_default()
+            .push(Vertex { line, slot, pos });
+    }
+
+    /// Removes the vertex at `pos` of `line`.
+    fn remove(&mut self, pos: Position, line: usize) {
+        let key = self.key(pos);
+        if let Some(entries) = self.cells.get_mut(&key) {
+            entries.retain(|vertex| vertex.line != line);
+            if entries.is_empty() {
+                self.cells.remove(&key);
+            }
+        }
+    }
+
+    /// Returns the vertices in the cell of `pos` and those around it, which
+    /// include all within the cell size of it.
+    fn around(&self, pos: Position) -> impl Iterator<Item = &Vertex> {
+        let key = self.key(pos);
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).map(move |dy| (dx, dy)))
+            .filter_map(move |(dx, dy)| {
+                self.cells
+                    .get(&(key.0.saturating_add(dx), key.1.saturating_add(dy)))
+            })
+            .flatten()
+    }
+
+    /// Returns the line and slot of the vertex nearest to `pen`. Of those
+    /// equally near, the one of the first line is taken, and of its
+    /// vertices the one in the first slot.
+    ///
+    /// Searches rings of cells around `pen`, outwards, until no vertex
+    /// further out can be nearer, or by then checking every vertex.
+    fn nearest(&self, pen: Position) -> Option<(usize, usize)> {
+        let center = self.key(pen);
+        let cell = |dx: i64, dy: i64| {
+            self.cells
+                .get(&(center.0.saturating_add(dx), center.1.saturating_add(dy)))
+                .into_iter()
+                .flatten()
+        };
+        let mut best = None;
+        for r in 0i64.. {
+            if (2 * r + 1).saturating_mul(2 * r + 1) >= self.cells.len() as i64 {
+                for vertex in self.cells.values().flatten() {
+                    closer(&mut best, pen, vertex);
+                }
+                break;
+            }
+            for dx in -r..=r {
+                cell(dx, -r).for_each(|vertex| closer(&mut best, pen, vertex));
+                if r > 0 {
+                    cell(dx, r).for_each(|vertex| closer(&mut best, pen, vertex));
+                }
+            }
+            for dy in 1 - r..r {
+                for dx in [-r, r] {
+                    cell(dx, dy).for_each(|vertex| closer(&mut best, pen, vertex));
+                }
+            }
+            // Vertices in further rings are more than `r` cells away.
+            if best.is_some_and(|(d, _, _)| d <= r as f32 * self.cell) {
+                break;
+            }
+        }
+        best.map(|(_, line, slot)| (line, slot))
+    }
+}
+
+/// Replaces `best`, the distance, line and slot of the nearest vertex so
+/// far, by those of `vertex` if it is nearer to `pen`, or equally near and
+/// of an earlier line or slot.
+fn closer(best: &mut Option<(f32, usize, usize)>, pen: Position, vertex: &Vertex) {
+    let (line, slot) = (vertex.line, vertex.slot);
+    let d = distance(pen, vertex.pos);
+    let better = match *best {
+        Some(best) => (d, line, slot) < best,
+        None => !d.is_nan(),
+    };
+    if better {
+        *best = Some((d, line, slot));
+    }
+}
+
+/// Calls `f` with the slot and position of every vertex `path` may be
+/// started at: its start and end, in slots 0 and 1, if it is open, or any
+/// vertex, in the slot of its index, if it is closed.
+fn for_each_start<F: FnMut(usize, Position)>(path: &Path, mut f: F) {
+    if path.closed {
+        for (k, &pos) in path.points.iter().enumerate() {
+            f(k, pos);
+        }
+    } else {
+        f(0, path.points[0]);
+        f(1, *path.points.last().unwrap());
+    }
+}
+
+/// Joins the lines of `run` continuing each other into chains, kept in the
+/// order of their first lines.
+fn join_run(run: Vec<Path>, epsilon: f32) -> Vec<Path> {
+    // Cells at least `epsilon` wide, so that the cells around an end hold
+    // all ends within `epsilon` of it.
+    let mut grid = Grid::new(epsilon.max(1e-3));
+    for (j, path) in run.iter().enumerate() {
+        if !path.closed {
+            for_each_start(path, |slot, pos| grid.insert(pos, j, slot));
+        }
+    }
+    let mut remaining: Vec<Option<Path>> = run.into_iter().map(Some).collect();
+    let mut joined = Vec::new();
+    for i in 0..remaining.len() {
+        let mut chain = match remaining[i].take() {
+            Some(path) => path,
+            None => continue,
+        };
+        if !chain.closed {
+            for_each_start(&chain, |_, pos| grid.remove(pos, i));
+            // Extend the end, then the start by extending the reversed chain.
+            for reversed in [false, true] {
+                if reversed {
+                    reverse(&mut chain);
+                }
+                while let Some((j, flip)) = continuation(&chain, &remaining, &grid, epsilon) {
+                    let mut next = remaining[j].take().unwrap();
+                    for_each_start(&next, |_, pos| grid.remove(pos, j));
+                    if flip {
+                        reverse(&mut next);
+                    }
+                    append(&mut chain, next);
+                }
+                if reversed {
+                    reverse(&mut chain);
+                }
+            }
+            let n = chain.points.len();
+            if n > 3 && distance(chain.points[0], chain.points[n - 1]) <= epsilon {
+                chain.points.pop();
+                chain.pressures.truncate(n - 1);
+                chain.arcs.retain(|arc| arc.end < n - 1);
+                chain.closed = true;
+            }
+        }
+        joined.push(chain);
+    }
+    joined
+}
+
+/// Returns the index of the first open line in `remaining` starting or
+/// ending where `chain` ends, and whether it needs to be reversed to
+/// continue it. Lines starting there are preferred. `grid` holds the ends
+/// of the remaining open lines.
+fn continuation(
+    chain: &Path,
+    remaining: &[Option<Path>],
+    grid: &Grid,
+    epsilon: f32,
+) -> Option<(usize, bool)> {
+    let end = end_of(chain);
+    grid.around(end)
+        .filter(|vertex| {
+            let path = remaining[vertex.line].as_ref().unwrap();
+            distance(end, vertex.pos) <= epsilon
+                && path.layer == chain.layer
+                && path.pen == chain.pen
+                && path.id == chain.id
+                && path.class == chain.class
+        })
+        .map(|vertex| (vertex.line, vertex.slot))
+        .min()
+        .map(|(j, slot)| (j, slot == 1))
+}
+
+/// Appends the points of `next` to `chain`, whose end point it starts at.
+fn append(chain: &mut Path, next: Path) {
+    let offset = chain.points.len() - 1;
+    if !chain.pressures.is_empty() || !next.pressures.is_empty() {
+        if chain.pressures.is_empty() {
+            chain.pressures = vec![1.0; chain.points.len()];
+        }
+        if next.pressures.is_empty() {
+            chain.pressures.extend(vec![1.0; next.points.len() - 1]);
+        } else {
+            chain.pressures.extend_from_slice(&next.pressures[1..]);
+        }
+    }
+    chain.arcs.extend(next.arcs.iter().map(|arc| ArcSegment {
+        start: arc.start + offset,
+        end: arc.end + offset,
+        ..*arc
+    }));
+    chain.points.extend_from_slice(&next.points[1..]);
+}
+
+/// Returns where the pen is after drawing `path`.
+fn end_of(path: &Path) -> Position {
+    if path.closed {
+        path.points[0]
+    } else {
+        *path.points.last().unwrap()
+    }
+}
+
+fn reverse(path: &mut Path) {
+    let last = path.points.len() - 1;
+    path.points.reverse();
+    path.pressures.reverse();
+    for arc in path.arcs.iter_mut() {
+        *arc = ArcSegment {
+            start: last - arc.end,
+            end: last - arc.start,
+            radius: arc.radius,
+            ccw: !arc.ccw,
+        };
+    }
+    path.arcs.reverse();
+}
+
+/// Makes the closed `path` start at its vertex `start`. Exact arcs running
+/// through that vertex are drawn as lines instead.
+fn rotate(path: &mut Path, start: usize) {
+    let n = path.points.len();
+    path.points.rotate_left(start);
+    if !path.pressures.is_empty() {
+        path.pressures.rotate_left(start);
+    }
+    let mut arcs: Vec<ArcSegment> = path
+        .arcs
+        .iter()
+        .filter(|arc| arc.start >= start || arc.end < start)
+        .map(|arc| ArcSegment {
+            start: (arc.start + n - start) % n,
+            end: (arc.end + n - start) % n,
+            ..*arc
+        })
+        .collect();
+    arcs.sort_by_key(|arc| arc.start);
+    path.arcs = arcs;
+}
+
+fn distance(p: Position, q: Position) -> f32 {
+    ((q.0 - p.0).powi(2) + (q.1 - p.1).powi(2)).sqrt()
+}
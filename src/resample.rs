@@ -0,0 +1,125 @@
+//! Resampling paths at uniform spacing.
+//!
+//! Stitch-based formats like embroidery need vertices at regular distances
+//! along a line, regardless of how the turtle moved to draw it.
+
+use crate::{Canvas, Path, Position};
+
+/// Largest number of vertices resampling may produce, so that a tiny
+/// spacing can't exhaust memory.
+const MAX_VERTICES: f64 = 1e7;
+
+impl Canvas {
+    /// Replaces the vertices of all lines by vertices `spacing` apart along
+    /// them. Each line keeps its first and last point, so the last gap may
+    /// be shorter; closed paths are resampled around their closing segment.
+    /// Pen pressure is interpolated. Dots, stamps and text are left as they
+    /// are. Has no effect unless `spacing` is positive, or if it would
+    /// produce more than ten million vertices.
+    pub fn resample(&mut self, spacing: f32) {
+        if spacing <= 0.0 || !spacing.is_finite() {
+            return;
+        }
+        let length: f64 = self
+            .paths
+            .iter()
+            .filter(|path| is_line(path))
+            .map(|path| path_length(path) as f64)
+            .sum();
+        if length / spacing as f64 > MAX_VERTICES {
+            return;
+        }
+        self.checkpoint_full();
+        for path in self.paths.iter_mut() {
+            if is_line(path) {
+                resample_path(path, spacing);
+            }
+        }
+    }
+}
+
+fn is_line(path: &Path) -> bool {
+    path.dot.is_none() && path.stamp.is_none() && path.label.is_none()
+}
+
+fn distance(p: Position, q: Position) -> f32 {
+    ((q.0 - p.0).powi(2) + (q.1 - p.1).powi(2)).sqrt()
+}
+
+/// Length of a path, including its closing segment.
+fn path_length(path: &Path) -> f32 {
+    let closing = match (path.closed, path.points.first(), path.points.last()) {
+        (true, Some(&first), Some(&last)) => distance(last, first),
+        _ => 0.0,
+    };
+    path.points
+        .windows(2)
+        .map(|pair| distance(pair[0], pair[1]))
+        .sum::<f32>()
+        + closing
+}
+
+fn resample_path(path: &mut Path, spacing: f32) {
+    if path.points.len() < 2 {
+        return;
+    }
+    let mut points = path.points.clone();
+    let mut pressures = path.pressures.clone();
+    if path.closed {
+        points.push(points[0]);
+        if !pressures.is_empty() {
+            pressures.push(pressures[0]);
+        }
+    }
+    let pressure_at = |i: usize, t: f32| match (pressures.get(i), pressures.get(i + 1)) {
+        (Some(&p), Some(&q)) => p + (q - p) * t,
+        _ => 1.0,
+    };
+
+    let mut resampled = vec![points[0]];
+    let mut resampled_pressures = Vec::new();
+    if !pressures.is_empty() {
+        resampled_pressures.push(pressures[0]);
+    }
+    // Distance along the current segment to the next vertex. Vertices are
+    // placed by their index from there, as adding up the spacing would stop
+    // advancing once it falls below the precision of the distance.
+    let mut next = spacing;
+    for i in 0..points.len() - 1 {
+        let (p, q) = (points[i], points[i + 1]);
+        let length = distance(p, q);
+        if length == 0.0 || next > length {
+            next -= length;
+            continue;
+        }
+        let count = ((length - next) / spacing).floor() as usize + 1;
+        for k in 0..count {
+            let t = ((next + k as f32 * spacing) / length).min(1.0);
+            resampled.push(Position(p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t));
+            if !pressures.is_empty() {
+                resampled_pressures.push(pressure_at(i, t));
+            }
+        }
+        next = next + count as f32 * spacing - length;
+    }
+    // Keep the end point unless a vertex fell close to it already.
+    let end = *points.last().unwrap();
+    let last = *resampled.last().unwrap();
+    let gap = distance(last, end);
+    if gap > spacing * 1e-3 || resampled.len() == 1 {
+        resampled.push(end);
+        if !pressures.is_empty() {
+            resampled_pressures.push(*pressures.last().unwrap());
+        }
+    } else {
+        *resampled.last_mut().unwrap() = end;
+    }
+    if path.closed && resampled.len() > 1 {
+        // The closing segment is implicit again.
+        resampled.pop();
+        resampled_pressures.truncate(resampled.len());
+    }
+    path.points = resampled;
+    path.pressures = resampled_pressures;
+    path.arcs.clear();
+}
@@ -0,0 +1,47 @@
+//! Random numbers for generative features. All stochastic helpers take a
+//! `RandomSource`, so they produce reproducible output without external
+//! dependencies, and users can inject their own generator or a recorded
+//! sequence.
+
+/// A source of random numbers. Only `next_u64` has to be implemented.
+pub trait RandomSource {
+    /// Returns the next uniformly distributed 64-bit number.
+    fn next_u64(&mut self) -> u64;
+
+    /// Returns a uniformly distributed number in `0.0..1.0`.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Returns a uniformly distributed number in `low..high`.
+    fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + self.next_f32() * (high - low)
+    }
+
+    /// Returns a uniformly distributed index in `0..n`. `n` must not be zero.
+    fn index(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// SplitMix64 pseudo random number generator.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        SplitMix64 { state: seed }
+    }
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,147 @@
+//! Serving a drawing over HTTP, for a live preview in the browser.
+//!
+//! A `PreviewServer` serves a page showing the latest SVG passed to
+//! `update`. The page polls for changes, and keeps polling while the server
+//! is down, so a tab stays up to date across re-runs of a generator
+//! program that binds the same address.
+
+use crate::Canvas;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>Turtle preview</title>
+<style>body { margin: 0; } img { width: 100vw; height: 100vh; object-fit: contain; }</style>
+</head>
+<body>
+<img id="drawing" src="/drawing.svg">
+<script>
+let version = null;
+async function poll() {
+  try {
+    const v = await (await fetch("/version", { cache: "no-store" })).text();
+    if (version !== null && v !== version) {
+      document.getElementById("drawing").src = "/drawing.svg?v=" + v;
+    }
+    version = v;
+  } catch (e) {}
+  setTimeout(poll, 500);
+}
+poll();
+</script>
+</body>
+</html>
+"#;
+
+struct Drawing {
+    /// Changes with every update, also across runs.
+    version: u128,
+    svg: Vec<u8>,
+}
+
+/// An HTTP server showing the latest drawing at `/`, answering requests on
+/// a background thread.
+pub struct PreviewServer {
+    addr: SocketAddr,
+    drawing: Arc<Mutex<Drawing>>,
+    thread: JoinHandle<()>,
+}
+
+impl PreviewServer {
+    /// Starts serving on `addr`, e.g. `"127.0.0.1:8000"`, initially an empty
+    /// drawing.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<PreviewServer> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let drawing = Arc::new(Mutex::new(Drawing {
+            version: now(),
+            svg: Vec::new(),
+        }));
+        let shared = Arc::clone(&drawing);
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A failing client mustn't stop the server.
+                let _ = respond(stream, &shared);
+            }
+        });
+        Ok(PreviewServer {
+            addr,
+            drawing,
+            thread,
+        })
+    }
+
+    /// Returns the address served, with the actual port if bound to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Shows the current drawing of `canvas`.
+    pub fn update(&self, canvas: &Canvas) -> io::Result<()> {
+        let mut svg = Vec::new();
+        canvas.save_svg(&mut svg)?;
+        let mut drawing = self.drawing.lock().unwrap();
+        drawing.version = now().max(drawing.version + 1);
+        drawing.svg = svg;
+        Ok(())
+    }
+
+    /// Keeps serving until the process is ended, e.g. at the end of a
+    /// generator program.
+    pub fn wait(self) {
+        let _ = self.thread.join();
+    }
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos())
+}
+
+fn respond(stream: TcpStream, drawing: &Mutex<Drawing>) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Skip the headers.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let target = request.split_whitespace().nth(1).unwrap_or("/");
+    let path = target.split('?').next().unwrap_or(target);
+    let (status, content_type, body) = match path {
+        "/" => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            PAGE.as_bytes().to_vec(),
+        ),
+        "/drawing.svg" => (
+            "200 OK",
+            "image/svg+xml",
+            drawing.lock().unwrap().svg.clone(),
+        ),
+        "/version" => (
+            "200 OK",
+            "text/plain",
+            drawing.lock().unwrap().version.to_string().into_bytes(),
+        ),
+        _ => ("404 Not Found", "text/plain", b"not found".to_vec()),
+    };
+    let mut stream = reader.into_inner();
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)
+}
@@ -0,0 +1,50 @@
+/// The glyph drawn by `Canvas::stamp`.
+///
+/// Shapes are given in local coordinates with the turtle at the origin,
+/// heading towards positive y.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Shape {
+    /// A small arrowhead, the classic turtle cursor.
+    #[default]
+    Arrow,
+    /// A turtle seen from above.
+    Turtle,
+    /// A custom polygon.
+    Polygon(Vec<(f32, f32)>),
+}
+
+impl Shape {
+    /// Returns the outline of the shape.
+    pub fn outline(&self) -> Vec<(f32, f32)> {
+        match *self {
+            Shape::Arrow => vec![(0.0, 5.0), (-4.0, -3.0), (0.0, -1.0), (4.0, -3.0)],
+            Shape::Turtle => vec![
+                (0.0, 16.0),
+                (-2.0, 14.0),
+                (-1.0, 10.0),
+                (-4.0, 7.0),
+                (-7.0, 9.0),
+                (-9.0, 8.0),
+                (-6.0, 5.0),
+                (-7.0, 1.0),
+                (-5.0, -3.0),
+                (-8.0, -6.0),
+                (-6.0, -8.0),
+                (-4.0, -5.0),
+                (0.0, -7.0),
+                (4.0, -5.0),
+                (6.0, -8.0),
+                (8.0, -6.0),
+                (5.0, -3.0),
+                (7.0, 1.0),
+                (6.0, 5.0),
+                (9.0, 8.0),
+                (7.0, 9.0),
+                (4.0, 7.0),
+                (1.0, 10.0),
+                (2.0, 14.0),
+            ],
+            Shape::Polygon(ref points) => points.clone(),
+        }
+    }
+}
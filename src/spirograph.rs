@@ -0,0 +1,154 @@
+//! Spirograph curves: hypotrochoids and epitrochoids.
+//!
+//! A circle of radius `rolling_radius` rolls without slipping inside
+//! (hypotrochoid) or outside (epitrochoid) a fixed circle of radius
+//! `fixed_radius`, and a pen at distance `offset` from the center of the
+//! rolling circle traces the curve.
+
+use crate::{Position, Radiant, Turtle};
+use std::f32::consts::PI;
+
+/// The largest number of times the rolling circle goes around before the
+/// curve is drawn as closed.
+const MAX_TURNS: usize = 1000;
+
+/// The largest angle in degree which the rolling circle moves around or
+/// turns about itself per line segment.
+const MAX_STEP: f32 = 2.0;
+
+/// The radii and pen offset of a spirograph curve.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spirograph {
+    fixed_radius: f32,
+    rolling_radius: f32,
+    offset: f32,
+    inside: bool,
+}
+
+impl Spirograph {
+    /// A hypotrochoid, rolling inside the fixed circle.
+    pub fn hypotrochoid(fixed_radius: f32, rolling_radius: f32, offset: f32) -> Spirograph {
+        Spirograph {
+            fixed_radius,
+            rolling_radius,
+            offset,
+            inside: true,
+        }
+    }
+
+    /// An epitrochoid, rolling outside the fixed circle.
+    pub fn epitrochoid(fixed_radius: f32, rolling_radius: f32, offset: f32) -> Spirograph {
+        Spirograph {
+            fixed_radius,
+            rolling_radius,
+            offset,
+            inside: false,
+        }
+    }
+
+    /// Returns how often the rolling circle goes around the fixed one until
+    /// the curve closes. Radii without small common multiple are cut off
+    /// after 1000 turns.
+    pub fn turns(&self) -> usize {
+        let ratio = self.fixed_radius / self.rolling_radius;
+        if !ratio.is_finite() {
+            return 1;
+        }
+        (1..MAX_TURNS)
+            .find(|&n| {
+                let revolutions = n as f32 * ratio;
+                (revolutions - revolutions.round()).abs() < 1e-3
+            })
+            .unwrap_or(MAX_TURNS)
+    }
+
+    /// Returns the point of the curve after the rolling circle went `angle`
+    /// radians around the fixed one, relative to the center of the fixed
+    /// circle. The curve starts on the positive x axis, at `fixed_radius -
+    /// rolling_radius + offset` for hypotrochoids and `fixed_radius +
+    /// rolling_radius - offset` for epitrochoids.
+    pub fn point(&self, angle: f32) -> Position {
+        let (center, spin) = self.center_and_spin();
+        // The pen starts outside for hypotrochoids, inside for epitrochoids.
+        let d = if self.inside {
+            self.offset
+        } else {
+            -self.offset
+        };
+        Position(
+            center * angle.cos() + d * (spin * angle).cos(),
+            center * angle.sin() - d * (spin * angle).sin(),
+        )
+    }
+
+    /// Returns the distance of the center of the rolling circle from the
+    /// center of the fixed one, and how fast the rolling circle turns about
+    /// itself relative to going around.
+    fn center_and_spin(&self) -> (f32, f32) {
+        let (big, r) = (self.fixed_radius, self.rolling_radius);
+        let center = if self.inside { big - r } else { big + r };
+        (center, center / r)
+    }
+
+    /// Draws the whole curve around the position of the turtle, with the
+    /// x axis of `point` pointing in the direction of its heading. The
+    /// number of segments is chosen so that neither the rolling circle nor
+    /// the pen turns by more than two degree at once.
+    ///
+    /// The turtle first moves to the start of the curve without drawing,
+    /// and ends at its original position and heading. Nothing is drawn
+    /// unless the rolling radius is positive.
+    pub fn draw<T: Turtle>(&self, turtle: &mut T) {
+        if self.rolling_radius.is_nan() || self.rolling_radius <= 0.0 {
+            return;
+        }
+        let heading = turtle.heading();
+        let (_, spin) = self.center_and_spin();
+        let total = 2.0 * PI * self.turns() as f32;
+        let step = (MAX_STEP.to_radians() / spin.abs().max(1.0)).max(1e-4);
+        let segments = ((total / step).ceil() as usize).max(1);
+
+        // The curve is traced relative to the turtle, so that it doesn't
+        // matter which way the headings of the turtle count.
+        let mut pen = Pen {
+            pos: Position::origin(),
+            angle: 0.0,
+        };
+        pen.travel(turtle, self.point(0.0), false);
+        for i in 1..=segments {
+            let angle = total * i as f32 / segments as f32;
+            pen.travel(turtle, self.point(angle), true);
+        }
+        pen.travel(turtle, Position::origin(), false);
+        turtle.set_heading(heading);
+    }
+}
+
+/// Where the turtle is relative to its starting point, with the x axis
+/// pointing ahead and the y axis to the left of it.
+struct Pen {
+    pos: Position,
+    /// Direction of the turtle, counterclockwise from the x axis in
+    /// radians.
+    angle: f32,
+}
+
+impl Pen {
+    /// Moves `turtle` straight to `pos`, drawing a line if `draw` is set.
+    fn travel<T: Turtle>(&mut self, turtle: &mut T, pos: Position, draw: bool) {
+        let (dx, dy) = (pos.0 - self.pos.0, pos.1 - self.pos.1);
+        let distance = dx.hypot(dy);
+        if distance == 0.0 {
+            return;
+        }
+        let angle = dy.atan2(dx);
+        turtle.rotate(Radiant(angle - self.angle));
+        if draw {
+            turtle.forward(distance);
+        } else {
+            turtle.move_forward(distance);
+        }
+        self.pos = pos;
+        self.angle = angle;
+    }
+}
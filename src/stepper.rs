@@ -0,0 +1,120 @@
+//! Stepping through recorded turtle programs, for debugging.
+
+use crate::recorder::Command;
+use crate::{Canvas, CommandRecorder};
+
+/// Performs recorded commands one at a time on a canvas, which can be
+/// inspected between steps. Breakpoints stop a run when the state stack
+/// reaches a given depth, which follows the recursion of branching
+/// drawings.
+#[derive(Clone)]
+pub struct Stepper {
+    commands: Vec<Command>,
+    /// The canvas before the first command, for rewinding.
+    start: Canvas,
+    canvas: Canvas,
+    /// Number of commands performed.
+    position: usize,
+    /// Number of pushed states.
+    depth: usize,
+    breakpoints: Vec<usize>,
+}
+
+impl Stepper {
+    /// Creates a stepper performing `commands` on a copy of `canvas`, which
+    /// gives the initial settings like pen and heading mode.
+    pub fn new(commands: Vec<Command>, canvas: Canvas) -> Stepper {
+        Stepper {
+            commands,
+            start: canvas.clone(),
+            canvas,
+            position: 0,
+            depth: 0,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Creates a stepper for the commands of `recorder`, drawing on a new
+    /// canvas.
+    pub fn from_recorder(recorder: CommandRecorder) -> Stepper {
+        Stepper::new(recorder.into_commands(), Canvas::new())
+    }
+
+    /// Makes `run` stop after a command which pushes the state stack to
+    /// `depth`.
+    pub fn breakpoint_at_depth(&mut self, depth: usize) {
+        if !self.breakpoints.contains(&depth) {
+            self.breakpoints.push(depth);
+        }
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Performs the next command and returns it, or `None` at the end.
+    pub fn step(&mut self) -> Option<Command> {
+        let command = *self.commands.get(self.position)?;
+        command.apply(&mut self.canvas);
+        match command {
+            Command::Push => self.depth += 1,
+            Command::Pop => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+        self.position += 1;
+        Some(command)
+    }
+
+    /// Performs commands until `n` are performed in total, rewinding first
+    /// if more are.
+    pub fn run_to(&mut self, n: usize) {
+        if n < self.position {
+            self.rewind();
+        }
+        while self.position < n && self.step().is_some() {}
+    }
+
+    /// Performs commands until a breakpoint is hit or all are performed.
+    /// Returns `true` if stopped at a breakpoint.
+    pub fn run(&mut self) -> bool {
+        while let Some(command) = self.step() {
+            if let Command::Push = command {
+                if self.breakpoints.contains(&self.depth) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Restores the canvas to before the first command.
+    pub fn rewind(&mut self) {
+        self.canvas = self.start.clone();
+        self.position = 0;
+        self.depth = 0;
+    }
+
+    /// Returns the canvas as drawn by the commands performed so far.
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    /// Returns the number of commands performed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns the number of pushed states.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the command `step` performs next.
+    pub fn next_command(&self) -> Option<&Command> {
+        self.commands.get(self.position)
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+}
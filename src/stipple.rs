@@ -0,0 +1,143 @@
+//! Point distributions for stippling: Poisson-disk sampling and
+//! brightness-weighted stippling of a grayscale image.
+//!
+//! Both use Bridson's algorithm on a background grid. All randomness comes
+//! from the passed generator, so equal seeds give equal point sets.
+
+use crate::rng::RandomSource;
+use crate::{Canvas, Position};
+use std::f32::consts::PI;
+
+/// Number of candidates tried around an active point before it is retired.
+const CANDIDATES: usize = 30;
+
+/// Variable-radius Bridson sampling in the rectangle `min`-`max`, where
+/// `radius(p)` is the minimum distance of other points to `p` and lies in
+/// `min_radius..=max_radius`.
+fn sample<F: FnMut(Position) -> f32, R: RandomSource + ?Sized>(
+    min: Position,
+    max: Position,
+    min_radius: f32,
+    max_radius: f32,
+    mut radius: F,
+    rng: &mut R,
+) -> Vec<Position> {
+    let (width, height) = (max.0 - min.0, max.1 - min.1);
+    if width <= 0.0 || height <= 0.0 || min_radius <= 0.0 {
+        return Vec::new();
+    }
+    let cell = min_radius / 2f32.sqrt();
+    let (cols, rows) = (
+        (width / cell).ceil() as usize + 1,
+        (height / cell).ceil() as usize + 1,
+    );
+    // Each grid cell holds at most one point.
+    let mut grid: Vec<Option<usize>> = vec![None; cols * rows];
+    let cell_of = |p: Position| {
+        (
+            ((p.0 - min.0) / cell) as usize,
+            ((p.1 - min.1) / cell) as usize,
+        )
+    };
+    let reach = (max_radius / cell).ceil() as isize;
+
+    let mut points: Vec<(Position, f32)> = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+
+    let first = Position(rng.range(min.0, max.0), rng.range(min.1, max.1));
+    let r = radius(first);
+    let (cx, cy) = cell_of(first);
+    grid[cy * cols + cx] = Some(0);
+    points.push((first, r));
+    active.push(0);
+
+    while !active.is_empty() {
+        let slot = rng.index(active.len());
+        let (center, r) = points[active[slot]];
+        let mut found = false;
+        for _ in 0..CANDIDATES {
+            let angle = rng.range(0.0, 2.0 * PI);
+            let dist = rng.range(r, 2.0 * r);
+            let p = Position(center.0 + dist * angle.cos(), center.1 + dist * angle.sin());
+            if p.0 < min.0 || p.1 < min.1 || p.0 >= max.0 || p.1 >= max.1 {
+                continue;
+            }
+            let rp = radius(p);
+            let (cx, cy) = cell_of(p);
+            let mut fits = true;
+            'search: for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (x, y) = (cx as isize + dx, cy as isize + dy);
+                    if x < 0 || y < 0 || x >= cols as isize || y >= rows as isize {
+                        continue;
+                    }
+                    if let Some(other) = grid[y as usize * cols + x as usize] {
+                        let (q, rq) = points[other];
+                        let (ddx, ddy) = (p.0 - q.0, p.1 - q.1);
+                        let limit = rp.max(rq);
+                        if ddx * ddx + ddy * ddy < limit * limit {
+                            fits = false;
+                            break 'search;
+                        }
+                    }
+                }
+            }
+            if fits && grid[cy * cols + cx].is_none() {
+                grid[cy * cols + cx] = Some(points.len());
+                active.push(points.len());
+                points.push((p, rp));
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            active.swap_remove(slot);
+        }
+    }
+
+    points.into_iter().map(|(p, _)| p).collect()
+}
+
+/// Returns points in the rectangle `min`-`max` which are at least `radius`
+/// apart from each other, and densely cover the rectangle.
+pub fn poisson_disk<R: RandomSource + ?Sized>(
+    min: Position,
+    max: Position,
+    radius: f32,
+    rng: &mut R,
+) -> Vec<Position> {
+    sample(min, max, radius, radius, |_| radius, rng)
+}
+
+/// Returns stipple points in the rectangle `min`-`max` for the grayscale
+/// image given by `brightness`, which maps a position to `0.0` (black) up to
+/// `1.0` (white). Points are spaced `min_radius` apart in black areas, and up
+/// to `max_radius` apart in white areas.
+pub fn stipple<F: FnMut(Position) -> f32, R: RandomSource + ?Sized>(
+    min: Position,
+    max: Position,
+    min_radius: f32,
+    max_radius: f32,
+    mut brightness: F,
+    rng: &mut R,
+) -> Vec<Position> {
+    let max_radius = max_radius.max(min_radius);
+    sample(
+        min,
+        max,
+        min_radius,
+        max_radius,
+        |p| min_radius + brightness(p).clamp(0.0, 1.0) * (max_radius - min_radius),
+        rng,
+    )
+}
+
+impl Canvas {
+    /// Draws a dot of `diameter` at each of `points`, like `dot` does.
+    pub fn draw_dots(&mut self, points: &[Position], diameter: f32) {
+        self.checkpoint();
+        for &p in points {
+            self.add_dot(p, diameter);
+        }
+    }
+}
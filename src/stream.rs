@@ -0,0 +1,399 @@
+//! Incremental SVG output for live viewers.
+//!
+//! Instead of writing a whole document, an `SvgStream` hands out the changes
+//! since it was last polled as a list of patches, e.g. to be sent to a
+//! browser over server-sent events or a WebSocket. The viewer keeps an SVG
+//! document with one group per layer and applies the patches to it:
+//!
+//! ```text
+//! <svg viewBox="..."><rect /><defs /><g stroke="black" stroke-width="...">
+//!   <g id="layer">...</g>
+//! </g></svg>
+//! ```
+//!
+//! A path is sent once the turtle has moved on to the next one, as the path
+//! being drawn may still grow. Paths are never updated in place; when earlier
+//! paths are rewritten (undo, `normalize`, `end_fill`, ...), the stream
+//! starts over with `SvgPatch::Reset`.
+//!
+//! Streams ignore the export orientation, cylinder wrapping, turtle glyphs
+//! and the debug overlay. Pressure
+//! outlines of pens without a width use the stroke width at the time they
+//! are sent.
+//!
+//! For drawings too large to keep in a `Canvas`, an `SvgStreamTurtle` writes
+//! an SVG document directly while the turtle moves, in constant memory.
+
+use crate::tracker::Tracker;
+use crate::{Canvas, Color, Degree, Distance, Position, Rect, Turtle};
+use std::io::{self, Write};
+
+/// A change to the SVG document of a viewer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SvgPatch {
+    /// Remove all definitions and elements.
+    Reset,
+    /// Set the view box and the default stroke width of the document.
+    ViewBox {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        stroke_width: f32,
+    },
+    /// Set the fill color of the background rectangle covering the view box,
+    /// or remove the background.
+    Background(Option<String>),
+    /// Set the layer groups, from bottom to top. Groups not yet present are
+    /// created empty, existing groups are reordered.
+    Layers(Vec<String>),
+    /// Append an element to `<defs>`.
+    Define(String),
+    /// Append an element to the group of a layer.
+    Append { layer: String, element: String },
+}
+
+impl SvgPatch {
+    /// Serializes the patch as a single-line JSON object, suitable as the
+    /// data of a server-sent event or a WebSocket text message.
+    ///
+    /// The object has a `"type"` of `"reset"`, `"viewbox"`, `"background"`,
+    /// `"layers"`, `"define"` or `"append"` and the fields of the variant.
+    pub fn to_json(&self) -> String {
+        match *self {
+            SvgPatch::Reset => r#"{"type":"reset"}"#.to_string(),
+            SvgPatch::ViewBox {
+                x,
+                y,
+                width,
+                height,
+                stroke_width,
+            } => format!(
+                r#"{{"type":"viewbox","x":{},"y":{},"width":{},"height":{},"stroke_width":{}}}"#,
+                json_number(x),
+                json_number(y),
+                json_number(width),
+                json_number(height),
+                json_number(stroke_width)
+            ),
+            SvgPatch::Background(ref color) => format!(
+                r#"{{"type":"background","color":{}}}"#,
+                color
+                    .as_ref()
+                    .map_or_else(|| "null".to_string(), |c| json_string(c))
+            ),
+            SvgPatch::Layers(ref names) => {
+                let names: Vec<String> = names.iter().map(|name| json_string(name)).collect();
+                format!(r#"{{"type":"layers","names":[{}]}}"#, names.join(","))
+            }
+            SvgPatch::Define(ref element) => {
+                format!(r#"{{"type":"define","element":{}}}"#, json_string(element))
+            }
+            SvgPatch::Append {
+                ref layer,
+                ref element,
+            } => format!(
+                r#"{{"type":"append","layer":{},"element":{}}}"#,
+                json_string(layer),
+                json_string(element)
+            ),
+        }
+    }
+}
+
+/// Formats a number for JSON, which has no representation of infinity or NaN.
+fn json_number(n: f32) -> String {
+    if n.is_finite() {
+        n.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Tracks what has been sent to a viewer of a canvas.
+#[derive(Clone, Debug, Default)]
+pub struct SvgStream {
+    /// Revision of the canvas when the stream (re)started, `None` before the
+    /// first poll.
+    revision: Option<u64>,
+    /// Number of paths sent.
+    paths: usize,
+    /// Number of shapes defined.
+    shapes: usize,
+    view_box: Option<(f32, f32, f32, f32, f32)>,
+    background: Option<String>,
+    layers: Vec<String>,
+}
+
+impl SvgStream {
+    /// Creates a stream for a viewer with an empty document.
+    pub fn new() -> SvgStream {
+        SvgStream::default()
+    }
+
+    /// Returns the patches which bring the viewer up to date with the
+    /// finished paths of `canvas`.
+    pub fn poll(&mut self, canvas: &Canvas) -> Vec<SvgPatch> {
+        let finished = canvas.paths.len().saturating_sub(1);
+        self.patches(canvas, finished)
+    }
+
+    /// Returns the patches which bring the viewer up to date with all paths
+    /// of `canvas`, including the path being drawn, and ends the stream.
+    pub fn finish(mut self, canvas: &Canvas) -> Vec<SvgPatch> {
+        self.patches(canvas, canvas.paths.len())
+    }
+
+    fn patches(&mut self, canvas: &Canvas, until: usize) -> Vec<SvgPatch> {
+        let mut patches = Vec::new();
+
+        if self.revision != Some(canvas.revision) {
+            if self.revision.is_some() {
+                patches.push(SvgPatch::Reset);
+            }
+            *self = SvgStream {
+                revision: Some(canvas.revision),
+                ..SvgStream::default()
+            };
+        }
+
+        let (top_left, width, height, stroke_width) = canvas.svg_frame();
+        let view_box = (top_left.0, top_left.1, width, height, stroke_width);
+        if self.view_box != Some(view_box) {
+            self.view_box = Some(view_box);
+            patches.push(SvgPatch::ViewBox {
+                x: top_left.0,
+                y: top_left.1,
+                width,
+                height,
+                stroke_width,
+            });
+        }
+
+        let background = canvas.background.map(|color| color.to_string());
+        if self.background != background {
+            self.background = background.clone();
+            patches.push(SvgPatch::Background(background));
+        }
+
+        let layers: Vec<String> = canvas
+            .layer_order
+            .iter()
+            .map(|&i| canvas.layers[i].clone())
+            .collect();
+        if self.layers != layers {
+            self.layers = layers.clone();
+            patches.push(SvgPatch::Layers(layers));
+        }
+
+        while self.shapes < canvas.shapes.len() {
+            let mut element = Vec::new();
+            canvas
+                .write_svg_shape(&mut element, self.shapes)
+                .expect("writing to a Vec can't fail");
+            patches.push(SvgPatch::Define(into_element(element)));
+            self.shapes += 1;
+        }
+
+        while self.paths < until {
+            let path = &canvas.paths[self.paths];
+            let mut element = Vec::new();
+            canvas
+                .write_svg_path(&mut element, path, stroke_width)
+                .expect("writing to a Vec can't fail");
+            if !element.is_empty() {
+                patches.push(SvgPatch::Append {
+                    layer: canvas.layers[path.layer].clone(),
+                    element: into_element(element),
+                });
+            }
+            self.paths += 1;
+        }
+
+        patches
+    }
+}
+
+/// A turtle writing an SVG document as it draws, without keeping the
+/// drawing in memory. The view box has to be known up front.
+///
+/// Consecutive lines with the same pen are written as one `<path>`
+/// element, with a new subpath where the turtle moved without drawing.
+/// Write errors are kept until `finish`, which ends the document; drawing
+/// stops at the first one.
+pub struct SvgStreamTurtle<W: Write> {
+    writer: W,
+    /// Pen color and width.
+    tracker: Tracker<(Color, Option<f32>)>,
+    /// End of the path data written so far, while a `<path>` is open.
+    open: Option<Position>,
+    /// Pen of the open `<path>`.
+    open_pen: (Color, Option<f32>),
+    error: Option<io::Error>,
+}
+
+impl<W: Write> SvgStreamTurtle<W> {
+    /// Writes the start of a document showing `view_box`, in turtle
+    /// coordinates, and returns a turtle drawing into it with black lines of
+    /// the default width of the SVG export.
+    pub fn new(mut writer: W, view_box: Rect) -> io::Result<SvgStreamTurtle<W>> {
+        let stroke_width = view_box.width.max(view_box.height) / 1000.0;
+        writeln!(
+            writer,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" version="1.1" baseProfile="full" viewBox="{} {} {} {}">
+<g stroke="black" stroke-width="{}" fill="none">"#,
+            view_box.x,
+            -(view_box.y + view_box.height),
+            view_box.width,
+            view_box.height,
+            stroke_width
+        )?;
+        Ok(SvgStreamTurtle {
+            writer,
+            tracker: Tracker::new((Color::BLACK, None)),
+            open: None,
+            open_pen: (Color::BLACK, None),
+            error: None,
+        })
+    }
+
+    pub fn set_pen_color(&mut self, color: Color) {
+        self.tracker.state().pen.0 = color;
+    }
+
+    /// Sets the width of lines, in turtle units.
+    pub fn set_pen_width(&mut self, width: f32) {
+        self.tracker.state().pen.1 = Some(width);
+    }
+
+    /// Ends the document and returns the writer, or the first write error.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.close_path();
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+        writeln!(self.writer, "</g>\n</svg>")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Ends the open `<path>` element, if any.
+    fn close_path(&mut self) {
+        if self.open.take().is_some() {
+            self.write(format_args!("\" />\n"));
+        }
+    }
+
+    fn write(&mut self, args: std::fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(error) = self.writer.write_fmt(args) {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Writes a line from `from` to `to`.
+    fn line(&mut self, from: Position, to: Position, pen: (Color, Option<f32>)) {
+        if self.open.is_some() && self.open_pen != pen {
+            self.close_path();
+        }
+        match self.open {
+            None => {
+                self.write(format_args!("<path"));
+                if pen.0 != Color::BLACK {
+                    self.write(format_args!(r#" stroke="{}""#, pen.0));
+                }
+                if let Some(width) = pen.1 {
+                    self.write(format_args!(r#" stroke-width="{}""#, width));
+                }
+                self.write(format_args!(r#" d="M{} {}"#, from.0, -from.1));
+                self.open_pen = pen;
+            }
+            Some(end) if end.0 != from.0 || end.1 != from.1 => {
+                self.write(format_args!(" M{} {}", from.0, -from.1));
+            }
+            Some(_) => {}
+        }
+        self.write(format_args!(" L{} {}", to.0, -to.1));
+        self.open = Some(to);
+    }
+}
+
+impl<W: Write> Turtle for SvgStreamTurtle<W> {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        if let Some(from) = self.tracker.forward(distance.into().0) {
+            let to = self.tracker.current().pos;
+            self.line(from.pos, to, from.pen);
+        }
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        self.tracker.move_forward(distance.into().0);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.rotate(angle.into());
+    }
+
+    fn position(&self) -> Position {
+        self.tracker.current().pos
+    }
+
+    fn heading(&self) -> Degree {
+        self.tracker.current().heading
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.set_heading(angle.into());
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.tracker.current().pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.tracker.state().pendown = true;
+    }
+
+    fn pen_up(&mut self) {
+        self.tracker.state().pendown = false;
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.tracker.goto(pos);
+    }
+
+    fn push(&mut self) {
+        self.tracker.push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.tracker.pop()
+    }
+}
+
+fn into_element(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes)
+        .expect("SVG output is UTF-8")
+        .trim_end()
+        .to_string()
+}
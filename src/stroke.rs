@@ -0,0 +1,39 @@
+//! Recording strokes of pen input.
+//!
+//! Input devices like graphics tablets report a stream of positions, each
+//! with the pressure of the pen. Recorded as a stroke of the turtle, such
+//! input can be edited, undone and exported like any other drawing.
+
+use crate::{Canvas, Position};
+
+impl Canvas {
+    /// Records a stroke of pen input, given as samples of the position and
+    /// pressure of the pen. The turtle jumps to the first sample and draws
+    /// a single line through the others, its width varying with the
+    /// pressure as set by `set_pen_pressure`, regardless of the pen state
+    /// and travel mode. Afterwards the turtle rests at the last sample with
+    /// its pen and pressure as before. The stroke is undone as a whole.
+    pub fn record_stroke<I>(&mut self, samples: I)
+    where
+        I: IntoIterator<Item = (Position, f32)>,
+    {
+        let mut samples = samples.into_iter();
+        let (start, pressure) = match samples.next() {
+            Some(sample) => sample,
+            None => return,
+        };
+        self.checkpoint();
+        let state = self.current_state();
+        let (pendown, outer) = (state.pendown, state.pressure);
+        self.current_state_mut().pressure = pressure.max(0.0);
+        self.jump_to(start);
+        self.current_state_mut().pendown = true;
+        for (pos, pressure) in samples {
+            self.current_state_mut().pressure = pressure.max(0.0);
+            self.travel_to(pos);
+        }
+        let state = self.current_state_mut();
+        state.pendown = pendown;
+        state.pressure = outer;
+    }
+}
@@ -0,0 +1,118 @@
+//! Sending the commands of a turtle program to two turtles at once.
+
+use crate::{Degree, Distance, Position, Turtle};
+
+/// A turtle which forwards every command to two turtles, e.g. to draw on a
+/// `Canvas` while recording with a `CommandRecorder`. Nest tees to drive
+/// more than two turtles. Queries like `position` are answered by the first
+/// turtle.
+#[derive(Clone, Debug)]
+pub struct TeeTurtle<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Turtle, B: Turtle> TeeTurtle<A, B> {
+    pub fn new(first: A, second: B) -> TeeTurtle<A, B> {
+        TeeTurtle { first, second }
+    }
+
+    pub fn first(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    pub fn second(&mut self) -> &mut B {
+        &mut self.second
+    }
+
+    /// Returns both turtles.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Turtle, B: Turtle> Turtle for TeeTurtle<A, B> {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        let distance = distance.into();
+        self.first.forward(distance);
+        self.second.forward(distance);
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        let distance = distance.into();
+        self.first.move_forward(distance);
+        self.second.move_forward(distance);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        let angle = angle.into();
+        self.first.rotate(angle);
+        self.second.rotate(angle);
+    }
+
+    fn position(&self) -> Position {
+        self.first.position()
+    }
+
+    fn set_x(&mut self, x: f32) {
+        self.first.set_x(x);
+        self.second.set_x(x);
+    }
+
+    fn set_y(&mut self, y: f32) {
+        self.first.set_y(y);
+        self.second.set_y(y);
+    }
+
+    fn heading(&self) -> Degree {
+        self.first.heading()
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        let angle = angle.into();
+        self.first.set_heading(angle);
+        self.second.set_heading(angle);
+    }
+
+    fn towards(&self, pos: Position) -> Degree {
+        self.first.towards(pos)
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.first.is_pen_down()
+    }
+
+    fn pen_down(&mut self) {
+        self.first.pen_down();
+        self.second.pen_down();
+    }
+
+    fn pen_up(&mut self) {
+        self.first.pen_up();
+        self.second.pen_up();
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.first.goto(pos);
+        self.second.goto(pos);
+    }
+
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        let (radius, extent) = (radius.into(), extent.into());
+        self.first.arc(radius, extent);
+        self.second.arc(radius, extent);
+    }
+
+    fn push(&mut self) {
+        self.first.push();
+        self.second.push();
+    }
+
+    /// Pops the state of both turtles. Returns `false` if either had no
+    /// saved state.
+    fn pop(&mut self) -> bool {
+        let first = self.first.pop();
+        let second = self.second.pop();
+        first && second
+    }
+}
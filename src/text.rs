@@ -0,0 +1,43 @@
+/// Horizontal alignment of text relative to the anchor position.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum TextAlign {
+    /// The text starts at the anchor.
+    #[default]
+    Start,
+    /// The text is centered on the anchor.
+    Middle,
+    /// The text ends at the anchor.
+    End,
+}
+
+impl TextAlign {
+    /// Returns the fraction of the text width left of the anchor.
+    pub(crate) fn fraction(self) -> f32 {
+        match self {
+            TextAlign::Start => 0.0,
+            TextAlign::Middle => 0.5,
+            TextAlign::End => 1.0,
+        }
+    }
+}
+
+/// Rough width of a character in units of the font size, used where the
+/// real extent of text isn't known (bounds, diagrams.net geometry).
+pub(crate) const CHAR_WIDTH: f32 = 0.6;
+
+/// Escapes a string for use in a PostScript string literal. Non-ASCII
+/// characters are written as octal escapes of their UTF-8 bytes.
+pub(crate) fn ps_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        match b {
+            b'(' | b')' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(b as char);
+            }
+            0x20..=0x7e => escaped.push(b as char),
+            _ => escaped.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,170 @@
+//! Logging the commands of a turtle program, for debugging.
+
+use crate::{Degree, Distance, Position, Turtle};
+use std::fmt;
+use std::io::{self, Write};
+
+/// A turtle which writes a line per command, like `forward 100` or
+/// `right 90`, before passing the command on to another turtle. Lines are
+/// indented by the number of pushed states, which follows the recursion of
+/// branching drawings.
+///
+/// Composite commands like `polygon` are logged as the commands they consist
+/// of. Write errors don't interrupt drawing; the first one is kept, see
+/// `error`.
+pub struct TraceTurtle<W: Write, T: Turtle> {
+    writer: W,
+    turtle: T,
+    depth: usize,
+    error: Option<io::Error>,
+}
+
+impl<W: Write, T: Turtle> TraceTurtle<W, T> {
+    pub fn new(writer: W, turtle: T) -> TraceTurtle<W, T> {
+        TraceTurtle {
+            writer,
+            turtle,
+            depth: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the turtle the commands are passed to.
+    pub fn turtle(&mut self) -> &mut T {
+        &mut self.turtle
+    }
+
+    /// Returns the first error writing the log, if any.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the writer and the turtle.
+    pub fn into_inner(self) -> (W, T) {
+        (self.writer, self.turtle)
+    }
+
+    fn log(&mut self, command: fmt::Arguments) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = writeln!(
+            self.writer,
+            "{:indent$}{}",
+            "",
+            command,
+            indent = 2 * self.depth
+        );
+        if let Err(err) = result {
+            self.error = Some(err);
+        }
+    }
+}
+
+impl<W: Write, T: Turtle> Turtle for TraceTurtle<W, T> {
+    fn forward<D: Into<Distance>>(&mut self, distance: D) {
+        let distance = distance.into();
+        self.log(format_args!("forward {}", distance.0));
+        self.turtle.forward(distance);
+    }
+
+    fn backward<D: Into<Distance>>(&mut self, distance: D) {
+        let distance = distance.into();
+        self.log(format_args!("backward {}", distance.0));
+        self.turtle.backward(distance);
+    }
+
+    fn move_forward<D: Into<Distance>>(&mut self, distance: D) {
+        let distance = distance.into();
+        self.log(format_args!("move_forward {}", distance.0));
+        self.turtle.move_forward(distance);
+    }
+
+    fn rotate<A: Into<Degree>>(&mut self, angle: A) {
+        let angle = angle.into();
+        self.log(format_args!("rotate {}", angle.0));
+        self.turtle.rotate(angle);
+    }
+
+    fn right<A: Into<Degree>>(&mut self, angle: A) {
+        let angle = angle.into();
+        self.log(format_args!("right {}", angle.0));
+        self.turtle.right(angle);
+    }
+
+    fn left<A: Into<Degree>>(&mut self, angle: A) {
+        let angle = angle.into();
+        self.log(format_args!("left {}", angle.0));
+        self.turtle.left(angle);
+    }
+
+    fn position(&self) -> Position {
+        self.turtle.position()
+    }
+
+    fn set_x(&mut self, x: f32) {
+        self.log(format_args!("set_x {}", x));
+        self.turtle.set_x(x);
+    }
+
+    fn set_y(&mut self, y: f32) {
+        self.log(format_args!("set_y {}", y));
+        self.turtle.set_y(y);
+    }
+
+    fn heading(&self) -> Degree {
+        self.turtle.heading()
+    }
+
+    fn set_heading<A: Into<Degree>>(&mut self, angle: A) {
+        let angle = angle.into();
+        self.log(format_args!("set_heading {}", angle.0));
+        self.turtle.set_heading(angle);
+    }
+
+    fn towards(&self, pos: Position) -> Degree {
+        self.turtle.towards(pos)
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.turtle.is_pen_down()
+    }
+
+    fn pen_down(&mut self) {
+        self.log(format_args!("pen_down"));
+        self.turtle.pen_down();
+    }
+
+    fn pen_up(&mut self) {
+        self.log(format_args!("pen_up"));
+        self.turtle.pen_up();
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.log(format_args!("goto {} {}", pos.0, pos.1));
+        self.turtle.goto(pos);
+    }
+
+    fn arc<R: Into<Distance>, E: Into<Degree>>(&mut self, radius: R, extent: E) {
+        let (radius, extent) = (radius.into(), extent.into());
+        self.log(format_args!("arc {} {}", radius.0, extent.0));
+        self.turtle.arc(radius, extent);
+    }
+
+    fn push(&mut self) {
+        self.log(format_args!("push"));
+        self.turtle.push();
+        self.depth += 1;
+    }
+
+    fn pop(&mut self) -> bool {
+        let popped = self.turtle.pop();
+        if popped {
+            self.depth = self.depth.saturating_sub(1);
+            self.log(format_args!("pop"));
+        } else {
+            self.log(format_args!("pop (stack empty)"));
+        }
+        popped
+    }
+}
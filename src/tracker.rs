@@ -0,0 +1,101 @@
+//! Keeping track of a turtle which doesn't record its paths.
+//!
+//! Turtles that hand each line to a writer, device or rendering context as
+//! they move have no `Canvas` to keep their position, heading and pen in.
+//! A `Tracker` keeps them, together with the stack of `push` and `pop`, so
+//! that such turtles only have to produce their output.
+
+use crate::{Degree, Position};
+
+/// The state of a tracked turtle. `P` holds the pen settings of the turtle
+/// using the tracker, which are saved and restored along with the rest.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct State<P> {
+    pub(crate) pos: Position,
+    /// Counterclockwise, 0 pointing upwards.
+    pub(crate) heading: Degree,
+    pub(crate) pendown: bool,
+    pub(crate) pen: P,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Tracker<P> {
+    states: Vec<State<P>>,
+}
+
+impl<P: Copy> Tracker<P> {
+    /// Starts at the origin, heading upwards with the pen down.
+    pub(crate) fn new(pen: P) -> Tracker<P> {
+        Tracker {
+            states: vec![State {
+                pos: Position::origin(),
+                heading: Degree(0.0),
+                pendown: true,
+                pen,
+            }],
+        }
+    }
+
+    pub(crate) fn current(&self) -> &State<P> {
+        self.states.last().unwrap()
+    }
+
+    pub(crate) fn state(&mut self) -> &mut State<P> {
+        self.states.last_mut().unwrap()
+    }
+
+    /// Moves forward by `distance`. Returns the state before the move if
+    /// the pen is down, so that a line is to be drawn from there to the
+    /// new position.
+    pub(crate) fn forward(&mut self, distance: f32) -> Option<State<P>> {
+        let before = *self.current();
+        self.move_forward(distance);
+        if before.pendown {
+            Some(before)
+        } else {
+            None
+        }
+    }
+
+    /// Moves forward by `distance` without drawing.
+    pub(crate) fn move_forward(&mut self, distance: f32) {
+        let heading = self.current().heading;
+        self.advance(heading, distance);
+    }
+
+    /// Moves by `distance` in direction `heading`, without drawing.
+    pub(crate) fn advance(&mut self, heading: Degree, distance: f32) {
+        let (sin, cos) = heading.sin_cos();
+        let state = self.state();
+        state.pos = Position(state.pos.0 - sin * distance, state.pos.1 + cos * distance);
+    }
+
+    pub(crate) fn rotate(&mut self, angle: Degree) {
+        let state = self.state();
+        state.heading = Degree(state.heading.0 + angle.0).normalized();
+    }
+
+    pub(crate) fn set_heading(&mut self, angle: Degree) {
+        self.state().heading = angle.normalized();
+    }
+
+    /// Jumps to `pos` without drawing, like a `Canvas` does by default.
+    pub(crate) fn goto(&mut self, pos: Position) {
+        self.state().pos = pos;
+    }
+
+    pub(crate) fn push(&mut self) {
+        let state = *self.current();
+        self.states.push(state);
+    }
+
+    /// Restores the state saved by the last `push`, if any.
+    pub(crate) fn pop(&mut self) -> bool {
+        if self.states.len() > 1 {
+            self.states.pop();
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Turtle graphics in three dimensions.
+//!
+//! A 3D turtle is oriented by a frame of three unit vectors: its heading,
+//! the direction to its left and the direction above it. It turns with
+//! `yaw` around its up vector, `pitch` around its left vector and `roll`
+//! around its heading, like the turtle of "The Algorithmic Beauty of
+//! Plants". The initial frame matches the 2D turtle: heading along +y, left
+//! along -x and up along +z, so that a turtle which only yaws draws in the
+//! xy plane like a `Canvas`.
+
+use crate::{Canvas, Degree, Distance, Position, Turtle};
+use std::io::{self, Write};
+
+/// A point in space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Position3D(pub f32, pub f32, pub f32);
+
+impl Position3D {
+    pub fn new(x: f32, y: f32, z: f32) -> Position3D {
+        Position3D(x, y, z)
+    }
+
+    pub fn origin() -> Position3D {
+        Position3D(0.0, 0.0, 0.0)
+    }
+}
+
+/// The orientation of a 3D turtle, as orthonormal vectors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Frame3D {
+    pub heading: [f32; 3],
+    pub left: [f32; 3],
+    pub up: [f32; 3],
+}
+
+impl Frame3D {
+    /// The initial orientation, heading along +y with up along +z.
+    pub fn initial() -> Frame3D {
+        Frame3D {
+            heading: [0.0, 1.0, 0.0],
+            left: [-1.0, 0.0, 0.0],
+            up: [0.0, 0.0, 1.0],
+        }
+    }
+
+    /// Turns `a` towards `b` by `angle`, keeping both orthogonal.
+    fn rotate(a: &mut [f32; 3], b: &mut [f32; 3], angle: Degree) {
+        let (sin, cos) = angle.sin_cos();
+        let (old_a, old_b) = (*a, *b);
+        for i in 0..3 {
+            a[i] = old_a[i] * cos + old_b[i] * sin;
+            b[i] = old_b[i] * cos - old_a[i] * sin;
+        }
+    }
+
+    /// Restores unit length and orthogonality lost to rounding.
+    fn orthonormalize(&mut self) {
+        self.heading = normalize(self.heading);
+        self.left = normalize(sub(
+            self.left,
+            scale(self.heading, dot(self.left, self.heading)),
+        ));
+        self.up = cross(self.heading, self.left);
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], f: f32) -> [f32; 3] {
+    [a[0] * f, a[1] * f, a[2] * f]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let len = dot(a, a).sqrt();
+    if len > 0.0 {
+        scale(a, 1.0 / len)
+    } else {
+        a
+    }
+}
+
+pub trait Turtle3D {
+    /// Move turtle forward along its heading by `distance`.
+    fn forward<T: Into<Distance>>(&mut self, distance: T);
+
+    /// Move turtle backward by `distance`.
+    fn backward<T: Into<Distance>>(&mut self, distance: T) {
+        self.forward(-distance.into())
+    }
+
+    /// Move turtle forward by `distance` *without* drawing.
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T);
+
+    /// Turn around the up vector, to the left for positive `angle`.
+    fn yaw<T: Into<Degree>>(&mut self, angle: T);
+
+    /// Turn around the left vector, nose up for positive `angle`.
+    fn pitch<T: Into<Degree>>(&mut self, angle: T);
+
+    /// Turn around the heading, rolling to the right for positive `angle`.
+    fn roll<T: Into<Degree>>(&mut self, angle: T);
+
+    /// Turn turtle left by `angle` degree, see `yaw`.
+    fn left<T: Into<Degree>>(&mut self, angle: T) {
+        self.yaw(angle);
+    }
+
+    /// Turn turtle right by `angle` degree, see `yaw`.
+    fn right<T: Into<Degree>>(&mut self, angle: T) {
+        self.yaw(-angle.into());
+    }
+
+    /// Turn by 180 degree around the up vector.
+    fn turn_around(&mut self) {
+        self.yaw(180.0);
+    }
+
+    /// Returns the current position of the turtle.
+    fn position(&self) -> Position3D;
+
+    /// Returns the current orientation of the turtle.
+    fn orientation(&self) -> Frame3D;
+
+    /// Returns `true` if pen is down.
+    fn is_pen_down(&self) -> bool;
+
+    /// Put the pen down.
+    fn pen_down(&mut self);
+
+    /// Put the pen up.
+    fn pen_up(&mut self);
+
+    /// Push current turtle state on stack.
+    fn push(&mut self);
+
+    /// Restore previously saved turtle state. Returns `false`, leaving the
+    /// state unchanged, if there is no saved state.
+    fn pop(&mut self) -> bool;
+}
+
+/// How a `Camera` maps depth.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Parallel projection, keeping sizes regardless of depth.
+    Orthographic,
+    /// Central projection onto a plane `focal_length` in front of the eye.
+    /// Lines behind the eye are clipped.
+    Perspective { focal_length: f32 },
+}
+
+/// A camera projecting 3D drawings onto a `Canvas`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    eye: Position3D,
+    /// Unit vectors to the right, up, and forward in the view.
+    right: [f32; 3],
+    up: [f32; 3],
+    forward: [f32; 3],
+    projection: Projection,
+}
+
+impl Default for Camera {
+    /// An orthographic camera looking down the z axis, which shows the xy
+    /// plane like a `Canvas`.
+    fn default() -> Camera {
+        Camera::looking_at(
+            Position3D(0.0, 0.0, 1.0),
+            Position3D::origin(),
+            [0.0, 1.0, 0.0],
+        )
+    }
+}
+
+impl Camera {
+    /// Creates an orthographic camera at `eye` looking at `target`, with
+    /// `up` pointing upwards in the view. Returns the default camera if
+    /// `eye` and `target` coincide or `up` is parallel to the view.
+    pub fn looking_at(eye: Position3D, target: Position3D, up: [f32; 3]) -> Camera {
+        let forward = normalize([target.0 - eye.0, target.1 - eye.1, target.2 - eye.2]);
+        let right = normalize(cross(forward, up));
+        if dot(right, right) == 0.0 || dot(forward, forward) == 0.0 {
+            return Camera::default();
+        }
+        Camera {
+            eye,
+            right,
+            up: cross(right, forward),
+            forward,
+            projection: Projection::Orthographic,
+        }
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Camera {
+        self.projection = projection;
+        self
+    }
+
+    /// Returns `pos` in view coordinates: right, up and depth.
+    fn view(&self, pos: Position3D) -> [f32; 3] {
+        let d = [pos.0 - self.eye.0, pos.1 - self.eye.1, pos.2 - self.eye.2];
+        [dot(d, self.right), dot(d, self.up), dot(d, self.forward)]
+    }
+
+    /// Projects a polyline, splitting it where it passes behind the eye.
+    fn project(&self, path: &[Position3D]) -> Vec<Vec<Position>> {
+        let focal_length = match self.projection {
+            Projection::Orthographic => {
+                let points = path.iter().map(|&pos| self.view(pos));
+                return vec![points.map(|v| Position(v[0], v[1])).collect()];
+            }
+            Projection::Perspective { focal_length } => focal_length,
+        };
+        // Depth of the near plane.
+        let near = focal_length.abs().max(1e-3) * 1e-3;
+        let flat = |v: [f32; 3]| Position(v[0] * focal_length / v[2], v[1] * focal_length / v[2]);
+        let clip = |a: [f32; 3], b: [f32; 3]| {
+            let t = (near - a[2]) / (b[2] - a[2]);
+            [a[0] + t * (b[0] - a[0]), a[1] + t * (b[1] - a[1]), near]
+        };
+
+        let mut pieces = Vec::new();
+        let mut piece = Vec::new();
+        let views: Vec<[f32; 3]> = path.iter().map(|&pos| self.view(pos)).collect();
+        for (i, &v) in views.iter().enumerate() {
+            let prev = if i > 0 { Some(views[i - 1]) } else { None };
+            match prev {
+                Some(p) if p[2] >= near && v[2] < near => {
+                    piece.push(flat(clip(p, v)));
+                    pieces.push(std::mem::take(&mut piece));
+                }
+                Some(p) if p[2] < near && v[2] >= near => {
+                    piece.push(flat(clip(p, v)));
+                    piece.push(flat(v));
+                }
+                _ if v[2] >= near => piece.push(flat(v)),
+                _ => {}
+            }
+        }
+        pieces.push(piece);
+        pieces.retain(|piece| piece.len() > 1);
+        pieces
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct State3D {
+    pos: Position3D,
+    frame: Frame3D,
+    pendown: bool,
+}
+
+/// A 3D turtle which records the polylines it draws.
+#[derive(Clone, Debug)]
+pub struct Canvas3D {
+    states: Vec<State3D>,
+    paths: Vec<Vec<Position3D>>,
+}
+
+impl Default for Canvas3D {
+    fn default() -> Canvas3D {
+        Canvas3D::new()
+    }
+}
+
+impl Canvas3D {
+    pub fn new() -> Canvas3D {
+        Canvas3D {
+            states: vec![State3D {
+                pos: Position3D::origin(),
+                frame: Frame3D::initial(),
+                pendown: true,
+            }],
+            paths: vec![vec![Position3D::origin()]],
+        }
+    }
+
+    /// Returns the drawn polylines. Paths with a single point, where the
+    /// turtle only moved, are left out.
+    pub fn paths(&self) -> Vec<&[Position3D]> {
+        self.paths
+            .iter()
+            .filter(|path| path.len() > 1)
+            .map(|path| path.as_slice())
+            .collect()
+    }
+
+    /// Saves the drawn polylines as Wavefront OBJ, one `l` element per
+    /// polyline.
+    pub fn save_obj<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        let paths = self.paths();
+        for path in paths.iter() {
+            for pos in path.iter() {
+                writeln!(wr, "v {} {} {}", pos.0, pos.1, pos.2)?;
+            }
+        }
+        // OBJ indices start at 1.
+        let mut first = 1;
+        for path in paths.iter() {
+            let indices: Vec<String> = (first..first + path.len()).map(|i| i.to_string()).collect();
+            writeln!(wr, "l {}", indices.join(" "))?;
+            first += path.len();
+        }
+        Ok(())
+    }
+
+    /// Saves the drawn polylines as ASCII PLY, with an edge per line
+    /// segment.
+    pub fn save_ply<W: Write>(&self, wr: &mut W) -> io::Result<()> {
+        let paths = self.paths();
+        let vertices: usize = paths.iter().map(|path| path.len()).sum();
+        let edges: usize = paths.iter().map(|path| path.len() - 1).sum();
+        writeln!(wr, "ply")?;
+        writeln!(wr, "format ascii 1.0")?;
+        writeln!(wr, "element vertex {}", vertices)?;
+        writeln!(wr, "property float x")?;
+        writeln!(wr, "property float y")?;
+        writeln!(wr, "property float z")?;
+        writeln!(wr, "element edge {}", edges)?;
+        writeln!(wr, "property int vertex1")?;
+        writeln!(wr, "property int vertex2")?;
+        writeln!(wr, "end_header")?;
+        for path in paths.iter() {
+            for pos in path.iter() {
+                writeln!(wr, "{} {} {}", pos.0, pos.1, pos.2)?;
+            }
+        }
+        let mut first = 0;
+        for path in paths.iter() {
+            for i in first..first + path.len() - 1 {
+                writeln!(wr, "{} {}", i, i + 1)?;
+            }
+            first += path.len();
+        }
+        Ok(())
+    }
+
+    /// Draws the polylines as seen by `camera` onto `canvas`, with its
+    /// current pen, so that they can be exported like any 2D drawing.
+    pub fn project(&self, camera: &Camera, canvas: &mut Canvas) {
+        let pendown = canvas.is_pen_down();
+        for path in self.paths() {
+            for points in camera.project(path) {
+                canvas.pen_up();
+                canvas.goto(points[0]);
+                canvas.pen_down();
+                for &pos in points[1..].iter() {
+                    canvas.travel_to(pos);
+                }
+            }
+        }
+        if !pendown {
+            canvas.pen_up();
+        }
+    }
+
+    fn current_state(&self) -> &State3D {
+        self.states.last().unwrap()
+    }
+
+    fn current_state_mut(&mut self) -> &mut State3D {
+        self.states.last_mut().unwrap()
+    }
+
+    /// Starts a new path at `pos`, unless the current one is still empty.
+    fn move_to(&mut self, pos: Position3D) {
+        let path = self.paths.last_mut().unwrap();
+        if path.len() <= 1 {
+            path.clear();
+            path.push(pos);
+        } else {
+            self.paths.push(vec![pos]);
+        }
+    }
+
+    fn target(&self, distance: Distance) -> Position3D {
+        let state = self.current_state();
+        let h = scale(state.frame.heading, distance.0);
+        Position3D(state.pos.0 + h[0], state.pos.1 + h[1], state.pos.2 + h[2])
+    }
+}
+
+impl Turtle3D for Canvas3D {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        let dst = self.target(distance.into());
+        self.current_state_mut().pos = dst;
+        if self.current_state().pendown {
+            self.paths.last_mut().unwrap().push(dst);
+        } else {
+            self.move_to(dst);
+        }
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        let dst = self.target(distance.into());
+        self.current_state_mut().pos = dst;
+        self.move_to(dst);
+    }
+
+    fn yaw<T: Into<Degree>>(&mut self, angle: T) {
+        let frame = &mut self.current_state_mut().frame;
+        Frame3D::rotate(&mut frame.heading, &mut frame.left, angle.into());
+        frame.orthonormalize();
+    }
+
+    fn pitch<T: Into<Degree>>(&mut self, angle: T) {
+        let frame = &mut self.current_state_mut().frame;
+        Frame3D::rotate(&mut frame.heading, &mut frame.up, angle.into());
+        frame.orthonormalize();
+    }
+
+    fn roll<T: Into<Degree>>(&mut self, angle: T) {
+        let frame = &mut self.current_state_mut().frame;
+        Frame3D::rotate(&mut frame.left, &mut frame.up, angle.into());
+        frame.orthonormalize();
+    }
+
+    fn position(&self) -> Position3D {
+        self.current_state().pos
+    }
+
+    fn orientation(&self) -> Frame3D {
+        self.current_state().frame
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.current_state().pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.current_state_mut().pendown = true;
+    }
+
+    fn pen_up(&mut self) {
+        self.current_state_mut().pendown = false;
+    }
+
+    fn push(&mut self) {
+        let state = *self.current_state();
+        self.states.push(state);
+    }
+
+    fn pop(&mut self) -> bool {
+        if self.states.len() < 2 {
+            return false;
+        }
+        self.states.pop();
+        let pos = self.current_state().pos;
+        self.move_to(pos);
+        true
+    }
+}
@@ -0,0 +1,231 @@
+//! Undo journal of `Canvas`.
+//!
+//! Before every drawing command, a checkpoint is recorded. Most commands
+//! only append to the drawing, so a checkpoint usually stores the turtle
+//! state and how far the paths extended, which keeps recording cheap even
+//! for long paths. Commands which rewrite earlier paths record a full copy.
+
+use crate::{Canvas, Color, Path, Position, TextAlign, TurtleState};
+use std::mem;
+
+/// State of the last path when the checkpoint was taken.
+#[derive(Clone)]
+enum LastPath {
+    /// A path with less than two points, which may still be replaced.
+    Whole(Box<Path>),
+    /// A path which can only grow, with the lengths it had.
+    Grown {
+        points: usize,
+        /// The last point, which may be moved by merging collinear lines.
+        end: Position,
+        pressures: usize,
+        arcs: usize,
+        closed: bool,
+        id: Option<String>,
+        class: Option<String>,
+    },
+}
+
+/// State before a command which only appends to the drawing or changes the
+/// last path.
+#[derive(Clone)]
+pub(crate) struct Partial {
+    active_turtle: usize,
+    states: Vec<TurtleState>,
+    paths_len: usize,
+    last: Option<LastPath>,
+    /// Number of fill vertices, if filling.
+    filling: Option<usize>,
+    layers_len: usize,
+    layer_order: Vec<usize>,
+    current_layer: usize,
+    background: Option<Color>,
+    shapes_len: usize,
+    current_shape: usize,
+    font_size: f32,
+    text_align: TextAlign,
+    rotate_text: bool,
+    symmetry: usize,
+    symmetry_mirror: bool,
+}
+
+#[derive(Clone)]
+pub(crate) enum Checkpoint {
+    Partial(Box<Partial>),
+    Full(Box<Canvas>),
+}
+
+impl Canvas {
+    /// Sets how many drawing commands can be undone. A depth of 0 disables
+    /// the undo journal. The default is 100.
+    pub fn set_undo_depth(&mut self, depth: usize) {
+        self.undo_depth = depth;
+        while self.undo_journal.len() > depth {
+            self.undo_journal.pop_front();
+        }
+    }
+
+    /// Reverts the last drawing command, including changes of the turtle
+    /// state. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_journal.pop_back() {
+            Some(checkpoint) => {
+                self.restore(checkpoint);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reverts the last `n` drawing commands. Returns the number of commands
+    /// actually reverted.
+    pub fn undo_n(&mut self, n: usize) -> usize {
+        (0..n).take_while(|_| self.undo()).count()
+    }
+
+    fn record(&mut self, checkpoint: Checkpoint) {
+        if self.undo_depth == 0 {
+            return;
+        }
+        if self.undo_journal.len() >= self.undo_depth {
+            self.undo_journal.pop_front();
+        }
+        self.undo_journal.push_back(checkpoint);
+    }
+
+    /// Records a checkpoint before a command which only appends to the
+    /// drawing or changes the last path.
+    pub(crate) fn checkpoint(&mut self) {
+        if self.undo_depth == 0 {
+            return;
+        }
+        let last = self.paths.last().map(|path| {
+            if path.points.len() < 2 {
+                LastPath::Whole(Box::new(path.clone()))
+            } else {
+                LastPath::Grown {
+                    points: path.points.len(),
+                    end: *path.points.last().unwrap(),
+                    pressures: path.pressures.len(),
+                    arcs: path.arcs.len(),
+                    closed: path.closed,
+                    id: path.id.clone(),
+                    class: path.class.clone(),
+                }
+            }
+        });
+        let checkpoint = Checkpoint::Partial(Box::new(Partial {
+            active_turtle: self.active_turtle,
+            states: self.states.clone(),
+            paths_len: self.paths.len(),
+            last,
+            filling: self.filling.as_ref().map(|f| f.vertices.len()),
+            layers_len: self.layers.len(),
+            layer_order: self.layer_order.clone(),
+            current_layer: self.current_layer,
+            background: self.background,
+            shapes_len: self.shapes.len(),
+            current_shape: self.current_shape,
+            font_size: self.font_size,
+            text_align: self.text_align,
+            rotate_text: self.rotate_text,
+            symmetry: self.symmetry,
+            symmetry_mirror: self.symmetry_mirror,
+        }));
+        self.record(checkpoint);
+    }
+
+    /// Records a full copy before a command which rewrites earlier paths.
+    pub(crate) fn checkpoint_full(&mut self) {
+        self.revision += 1;
+        if self.undo_depth == 0 {
+            return;
+        }
+        let journal = mem::take(&mut self.undo_journal);
+        let copy = self.clone();
+        self.undo_journal = journal;
+        self.record(Checkpoint::Full(Box::new(copy)));
+    }
+
+    fn restore(&mut self, checkpoint: Checkpoint) {
+        let revision = self.revision + 1;
+        match checkpoint {
+            Checkpoint::Full(canvas) => {
+                let (journal, depth) = (mem::take(&mut self.undo_journal), self.undo_depth);
+                *self = *canvas;
+                self.undo_journal = journal;
+                self.undo_depth = depth;
+            }
+            Checkpoint::Partial(partial) => {
+                let Partial {
+                    active_turtle,
+                    states,
+                    paths_len,
+                    last,
+                    filling,
+                    layers_len,
+                    layer_order,
+                    current_layer,
+                    background,
+                    shapes_len,
+                    current_shape,
+                    font_size,
+                    text_align,
+                    rotate_text,
+                    symmetry,
+                    symmetry_mirror,
+                } = *partial;
+                // Other turtles are unchanged since the checkpoint.
+                if active_turtle != self.active_turtle {
+                    self.swap_turtle(active_turtle);
+                }
+                self.states = states;
+                self.paths.truncate(paths_len);
+                match last {
+                    Some(LastPath::Whole(path)) => {
+                        self.paths.pop();
+                        self.paths.push(*path);
+                    }
+                    Some(LastPath::Grown {
+                        points,
+                        end,
+                        pressures,
+                        arcs,
+                        closed,
+                        id,
+                        class,
+                    }) => {
+                        let path = self.paths.last_mut().unwrap();
+                        path.points.truncate(points);
+                        *path.points.last_mut().unwrap() = end;
+                        path.pressures.truncate(pressures);
+                        path.arcs.truncate(arcs);
+                        path.closed = closed;
+                        path.id = id;
+                        path.class = class;
+                    }
+                    None => {}
+                }
+                self.filling = match (filling, self.filling.take()) {
+                    (Some(len), Some(mut f)) => {
+                        f.vertices.truncate(len);
+                        Some(f)
+                    }
+                    _ => None,
+                };
+                self.layers.truncate(layers_len);
+                self.layer_order = layer_order;
+                self.current_layer = current_layer;
+                self.background = background;
+                self.shapes.truncate(shapes_len);
+                self.current_shape = current_shape;
+                self.font_size = font_size;
+                self.text_align = text_align;
+                self.rotate_text = rotate_text;
+                self.symmetry = symmetry;
+                self.symmetry_mirror = symmetry_mirror;
+            }
+        }
+        self.revision = revision;
+    }
+}
@@ -0,0 +1,123 @@
+//! Showing a drawing in an interactive widget, with zoom and pan.
+//!
+//! A `Viewport` maps canvas coordinates to the pixels of a widget, and
+//! `Viewport::strokes` returns the drawing as polylines in widget pixels,
+//! ready for a GUI painter such as egui's `Painter::line` or
+//! `Shape::convex_polygon`. Pointer drags and scroll events map to `pan`
+//! and `zoom_at`.
+
+use crate::{Canvas, Color, Position};
+
+/// A polyline or filled polygon in widget pixels, with y pointing down.
+#[derive(Clone, Debug)]
+pub struct Stroke {
+    pub points: Vec<(f32, f32)>,
+    pub color: Color,
+    /// Line width in pixels.
+    pub width: f32,
+    /// Set if the last point connects back to the first.
+    pub closed: bool,
+    /// Fill color of filled polygons, which aren't stroked.
+    pub fill: Option<Color>,
+}
+
+/// The visible part of a canvas in a widget of `width` x `height` pixels.
+#[derive(Copy, Clone, Debug)]
+pub struct Viewport {
+    width: f32,
+    height: f32,
+    /// Canvas position at the center of the widget.
+    center: Position,
+    /// Pixels per unit.
+    zoom: f32,
+}
+
+impl Viewport {
+    /// Creates a viewport showing the whole drawing of `canvas`, with the
+    /// margins of the SVG export.
+    pub fn fit(canvas: &Canvas, width: f32, height: f32) -> Viewport {
+        let (top_left, view_width, view_height, _) = canvas.svg_frame();
+        Viewport {
+            width,
+            height,
+            center: Position(
+                top_left.0 + view_width / 2.0,
+                -(top_left.1 + view_height / 2.0),
+            ),
+            zoom: (width / view_width).min(height / view_height),
+        }
+    }
+
+    /// Changes the widget size, keeping the center and zoom.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Returns the pixels per unit.
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Moves the drawing by `dx`, `dy` pixels, as by a pointer drag.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.center = Position(
+            self.center.0 - dx / self.zoom,
+            self.center.1 + dy / self.zoom,
+        );
+    }
+
+    /// Zooms in by `factor` (out if below 1), keeping the point under the
+    /// pixel `x`, `y` in place, as for a scroll event.
+    pub fn zoom_at(&mut self, x: f32, y: f32, factor: f32) {
+        if factor.is_nan() || factor <= 0.0 || factor.is_infinite() {
+            return;
+        }
+        let anchor = self.to_canvas(x, y);
+        self.zoom *= factor;
+        let moved = self.to_screen(anchor);
+        self.pan(x - moved.0, y - moved.1);
+    }
+
+    /// Returns the widget pixel showing `pos`.
+    pub fn to_screen(&self, pos: Position) -> (f32, f32) {
+        (
+            self.width / 2.0 + (pos.0 - self.center.0) * self.zoom,
+            self.height / 2.0 - (pos.1 - self.center.1) * self.zoom,
+        )
+    }
+
+    /// Returns the canvas position shown at the widget pixel `x`, `y`.
+    pub fn to_canvas(&self, x: f32, y: f32) -> Position {
+        Position(
+            self.center.0 + (x - self.width / 2.0) / self.zoom,
+            self.center.1 - (y - self.height / 2.0) / self.zoom,
+        )
+    }
+
+    /// Returns the paths of `canvas` in widget pixels, bottom layer first.
+    /// Labels aren't included, and strokes are drawn solid and with their
+    /// full pen width regardless of pen pressure.
+    pub fn strokes(&self, canvas: &Canvas) -> Vec<Stroke> {
+        if let Some(canvas) = canvas.export_copy() {
+            return self.strokes(&canvas);
+        }
+        let (_, _, _, stroke_width) = canvas.svg_frame();
+        let mut strokes = Vec::new();
+        for (_, paths) in canvas.paths_by_layer() {
+            for path in paths {
+                if path.label.is_some() || path.points.is_empty() {
+                    continue;
+                }
+                strokes.push(Stroke {
+                    points: path.points.iter().map(|&p| self.to_screen(p)).collect(),
+                    color: path.pen.color(),
+                    width: path.pen.width().unwrap_or(stroke_width) * self.zoom,
+                    closed: path.closed,
+                    fill: path.fill,
+                });
+            }
+        }
+        strokes
+    }
+}
@@ -0,0 +1,263 @@
+//! Delaunay triangulation and Voronoi diagrams of a set of points, drawn as
+//! scaffold onto a `Canvas`.
+//!
+//! The triangulation uses the Bowyer-Watson algorithm, which is simple and
+//! fast enough for the few thousand points typical of stippling.
+
+use crate::{Canvas, Position};
+
+/// Circumcircle of a triangle as center and squared radius.
+fn circumcircle(a: Position, b: Position, c: Position) -> Option<(Position, f32)> {
+    let d = 2.0 * (a.0 * (b.1 - c.1) + b.0 * (c.1 - a.1) + c.0 * (a.1 - b.1));
+    if d.abs() < f32::EPSILON {
+        return None;
+    }
+    let (a2, b2, c2) = (
+        a.0 * a.0 + a.1 * a.1,
+        b.0 * b.0 + b.1 * b.1,
+        c.0 * c.0 + c.1 * c.1,
+    );
+    let center = Position(
+        (a2 * (b.1 - c.1) + b2 * (c.1 - a.1) + c2 * (a.1 - b.1)) / d,
+        (a2 * (c.0 - b.0) + b2 * (a.0 - c.0) + c2 * (b.0 - a.0)) / d,
+    );
+    let (dx, dy) = (a.0 - center.0, a.1 - center.1);
+    Some((center, dx * dx + dy * dy))
+}
+
+/// Clips the segment `a`-`b` to the rectangle `min`-`max` (Liang-Barsky).
+fn clip_segment(
+    a: Position,
+    b: Position,
+    min: Position,
+    max: Position,
+) -> Option<(Position, Position)> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for &(p, q) in [
+        (-dx, a.0 - min.0),
+        (dx, max.0 - a.0),
+        (-dy, a.1 - min.1),
+        (dy, max.1 - a.1),
+    ]
+    .iter()
+    {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    Some((
+        Position(a.0 + t0 * dx, a.1 + t0 * dy),
+        Position(a.0 + t1 * dx, a.1 + t1 * dy),
+    ))
+}
+
+#[derive(Copy, Clone)]
+struct Triangle {
+    vertices: [usize; 3],
+    center: Position,
+    radius_sq: f32,
+}
+
+/// A Delaunay triangulation.
+pub struct Triangulation {
+    points: Vec<Position>,
+    triangles: Vec<[usize; 3]>,
+}
+
+impl Triangulation {
+    /// Triangulates `points`. Duplicate and collinear points are tolerated
+    /// but don't contribute triangles.
+    pub fn new(points: &[Position]) -> Triangulation {
+        let mut all: Vec<Position> = points.to_vec();
+        let n = all.len();
+        if n < 3 {
+            return Triangulation {
+                points: all,
+                triangles: Vec::new(),
+            };
+        }
+
+        // Super triangle containing all points.
+        let (min, max) = points
+            .iter()
+            .skip(1)
+            .fold((points[0], points[0]), |mm, p| p.min_max(&mm));
+        let size = (max.0 - min.0).max(max.1 - min.1).max(1.0);
+        let mid = Position((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0);
+        all.push(Position(mid.0 - 20.0 * size, mid.1 - size));
+        all.push(Position(mid.0, mid.1 + 20.0 * size));
+        all.push(Position(mid.0 + 20.0 * size, mid.1 - size));
+
+        let make = |all: &[Position], v: [usize; 3]| {
+            circumcircle(all[v[0]], all[v[1]], all[v[2]]).map(|(center, radius_sq)| Triangle {
+                vertices: v,
+                center,
+                radius_sq,
+            })
+        };
+
+        let mut triangles = vec![make(&all, [n, n + 1, n + 2]).unwrap()];
+
+        for i in 0..n {
+            let p = all[i];
+            let (bad, good): (Vec<Triangle>, Vec<Triangle>) =
+                triangles.into_iter().partition(|t| {
+                    let (dx, dy) = (p.0 - t.center.0, p.1 - t.center.1);
+                    dx * dx + dy * dy < t.radius_sq
+                });
+            triangles = good;
+
+            // The boundary of the cavity consists of the edges which belong
+            // to exactly one bad triangle.
+            let mut edges: Vec<(usize, usize)> = Vec::new();
+            for t in bad.iter() {
+                for k in 0..3 {
+                    let (a, b) = (t.vertices[k], t.vertices[(k + 1) % 3]);
+                    match edges
+                        .iter()
+                        .position(|&(c, d)| (c == b && d == a) || (c == a && d == b))
+                    {
+                        Some(shared) => {
+                            edges.swap_remove(shared);
+                        }
+                        None => edges.push((a, b)),
+                    }
+                }
+            }
+            for (a, b) in edges {
+                if let Some(t) = make(&all, [a, b, i]) {
+                    triangles.push(t);
+                }
+            }
+        }
+
+        let triangles = triangles
+            .into_iter()
+            .filter(|t| t.vertices.iter().all(|&v| v < n))
+            .map(|t| t.vertices)
+            .collect();
+        all.truncate(n);
+        Triangulation {
+            points: all,
+            triangles,
+        }
+    }
+
+    pub fn points(&self) -> &[Position] {
+        &self.points
+    }
+
+    /// Returns the triangles as indices into `points()`.
+    pub fn triangles(&self) -> &[[usize; 3]] {
+        &self.triangles
+    }
+
+    /// Returns every edge of the triangulation once, as pair of indices into
+    /// `points()` with the smaller index first.
+    pub fn edges(&self) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = self
+            .triangles
+            .iter()
+            .flat_map(|t| (0..3).map(move |k| (t[k].min(t[(k + 1) % 3]), t[k].max(t[(k + 1) % 3]))))
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    /// Returns the edges of the Voronoi diagram clipped to the rectangle
+    /// `min`-`max`. Unbounded cells are closed by the rectangle.
+    pub fn voronoi_edges(&self, min: Position, max: Position) -> Vec<(Position, Position)> {
+        let centers: Vec<Position> = self
+            .triangles
+            .iter()
+            .map(|t| {
+                circumcircle(self.points[t[0]], self.points[t[1]], self.points[t[2]])
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        // Map every edge to the triangles sharing it.
+        let mut adjacent: Vec<((usize, usize), usize)> = Vec::new();
+        for (i, t) in self.triangles.iter().enumerate() {
+            for k in 0..3 {
+                let (a, b) = (t[k], t[(k + 1) % 3]);
+                adjacent.push(((a.min(b), a.max(b)), i));
+            }
+        }
+        adjacent.sort();
+
+        // Long enough to leave the clip rectangle.
+        let far = 2.0 * ((max.0 - min.0).abs() + (max.1 - min.1).abs()) + 1.0;
+        let mut result = Vec::new();
+        let mut i = 0;
+        while i < adjacent.len() {
+            let (edge, t) = adjacent[i];
+            let segment = if i + 1 < adjacent.len() && adjacent[i + 1].0 == edge {
+                i += 2;
+                (centers[t], centers[adjacent[i - 1].1])
+            } else {
+                i += 1;
+                // Hull edge: ray from the circumcenter, pointing away from
+                // the opposite vertex of the triangle.
+                let (a, b) = (self.points[edge.0], self.points[edge.1]);
+                let tri = self.triangles[t];
+                let opposite = self.points[tri
+                    .iter()
+                    .cloned()
+                    .find(|&v| v != edge.0 && v != edge.1)
+                    .unwrap()];
+                let mut normal = (b.1 - a.1, a.0 - b.0);
+                let mid = Position((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+                if normal.0 * (opposite.0 - mid.0) + normal.1 * (opposite.1 - mid.1) > 0.0 {
+                    normal = (-normal.0, -normal.1);
+                }
+                let len = (normal.0 * normal.0 + normal.1 * normal.1).sqrt();
+                let c = centers[t];
+                (
+                    c,
+                    Position(c.0 + normal.0 / len * far, c.1 + normal.1 / len * far),
+                )
+            };
+            if let Some(clipped) = clip_segment(segment.0, segment.1, min, max) {
+                result.push(clipped);
+            }
+        }
+        result
+    }
+}
+
+impl Canvas {
+    /// Draws the edges of the Delaunay triangulation of `points`.
+    pub fn draw_delaunay(&mut self, points: &[Position]) {
+        self.checkpoint();
+        let triangulation = Triangulation::new(points);
+        for (a, b) in triangulation.edges() {
+            self.add_polyline(vec![triangulation.points[a], triangulation.points[b]]);
+        }
+    }
+
+    /// Draws the edges of the Voronoi diagram of `points`, clipped to the
+    /// rectangle `min`-`max`.
+    pub fn draw_voronoi(&mut self, points: &[Position], min: Position, max: Position) {
+        self.checkpoint();
+        let triangulation = Triangulation::new(points);
+        for (a, b) in triangulation.voronoi_edges(min, max) {
+            self.add_polyline(vec![a, b]);
+        }
+    }
+}
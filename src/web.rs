@@ -0,0 +1,145 @@
+//! Drawing live onto an HTML canvas.
+//!
+//! A `WebTurtle` issues the path calls of `CanvasRenderingContext2D` for
+//! every line it draws, so the drawing code used for SVG exports also runs
+//! in the browser. To stay free of dependencies, it draws through the
+//! `RenderingContext` trait, which mirrors the few calls needed. With
+//! web-sys, implement it for a wrapper around
+//! `web_sys::CanvasRenderingContext2d` by forwarding each method to the
+//! method of the same name (`set_stroke_style_str` for the stroke style).
+
+use crate::tracker::Tracker;
+use crate::{Color, Degree, Distance, Position, Turtle};
+
+/// The calls of `CanvasRenderingContext2D` a `WebTurtle` makes.
+/// Coordinates are in canvas pixels, with y pointing down.
+pub trait RenderingContext {
+    fn begin_path(&mut self);
+    fn move_to(&mut self, x: f64, y: f64);
+    fn line_to(&mut self, x: f64, y: f64);
+    fn stroke(&mut self);
+    /// Sets the stroke color, given like `#rrggbb`.
+    fn set_stroke_style(&mut self, style: &str);
+    fn set_line_width(&mut self, width: f64);
+}
+
+/// A turtle drawing each line immediately with a `RenderingContext`.
+///
+/// Turtle coordinates have y pointing up, like on a `Canvas`; the turtle
+/// starts in the middle of the HTML canvas, and `scale` pixels correspond
+/// to one unit.
+pub struct WebTurtle<C: RenderingContext> {
+    context: C,
+    /// Pen color and width.
+    tracker: Tracker<(Color, f32)>,
+    /// Pixel position of the origin.
+    origin: (f64, f64),
+    scale: f64,
+}
+
+impl<C: RenderingContext> WebTurtle<C> {
+    /// Creates a turtle for an HTML canvas of `width` x `height` pixels,
+    /// drawing black lines one pixel wide.
+    pub fn new(context: C, width: u32, height: u32) -> WebTurtle<C> {
+        WebTurtle {
+            context,
+            tracker: Tracker::new((Color::BLACK, 1.0)),
+            origin: (width as f64 / 2.0, height as f64 / 2.0),
+            scale: 1.0,
+        }
+    }
+
+    /// Sets the pixels per unit.
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = scale as f64;
+    }
+
+    pub fn set_pen_color(&mut self, color: Color) {
+        self.tracker.state().pen.0 = color;
+    }
+
+    /// Sets the width of lines, in units.
+    pub fn set_pen_width(&mut self, width: f32) {
+        self.tracker.state().pen.1 = width;
+    }
+
+    /// Returns the rendering context.
+    pub fn context(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    pub fn into_inner(self) -> C {
+        self.context
+    }
+
+    fn to_pixel(&self, pos: Position) -> (f64, f64) {
+        (
+            self.origin.0 + pos.0 as f64 * self.scale,
+            self.origin.1 - pos.1 as f64 * self.scale,
+        )
+    }
+
+    /// Draws a line from `from` to `to`.
+    fn line(&mut self, from: Position, to: Position, (color, width): (Color, f32)) {
+        let (from, to) = (self.to_pixel(from), self.to_pixel(to));
+        self.context.begin_path();
+        self.context.set_stroke_style(&color.to_string());
+        self.context.set_line_width(width as f64 * self.scale);
+        self.context.move_to(from.0, from.1);
+        self.context.line_to(to.0, to.1);
+        self.context.stroke();
+    }
+}
+
+impl<C: RenderingContext> Turtle for WebTurtle<C> {
+    fn forward<T: Into<Distance>>(&mut self, distance: T) {
+        if let Some(from) = self.tracker.forward(distance.into().0) {
+            let to = self.tracker.current().pos;
+            self.line(from.pos, to, from.pen);
+        }
+    }
+
+    fn move_forward<T: Into<Distance>>(&mut self, distance: T) {
+        self.tracker.move_forward(distance.into().0);
+    }
+
+    fn rotate<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.rotate(angle.into());
+    }
+
+    fn position(&self) -> Position {
+        self.tracker.current().pos
+    }
+
+    fn heading(&self) -> Degree {
+        self.tracker.current().heading
+    }
+
+    fn set_heading<T: Into<Degree>>(&mut self, angle: T) {
+        self.tracker.set_heading(angle.into());
+    }
+
+    fn is_pen_down(&self) -> bool {
+        self.tracker.current().pendown
+    }
+
+    fn pen_down(&mut self) {
+        self.tracker.state().pendown = true;
+    }
+
+    fn pen_up(&mut self) {
+        self.tracker.state().pendown = false;
+    }
+
+    fn goto(&mut self, pos: Position) {
+        self.tracker.goto(pos);
+    }
+
+    fn push(&mut self) {
+        self.tracker.push();
+    }
+
+    fn pop(&mut self) -> bool {
+        self.tracker.pop()
+    }
+}
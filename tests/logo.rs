@@ -0,0 +1,136 @@
+use std::thread;
+use std::time::Duration;
+use turtle_graphics::logo::{Limits, Program};
+use turtle_graphics::Canvas;
+
+fn run(source: &str) -> Result<(), turtle_graphics::logo::LogoError> {
+    Program::parse(source)?.run(&mut Canvas::new())
+}
+
+/// Runs `source` on a thread with a small stack, so that deep nesting
+/// overflows it unless limited.
+fn run_small_stack(source: String) -> Result<(), turtle_graphics::logo::LogoError> {
+    thread::Builder::new()
+        .stack_size(2 << 20)
+        .spawn(move || run(&source))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+#[test]
+fn deeply_nested_source_is_rejected() {
+    let sources = vec![
+        format!("FD {}1{}", "(".repeat(5000), ")".repeat(5000)),
+        format!("FD {}1", "- ".repeat(5000)),
+        format!("FD 1{}", " + 1".repeat(5000)),
+        format!("{}FD 1{}", "REPEAT 1 [".repeat(5000), "]".repeat(5000)),
+    ];
+    for source in sources {
+        let error = run_small_stack(source).unwrap_err();
+        assert!(error.is_limit_exceeded(), "{}", error);
+    }
+}
+
+#[test]
+fn moderate_nesting_is_fine() {
+    run(&format!("FD {}1{}", "(".repeat(50), ")".repeat(50))).unwrap();
+    run(&format!(
+        "{}FD 1{}",
+        "REPEAT 1 [".repeat(50),
+        "]".repeat(50)
+    ))
+    .unwrap();
+    run("TO F :n IF :n > 0 [F :n - 1] END F 50").unwrap();
+}
+
+#[test]
+fn runaway_recursion_is_stopped() {
+    let sources = vec![
+        "TO F :n F :n + 1 END F 0".to_string(),
+        "TO F :n OUTPUT 1 + F :n END FD F 0".to_string(),
+        "TO F :n REPEAT 1 [IF 1 [F :n]] END F 0".to_string(),
+    ];
+    for source in sources {
+        let error = run_small_stack(source).unwrap_err();
+        assert!(error.is_limit_exceeded(), "{}", error);
+    }
+}
+
+#[test]
+fn step_limit_stops_endless_loops() {
+    let limits = Limits {
+        max_steps: Some(1000),
+        ..Limits::default()
+    };
+    let program = Program::parse("TO F F END F").unwrap();
+    let error = program
+        .run_limited(&mut Canvas::new(), &[], &limits)
+        .unwrap_err();
+    assert!(error.is_limit_exceeded());
+    let program = Program::parse("REPEAT 1000000000 [RT 1]").unwrap();
+    let error = program
+        .run_limited(&mut Canvas::new(), &[], &limits)
+        .unwrap_err();
+    assert!(error.is_limit_exceeded());
+}
+
+#[test]
+fn time_limit_stops_long_loops() {
+    let limits = Limits {
+        max_time: Some(Duration::from_millis(10)),
+        ..Limits::default()
+    };
+    let program = Program::parse("REPEAT 1000000000 [RT 1]").unwrap();
+    let error = program
+        .run_limited(&mut Canvas::new(), &[], &limits)
+        .unwrap_err();
+    assert!(error.is_limit_exceeded());
+}
+
+#[test]
+fn absurd_repeat_counts_are_rejected() {
+    for count in &["1 / 0", "0 - 1 / 0", "0 / 0", "10000000000"] {
+        let error = run(&format!("REPEAT {} [FD 1]", count)).unwrap_err();
+        assert!(!error.is_limit_exceeded());
+    }
+    run("REPEAT 0 - 5 [FD 1]").unwrap();
+}
+
+#[test]
+fn segment_limit_keeps_the_drawing_so_far() {
+    let limits = Limits {
+        max_segments: Some(3),
+        ..Limits::default()
+    };
+    let program = Program::parse("REPEAT 10 [FD 10 RT 90]").unwrap();
+    let mut canvas = Canvas::new();
+    let error = program.run_limited(&mut canvas, &[], &limits).unwrap_err();
+    assert!(error.is_limit_exceeded());
+    assert_eq!(canvas.stats().drawn_length, 30.0);
+}
+
+#[test]
+fn pen_up_moves_dont_count_as_segments() {
+    let limits = Limits {
+        max_segments: Some(1),
+        ..Limits::default()
+    };
+    let program = Program::parse("PU REPEAT 10 [FD 10] PD FD 10").unwrap();
+    program
+        .run_limited(&mut Canvas::new(), &[], &limits)
+        .unwrap();
+}
+
+#[test]
+fn errors() {
+    let error = run("REPEAT 1 / 0 [FD 1]").unwrap_err();
+    assert!(!error.is_limit_exceeded());
+    assert!(run("FOO 1").is_err());
+    assert!(run("FD :x").is_err());
+    assert!(run("TO FD :n END").is_err());
+    assert!(Program::parse("REPEAT 4 [FD 1")
+        .unwrap_err()
+        .is_incomplete());
+    assert!(Program::parse("TO F FD 1").unwrap_err().is_incomplete());
+}
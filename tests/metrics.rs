@@ -0,0 +1,37 @@
+use turtle_graphics::{Canvas, Position, Turtle};
+
+/// Draws the same line on layers "a" and "b", in that order.
+fn overlapping_layers() -> Canvas {
+    let mut canvas = Canvas::new();
+    canvas.layer("a");
+    canvas.forward(10.0);
+    canvas.layer("b");
+    canvas.home();
+    canvas.forward(10.0);
+    canvas
+}
+
+fn hit_path(canvas: &Canvas) -> usize {
+    canvas
+        .hit_test(Position::new(0.0, 5.0), 1.0)
+        .map(|(path, _)| path)
+        .unwrap()
+}
+
+#[test]
+fn hit_test_returns_the_path_on_top() {
+    let mut canvas = overlapping_layers();
+    let top = hit_path(&canvas);
+    canvas.set_layer_order(&["b", "a"]);
+    let bottom = hit_path(&canvas);
+    assert_ne!(top, bottom);
+    canvas.set_layer_order(&["a", "b"]);
+    assert_eq!(hit_path(&canvas), top);
+}
+
+#[test]
+fn hit_test_respects_tolerance() {
+    let canvas = overlapping_layers();
+    assert!(canvas.hit_test(Position::new(2.0, 5.0), 1.0).is_none());
+    assert!(canvas.hit_test(Position::new(2.0, 5.0), 2.0).is_some());
+}
@@ -0,0 +1,123 @@
+use turtle_graphics::rng::{RandomSource, SplitMix64};
+use turtle_graphics::{Canvas, Position, Turtle};
+
+fn svg(canvas: &Canvas) -> String {
+    let mut out = Vec::new();
+    canvas.save_svg(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Draws a line from `from` to `to`, and finishes it, as the path being
+/// drawn is never rearranged.
+fn line(canvas: &mut Canvas, from: (f32, f32), to: (f32, f32)) {
+    canvas.pen_up();
+    canvas.goto(Position::new(from.0, from.1));
+    canvas.pen_down();
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    canvas.set_heading(dy.atan2(dx).to_degrees() - 90.0);
+    canvas.forward((dx * dx + dy * dy).sqrt());
+    canvas.pen_up();
+    canvas.goto(Position::origin());
+}
+
+/// Many short lines on a coarse grid, so that distances often tie.
+fn scribble(seed: u64) -> Canvas {
+    let mut rng = SplitMix64::new(seed);
+    let mut canvas = Canvas::new();
+    for i in 0..500 {
+        if i % 100 == 0 {
+            canvas.layer(if rng.next_f32() < 0.5 { "a" } else { "b" });
+        }
+        let mut point = || (rng.range(0.0, 20.0).floor(), rng.range(0.0, 20.0).floor());
+        let (from, to) = (point(), point());
+        if from != to {
+            line(&mut canvas, from, to);
+        }
+        if i % 7 == 0 {
+            canvas.goto(Position::new(from.0, from.1));
+            canvas.pen_down();
+            canvas.forward(3.0);
+            canvas.right(90.0);
+            canvas.forward(3.0);
+            canvas.close_path();
+            canvas.pen_up();
+        }
+    }
+    canvas
+}
+
+fn path_at(canvas: &Canvas, x: f32, y: f32) -> usize {
+    canvas.hit_test(Position::new(x, y), 0.1).unwrap().0
+}
+
+#[test]
+fn optimize_travel_is_deterministic() {
+    for seed in 0..5 {
+        let (mut a, mut b) = (scribble(seed), scribble(seed));
+        let saved = a.optimize_travel();
+        assert_eq!(saved, b.optimize_travel());
+        assert!(saved >= 0.0);
+        assert_eq!(svg(&a), svg(&b));
+    }
+}
+
+#[test]
+fn join_paths_is_deterministic() {
+    for seed in 0..5 {
+        let (mut a, mut b) = (scribble(seed), scribble(seed));
+        let joins = a.join_paths(0.5);
+        assert_eq!(joins, b.join_paths(0.5));
+        assert!(joins > 0);
+        assert_eq!(svg(&a), svg(&b));
+    }
+}
+
+#[test]
+fn optimize_travel_takes_the_first_of_equally_near_lines() {
+    let mut canvas = Canvas::new();
+    line(&mut canvas, (10.0, 0.0), (20.0, 0.0));
+    line(&mut canvas, (-10.0, 0.0), (-20.0, 0.0));
+    canvas.optimize_travel();
+    assert_eq!(path_at(&canvas, 15.0, 0.0), 0);
+    assert_eq!(path_at(&canvas, -15.0, 0.0), 1);
+
+    let mut canvas = Canvas::new();
+    line(&mut canvas, (-10.0, 0.0), (-20.0, 0.0));
+    line(&mut canvas, (10.0, 0.0), (20.0, 0.0));
+    canvas.optimize_travel();
+    assert_eq!(path_at(&canvas, -15.0, 0.0), 0);
+    assert_eq!(path_at(&canvas, 15.0, 0.0), 1);
+}
+
+#[test]
+fn optimize_travel_visits_the_nearest_line_next() {
+    let mut canvas = Canvas::new();
+    line(&mut canvas, (100.0, 0.0), (110.0, 0.0));
+    line(&mut canvas, (50.0, 0.0), (60.0, 0.0));
+    line(&mut canvas, (0.0, 1.0), (0.0, 10.0));
+    canvas.optimize_travel();
+    assert_eq!(path_at(&canvas, 0.0, 5.0), 0);
+    assert_eq!(path_at(&canvas, 55.0, 0.0), 1);
+    assert_eq!(path_at(&canvas, 105.0, 0.0), 2);
+}
+
+#[test]
+fn join_paths_appends_the_first_continuation() {
+    let mut canvas = Canvas::new();
+    line(&mut canvas, (0.0, 0.0), (10.0, 0.0));
+    line(&mut canvas, (20.0, 0.0), (10.0, 0.0));
+    line(&mut canvas, (10.0, 0.0), (10.0, 10.0));
+    assert_eq!(canvas.join_paths(0.1), 1);
+    assert_eq!(path_at(&canvas, 5.0, 0.0), 0);
+    assert_eq!(path_at(&canvas, 15.0, 0.0), 0);
+    assert_eq!(path_at(&canvas, 10.0, 5.0), 1);
+}
+
+#[test]
+fn join_paths_respects_epsilon() {
+    let mut canvas = Canvas::new();
+    line(&mut canvas, (0.0, 0.0), (10.0, 0.0));
+    line(&mut canvas, (10.5, 0.0), (20.0, 0.0));
+    assert_eq!(canvas.join_paths(0.1), 0);
+    assert_eq!(canvas.join_paths(1.0), 1);
+}
@@ -0,0 +1,59 @@
+use turtle_graphics::{Canvas, Turtle};
+
+fn segments(canvas: &Canvas) -> usize {
+    canvas.stats().segments
+}
+
+#[test]
+fn vertices_are_spaced_evenly() {
+    let mut canvas = Canvas::new();
+    canvas.forward(100.0);
+    canvas.resample(10.0);
+    assert_eq!(segments(&canvas), 10);
+    assert_eq!(canvas.stats().drawn_length, 100.0);
+}
+
+#[test]
+fn last_gap_may_be_shorter() {
+    let mut canvas = Canvas::new();
+    canvas.forward(25.0);
+    canvas.resample(10.0);
+    assert_eq!(segments(&canvas), 3);
+}
+
+#[test]
+fn spacing_carries_over_corners() {
+    let mut canvas = Canvas::new();
+    canvas.forward(15.0);
+    canvas.right(90.0);
+    canvas.forward(15.0);
+    canvas.resample(10.0);
+    // Vertices at 10 and 20 along the line, plus both ends.
+    assert_eq!(segments(&canvas), 3);
+}
+
+#[test]
+fn tiny_spacing_on_long_lines_terminates() {
+    let mut canvas = Canvas::new();
+    canvas.forward(1000.0);
+    canvas.resample(0.001);
+    assert_eq!(segments(&canvas), 1_000_000);
+}
+
+#[test]
+fn absurd_vertex_counts_are_rejected() {
+    let mut canvas = Canvas::new();
+    canvas.forward(1000.0);
+    canvas.resample(1e-5);
+    assert_eq!(segments(&canvas), 1);
+}
+
+#[test]
+fn invalid_spacing_has_no_effect() {
+    let mut canvas = Canvas::new();
+    canvas.forward(100.0);
+    for &spacing in &[0.0, -1.0, f32::NAN, f32::INFINITY] {
+        canvas.resample(spacing);
+        assert_eq!(segments(&canvas), 1);
+    }
+}
@@ -0,0 +1,42 @@
+use turtle_graphics::{Canvas, Position, Turtle};
+
+fn samples() -> Vec<(Position, f32)> {
+    (0..=10)
+        .map(|i| (Position::new(i as f32 * 10.0, 50.0), 0.5 + i as f32 / 20.0))
+        .collect()
+}
+
+#[test]
+fn stroke_is_one_line() {
+    let mut canvas = Canvas::new();
+    canvas.pen_up();
+    canvas.record_stroke(samples());
+    let stats = canvas.stats();
+    assert_eq!(stats.paths, 1);
+    assert_eq!(stats.drawn_length, 100.0);
+    let pos = canvas.position();
+    assert_eq!((pos.x(), pos.y()), (100.0, 50.0));
+    assert!(!canvas.is_pen_down());
+    assert_eq!(canvas.pen_pressure(), 1.0);
+}
+
+#[test]
+fn stroke_is_undone_as_a_whole() {
+    let mut canvas = Canvas::new();
+    canvas.forward(10.0);
+    canvas.record_stroke(samples());
+    assert!(canvas.undo());
+    let stats = canvas.stats();
+    assert_eq!(stats.paths, 1);
+    assert_eq!(stats.drawn_length, 10.0);
+    let pos = canvas.position();
+    assert_eq!((pos.x(), pos.y()), (0.0, 10.0));
+}
+
+#[test]
+fn empty_stroke_does_nothing() {
+    let mut canvas = Canvas::new();
+    canvas.record_stroke(Vec::new());
+    assert_eq!(canvas.stats().paths, 0);
+    assert!(!canvas.undo());
+}
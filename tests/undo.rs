@@ -0,0 +1,138 @@
+use turtle_graphics::{Affine, Canvas, Color, Position, Rect, Turtle};
+
+fn svg(canvas: &Canvas) -> String {
+    let mut out = Vec::new();
+    canvas.save_svg(&mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// A drawing with lines on two layers, a filled region, a dot and a path
+/// still being drawn.
+fn drawing() -> Canvas {
+    let mut canvas = Canvas::new();
+    canvas.forward(10.0);
+    canvas.right(90.0);
+    canvas.forward(10.0);
+    canvas.layer("top");
+    canvas.set_pen_color(Color::rgb(200, 0, 0));
+    canvas.begin_fill();
+    for _ in 0..3 {
+        canvas.forward(5.0);
+        canvas.left(120.0);
+    }
+    canvas.end_fill();
+    canvas.pen_up();
+    canvas.forward(10.0);
+    canvas.pen_down();
+    canvas.dot(2.0);
+    canvas.layer("default");
+    canvas.forward(10.0);
+    canvas.left(45.0);
+    canvas.forward(10.0);
+    canvas
+}
+
+/// Checks that `command` is undone by a single `undo`, including the turtle
+/// state, by drawing on after undoing it.
+fn assert_undone<F: Fn(&mut Canvas)>(command: F) {
+    let mut expected = drawing();
+    let before = svg(&expected);
+    let mut canvas = drawing();
+    command(&mut canvas);
+    assert!(canvas.undo());
+    assert_eq!(svg(&canvas), before);
+
+    canvas.forward(7.0);
+    canvas.right(30.0);
+    canvas.forward(7.0);
+    expected.forward(7.0);
+    expected.right(30.0);
+    expected.forward(7.0);
+    assert_eq!(svg(&canvas), svg(&expected));
+}
+
+#[test]
+fn drawing_commands_are_undone() {
+    assert_undone(|canvas| canvas.forward(5.0));
+    assert_undone(|canvas| canvas.backward(5.0));
+    assert_undone(|canvas| canvas.right(33.0));
+    assert_undone(|canvas| canvas.goto(Position::new(-20.0, 3.0)));
+    assert_undone(|canvas| canvas.home());
+    assert_undone(|canvas| canvas.pen_up());
+    assert_undone(|canvas| canvas.set_pen_width(3.0));
+    assert_undone(|canvas| canvas.set_pen_color(Color::rgb(0, 0, 200)));
+    assert_undone(|canvas| canvas.set_pen_pressure(0.5));
+    assert_undone(|canvas| canvas.set_background(Color::BLACK));
+    assert_undone(|canvas| canvas.close_path());
+    assert_undone(|canvas| canvas.dot(3.0));
+    assert_undone(|canvas| canvas.stamp());
+    assert_undone(|canvas| canvas.write_text("undo"));
+    assert_undone(|canvas| canvas.layer("new"));
+    assert_undone(|canvas| canvas.set_layer_order(&["top", "default"]));
+    assert_undone(|canvas| canvas.begin_fill());
+    assert_undone(|canvas| canvas.set_symmetry(6));
+    assert_undone(|canvas| canvas.set_symmetry_mirror(true));
+    assert_undone(|canvas| canvas.push());
+    assert_undone(|canvas| {
+        canvas.record_stroke(vec![
+            (Position::new(0.0, 0.0), 0.2),
+            (Position::new(5.0, 5.0), 0.8),
+        ])
+    });
+}
+
+#[test]
+fn rewriting_commands_are_undone() {
+    assert_undone(|canvas| canvas.clear());
+    assert_undone(|canvas| canvas.reset());
+    assert_undone(|canvas| canvas.normalize(Rect::new(0.0, 0.0, 100.0, 100.0)));
+    assert_undone(|canvas| canvas.transform(&Affine::rotate(30.0)));
+    assert_undone(|canvas| {
+        canvas.optimize_travel();
+    });
+    assert_undone(|canvas| {
+        canvas.join_paths(0.1);
+    });
+    assert_undone(|canvas| canvas.resample(1.0));
+    assert_undone(|canvas| {
+        canvas.remove_duplicate_segments(0.1);
+    });
+    assert_undone(|canvas| {
+        canvas.recognize_arcs(0.1);
+    });
+}
+
+#[test]
+fn undo_n_and_depth() {
+    let mut canvas = drawing();
+    let before = svg(&canvas);
+    for _ in 0..5 {
+        canvas.forward(1.0);
+        canvas.left(10.0);
+    }
+    assert_eq!(canvas.undo_n(10), 10);
+    assert_eq!(svg(&canvas), before);
+
+    canvas.set_undo_depth(2);
+    assert!(canvas.undo());
+    assert!(canvas.undo());
+    assert!(!canvas.undo());
+
+    canvas.set_undo_depth(0);
+    canvas.forward(1.0);
+    assert!(!canvas.undo());
+}
+
+#[test]
+fn undo_restores_symmetry() {
+    let mut canvas = Canvas::new();
+    canvas.set_symmetry(4);
+    canvas.set_symmetry_mirror(true);
+    assert!(canvas.undo());
+    assert!(canvas.undo());
+    canvas.forward(10.0);
+
+    let mut expected = Canvas::new();
+    expected.forward(10.0);
+    assert_eq!(svg(&canvas), svg(&expected));
+}